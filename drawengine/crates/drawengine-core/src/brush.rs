@@ -1,12 +1,22 @@
+use std::f64::consts::FRAC_PI_2;
+
 use serde::{Deserialize, Serialize};
 
+use crate::dash::{DashPattern, LineCap, LineJoin};
+use crate::nib::NibSettings;
 use crate::point::Color;
+use crate::pressure::PressureCurve;
+use crate::stamp::StampSettings;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BrushType {
     Pen,
     Highlighter,
     Eraser,
+    /// Places a textured tip along the stroke; see `BrushConfig::stamp`.
+    Stamp,
+    /// Flat nib held at a fixed angle; see `BrushConfig::calligraphy`.
+    Calligraphy,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,8 +29,51 @@ pub struct BrushConfig {
     pub pressure_sensitivity: f64,
     pub velocity_sensitivity: f64,
     pub smoothing: f64,
+    /// Extra width when the stylus is tilted (pencil shading): at 1.0 a stylus lying
+    /// flat draws twice as wide. 0 ignores tilt.
+    #[serde(default)]
+    pub tilt_sensitivity: f64,
+    /// Shapes pressure before it drives width.
+    #[serde(default, skip_serializing_if = "PressureCurve::is_linear")]
+    pub pressure_curve: PressureCurve,
+    /// Opacity multiplier by pressure; `None` keeps the color's alpha at any pressure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opacity_curve: Option<PressureCurve>,
+    /// Opacity lost with speed: at 1.0 a stroke fades out entirely at 1000 units/s.
+    #[serde(default)]
+    pub velocity_opacity: f64,
+    /// Random per-sample lightness variation in `0.0..=1.0`.
+    #[serde(default)]
+    pub color_jitter: f64,
+    /// Color the stroke blends into over `gradient_length` canvas units.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gradient_color: Option<Color>,
+    #[serde(default)]
+    pub gradient_length: f64,
+    /// Distance in canvas units over which the stroke widens from a point at its start.
+    #[serde(default)]
+    pub taper_in: f64,
+    /// Distance in canvas units over which the stroke narrows to a point at its end.
+    #[serde(default)]
+    pub taper_out: f64,
+    /// Tip and spacing for `BrushType::Stamp`; `None` uses `StampSettings::default()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<StampSettings>,
+    /// Nib for `BrushType::Calligraphy`; `None` uses `NibSettings::default()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nib: Option<NibSettings>,
+    /// Dashes and gaps along pen and highlighter strokes; `None` draws them solid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dash: Option<DashPattern>,
+    #[serde(default)]
+    pub line_cap: LineCap,
+    #[serde(default)]
+    pub line_join: LineJoin,
 }
 
+/// Width factor at the very tip of a taper, so the end stays visible.
+const MIN_TAPER_FACTOR: f64 = 0.1;
+
 impl BrushConfig {
     pub fn pen(color: Color, width: f64) -> Self {
         Self {
@@ -32,6 +85,20 @@ impl BrushConfig {
             pressure_sensitivity: 0.8,
             velocity_sensitivity: 0.3,
             smoothing: 0.5,
+            tilt_sensitivity: 0.0,
+            pressure_curve: PressureCurve::linear(),
+            opacity_curve: None,
+            velocity_opacity: 0.0,
+            color_jitter: 0.0,
+            gradient_color: None,
+            gradient_length: 0.0,
+            taper_in: 0.0,
+            taper_out: 0.0,
+            stamp: None,
+            nib: None,
+            dash: None,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Round,
         }
     }
 
@@ -47,6 +114,20 @@ impl BrushConfig {
             pressure_sensitivity: 0.1,
             velocity_sensitivity: 0.05,
             smoothing: 0.2,
+            tilt_sensitivity: 0.0,
+            pressure_curve: PressureCurve::linear(),
+            opacity_curve: None,
+            velocity_opacity: 0.0,
+            color_jitter: 0.0,
+            gradient_color: None,
+            gradient_length: 0.0,
+            taper_in: 0.0,
+            taper_out: 0.0,
+            stamp: None,
+            nib: None,
+            dash: None,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Round,
         }
     }
 
@@ -60,18 +141,124 @@ impl BrushConfig {
             pressure_sensitivity: 0.0,
             velocity_sensitivity: 0.0,
             smoothing: 0.3,
+            tilt_sensitivity: 0.0,
+            pressure_curve: PressureCurve::linear(),
+            opacity_curve: None,
+            velocity_opacity: 0.0,
+            color_jitter: 0.0,
+            gradient_color: None,
+            gradient_length: 0.0,
+            taper_in: 0.0,
+            taper_out: 0.0,
+            stamp: None,
+            nib: None,
+            dash: None,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Round,
+        }
+    }
+
+    pub fn stamp(color: Color, width: f64, settings: StampSettings) -> Self {
+        Self {
+            brush_type: BrushType::Stamp,
+            stamp: Some(settings),
+            ..Self::pen(color, width)
         }
     }
 
-    pub fn compute_width(&self, pressure: f64, velocity: f64) -> f64 {
+    pub fn calligraphy(color: Color, width: f64, nib: NibSettings) -> Self {
+        Self {
+            brush_type: BrushType::Calligraphy,
+            nib: Some(nib),
+            ..Self::pen(color, width)
+        }
+    }
+
+    /// Stamp placement, if this brush draws with stamps.
+    pub fn stamp_settings(&self) -> Option<StampSettings> {
+        (self.brush_type == BrushType::Stamp).then(|| self.stamp.clone().unwrap_or_default())
+    }
+
+    /// Nib shape, if this brush draws as a calligraphy pen.
+    pub fn nib_settings(&self) -> Option<NibSettings> {
+        (self.brush_type == BrushType::Calligraphy).then(|| self.nib.clone().unwrap_or_default())
+    }
+
+    /// Dash pattern, if this brush draws dashed paths.
+    pub fn dash_pattern(&self) -> Option<&DashPattern> {
+        matches!(self.brush_type, BrushType::Pen | BrushType::Highlighter)
+            .then_some(self.dash.as_ref())
+            .flatten()
+    }
+
+    /// Width for a sample. `altitude` is the stylus altitude in radians, if known;
+    /// tilt widens the stroke beyond `max_width_factor` so shading stays possible.
+    pub fn compute_width(&self, pressure: f64, velocity: f64, altitude: Option<f64>) -> f64 {
+        let pressure = self.pressure_curve.apply(pressure);
         let pressure_factor = 1.0 + (pressure - 0.5) * self.pressure_sensitivity;
         let velocity_factor = 1.0 - (velocity.min(1000.0) / 1000.0) * self.velocity_sensitivity;
         let factor = (pressure_factor * velocity_factor)
             .clamp(self.min_width_factor, self.max_width_factor);
-        self.base_width * factor
+        let tilt = altitude
+            .filter(|a| a.is_finite())
+            .map_or(0.0, |a| 1.0 - a.clamp(0.0, FRAC_PI_2) / FRAC_PI_2);
+        self.base_width * factor * (1.0 + tilt * self.tilt_sensitivity)
+    }
+
+    /// Width multiplier for a sample `distance` canvas units from the stroke start.
+    pub fn taper_in_factor(&self, distance: f64) -> f64 {
+        taper_factor(distance, self.taper_in)
+    }
+
+    /// Width multiplier for a sample `distance` canvas units before the stroke end.
+    pub fn taper_out_factor(&self, distance: f64) -> f64 {
+        taper_factor(distance, self.taper_out)
+    }
+
+    /// Opacity multiplier in `0.0..=1.0` for a sample.
+    pub fn compute_opacity(&self, pressure: f64, velocity: f64) -> f64 {
+        let by_pressure = self.opacity_curve.as_ref().map_or(1.0, |c| c.apply(pressure));
+        let by_velocity = 1.0 - (velocity.min(1000.0) / 1000.0) * self.velocity_opacity;
+        (by_pressure * by_velocity).clamp(0.0, 1.0)
+    }
+
+    /// Whether strokes need per-segment colors rather than just `color`.
+    pub fn has_color_dynamics(&self) -> bool {
+        self.opacity_curve.is_some()
+            || self.velocity_opacity > 0.0
+            || self.color_jitter > 0.0
+            || self.gradient_color.is_some()
+    }
+
+    /// Color for a sample `distance` canvas units into the stroke. `noise` in
+    /// `-1.0..=1.0` drives the jitter: positive lightens, negative darkens.
+    pub fn compute_color(&self, pressure: f64, velocity: f64, distance: f64, noise: f32) -> Color {
+        let mut color = match self.gradient_color {
+            Some(end) if self.gradient_length > 0.0 => {
+                let t = (distance / self.gradient_length).clamp(0.0, 1.0);
+                self.color.lerp(&end, t as f32)
+            }
+            _ => self.color,
+        };
+        let jitter = noise.clamp(-1.0, 1.0) * self.color_jitter.clamp(0.0, 1.0) as f32;
+        let toward = if jitter > 0.0 { Color::white() } else { Color::black() };
+        let alpha = color.a;
+        color = color.lerp(&toward, jitter.abs());
+        color.a = alpha * self.compute_opacity(pressure, velocity) as f32;
+        color
     }
 }
 
+/// Ease-out from `MIN_TAPER_FACTOR` at the tip to 1 at `length`, like a nib
+/// pressing down.
+fn taper_factor(distance: f64, length: f64) -> f64 {
+    if length.is_nan() || length <= 0.0 || distance >= length {
+        return 1.0;
+    }
+    let t = (distance / length).max(0.0);
+    MIN_TAPER_FACTOR + (1.0 - MIN_TAPER_FACTOR) * t * (2.0 - t)
+}
+
 impl Default for BrushConfig {
     fn default() -> Self {
         Self::pen(Color::black(), 2.0)
@@ -81,6 +268,7 @@ impl Default for BrushConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::point::Point;
 
     #[test]
     fn test_pen_default() {
@@ -92,25 +280,85 @@ mod tests {
     #[test]
     fn test_compute_width_pressure() {
         let brush = BrushConfig::pen(Color::black(), 4.0);
-        let w_low = brush.compute_width(0.1, 0.0);
-        let w_high = brush.compute_width(0.9, 0.0);
+        let w_low = brush.compute_width(0.1, 0.0, None);
+        let w_high = brush.compute_width(0.9, 0.0, None);
         assert!(w_high > w_low);
     }
 
     #[test]
     fn test_compute_width_velocity() {
         let brush = BrushConfig::pen(Color::black(), 4.0);
-        let w_slow = brush.compute_width(0.5, 0.0);
-        let w_fast = brush.compute_width(0.5, 800.0);
+        let w_slow = brush.compute_width(0.5, 0.0, None);
+        let w_fast = brush.compute_width(0.5, 800.0, None);
         assert!(w_slow > w_fast);
     }
 
     #[test]
     fn test_width_clamped() {
         let brush = BrushConfig::pen(Color::black(), 4.0);
-        let w = brush.compute_width(0.0, 2000.0);
+        let w = brush.compute_width(0.0, 2000.0, None);
         assert!(w >= brush.base_width * brush.min_width_factor);
-        let w = brush.compute_width(1.0, 0.0);
+        let w = brush.compute_width(1.0, 0.0, None);
         assert!(w <= brush.base_width * brush.max_width_factor);
     }
+
+    #[test]
+    fn test_tilt_widens_pencil() {
+        let mut brush = BrushConfig::pen(Color::black(), 4.0);
+        brush.tilt_sensitivity = 1.0;
+        let upright = brush.compute_width(0.5, 0.0, Some(FRAC_PI_2));
+        let flat = brush.compute_width(0.5, 0.0, Some(0.0));
+        assert!((upright - brush.compute_width(0.5, 0.0, None)).abs() < 1e-9);
+        assert!((flat - upright * 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pressure_curve_shapes_width() {
+        let mut brush = BrushConfig::pen(Color::black(), 4.0);
+        let linear = brush.compute_width(0.3, 0.0, None);
+        // Soft curve: light pressure already counts as firm.
+        let soft = vec![Point::new(0.0, 0.0), Point::new(0.3, 0.7), Point::new(1.0, 1.0)];
+        brush.pressure_curve = PressureCurve::new(soft).unwrap();
+        assert!(brush.compute_width(0.3, 0.0, None) > linear);
+        assert_eq!(brush.compute_opacity(0.1, 0.0), 1.0);
+        brush.opacity_curve = Some(PressureCurve::linear());
+        assert!((brush.compute_opacity(0.25, 0.0) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_color_dynamics() {
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        assert!(!brush.has_color_dynamics());
+        assert_eq!(brush.compute_color(0.2, 500.0, 10.0, 0.9), Color::black());
+
+        // Pencil: lighter pressure, lighter stroke.
+        brush.opacity_curve = Some(PressureCurve::linear());
+        assert!(brush.has_color_dynamics());
+        assert!((brush.compute_color(0.2, 0.0, 0.0, 0.0).a - 0.2).abs() < 1e-6);
+        brush.velocity_opacity = 0.5;
+        assert!((brush.compute_color(1.0, 1000.0, 0.0, 0.0).a - 0.5).abs() < 1e-6);
+
+        brush.opacity_curve = None;
+        brush.velocity_opacity = 0.0;
+        brush.gradient_color = Some(Color::white());
+        brush.gradient_length = 100.0;
+        assert!((brush.compute_color(0.5, 0.0, 50.0, 0.0).r - 0.5).abs() < 1e-6);
+        assert_eq!(brush.compute_color(0.5, 0.0, 500.0, 0.0), Color::white());
+
+        brush.gradient_color = None;
+        brush.color_jitter = 0.5;
+        let lighter = brush.compute_color(0.5, 0.0, 0.0, 1.0);
+        assert!((lighter.r - 0.5).abs() < 1e-6 && lighter.a == 1.0);
+    }
+
+    #[test]
+    fn test_taper_factor() {
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        assert_eq!(brush.taper_in_factor(0.0), 1.0);
+        brush.taper_in = 20.0;
+        assert!((brush.taper_in_factor(0.0) - MIN_TAPER_FACTOR).abs() < 1e-9);
+        assert!(brush.taper_in_factor(5.0) < brush.taper_in_factor(10.0));
+        assert_eq!(brush.taper_in_factor(20.0), 1.0);
+        assert_eq!(brush.taper_out_factor(0.0), 1.0);
+    }
 }
@@ -1,47 +1,140 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
 use uuid::Uuid;
 
 use crate::brush::{BrushConfig, BrushType};
 use crate::eraser::find_strokes_to_erase;
+use crate::error::EngineError;
+use crate::fill::{enclosed_region, FillRegion};
 use crate::geometry::BezierSegment;
 use crate::history::{History, HistoryAction};
+use crate::image::{Image, ImageData};
+use crate::infinite::{ChunkCoord, InfiniteCanvas};
+use crate::journal::{parse_journal, Journal, JournalEntry, RecoveryReport};
 use crate::layer::LayerManager;
-use crate::point::{Color, Point, StrokePoint};
+use crate::nib::nib_outline;
+use crate::package::{
+    image_entry_name, DocumentMetadata, PackageReader, PackageWriter, DOCUMENT_ENTRY,
+    METADATA_ENTRY, THUMBNAIL_ENTRY,
+};
+use crate::page::{find_page, Page, PageLayout};
+use crate::point::{BoundingBox, Color, Point, StrokePoint};
+use crate::prediction::{predict_points, prediction_segments};
+use crate::preset::PresetLibrary;
+use crate::pressure::PressureCurve;
+use crate::recognition::{snap_stroke, HoldDetector, ShapeSnapping};
 use crate::render::{
-    generate_full_render_commands, generate_incremental_commands, RenderCommand,
+    generate_chunk_render_commands, generate_incremental_commands,
+    generate_incremental_dash_commands, generate_incremental_outline_commands,
+    generate_incremental_stamp_commands, generate_multi_page_render_commands,
+    generate_page_render_commands, RenderCommand,
 };
-use crate::serialization::DocumentData;
+use crate::serialization::{DocumentData, CURRENT_VERSION};
+use crate::shape::Shape;
+use crate::stamp::Stamp;
 use crate::stroke::{Stroke, StrokeBuilder};
+use crate::template::PageTemplate;
+use crate::text::{EstimatedTextMeasure, TextBox, TextMeasure};
 use crate::transform::Viewport;
+use crate::validation::{
+    repair_brush, repair_document, sanitize_color, validate_document, ValidationReport,
+};
 
 pub struct DrawEngine {
-    pub layer_manager: LayerManager,
+    pub pages: Vec<Page>,
+    pub current_page_index: usize,
+    pub page_layout: PageLayout,
     pub viewport: Viewport,
     pub history: History,
-    pub background_color: Color,
-    pub canvas_width: f64,
-    pub canvas_height: f64,
+    pub journal: Journal,
+    /// Set for infinite-canvas documents; strokes then go to chunks instead of pages.
+    pub infinite: Option<InfiniteCanvas>,
+    /// Imported image bytes by id. Kept across `load` and `recover`, since documents
+    /// only reference them; `recover` also restores journaled bytes. Replaced by
+    /// `load_package`.
+    images: HashMap<Uuid, ImageData>,
 
     current_brush: BrushConfig,
+    brush_presets: PresetLibrary,
+    /// Lazy-rope length in canvas units; 0 disables the stabilizer.
+    stabilizer: f64,
+    /// How far ahead (seconds) to predict the pen; 0 disables prediction.
+    prediction_lookahead: f64,
+    /// Maps this device's raw pressure onto `0.0..=1.0` before any brush sees it.
+    device_pressure_curve: PressureCurve,
+    shape_snapping: ShapeSnapping,
+    hold: HoldDetector,
     active_builder: Option<StrokeBuilder>,
+    active_target: Option<StrokeTarget>,
+    /// Shape being reshaped by `move_shape_handle`, as it was before the drag, and
+    /// the id of its page.
+    shape_edit: Option<(Uuid, Shape)>,
+    /// Wraps text boxes; estimates until the host supplies real font metrics.
+    text_measure: Arc<dyn TextMeasure>,
+    /// Screen size in points, used to cull chunks in infinite mode.
+    view_size: (f64, f64),
+}
+
+/// Where the active stroke will be stored.
+#[derive(Debug, Clone, Copy)]
+enum StrokeTarget {
+    /// A page and its canvas-space origin.
+    Page { page_id: Uuid, origin: Point },
+    /// An infinite-canvas chunk; its origin follows the anchor.
+    Chunk(ChunkCoord),
 }
 
 impl DrawEngine {
     pub fn new(width: f64, height: f64) -> Self {
         Self {
-            layer_manager: LayerManager::new(),
+            pages: vec![Page::new(width, height)],
+            current_page_index: 0,
+            page_layout: PageLayout::default(),
             viewport: Viewport::new(),
             history: History::default(),
-            background_color: Color::white(),
-            canvas_width: width,
-            canvas_height: height,
+            journal: Journal::new(),
+            infinite: None,
+            images: HashMap::new(),
             current_brush: BrushConfig::default(),
+            brush_presets: PresetLibrary::with_builtins(),
+            stabilizer: 0.0,
+            prediction_lookahead: 0.0,
+            device_pressure_curve: PressureCurve::linear(),
+            shape_snapping: ShapeSnapping::Off,
+            hold: HoldDetector::default(),
             active_builder: None,
+            active_target: None,
+            shape_edit: None,
+            text_measure: Arc::new(EstimatedTextMeasure),
+            view_size: (width, height),
+        }
+    }
+
+    /// Engine for an unbounded whiteboard. `view_width`×`view_height` is the screen size.
+    pub fn new_infinite(view_width: f64, view_height: f64) -> Self {
+        Self {
+            infinite: Some(InfiniteCanvas::new()),
+            ..Self::new(view_width, view_height)
         }
     }
 
+    pub fn is_infinite(&self) -> bool {
+        self.infinite.is_some()
+    }
+
+    /// Update the screen size after a resize so infinite-canvas culling covers the view.
+    pub fn set_view_size(&mut self, width: f64, height: f64) -> Vec<RenderCommand> {
+        self.view_size = (width, height);
+        self.full_render()
+    }
+
     // --- Brush ---
 
-    pub fn set_brush(&mut self, brush: BrushConfig) {
+    /// Out-of-range settings are repaired as `load_repaired` would, so strokes drawn with
+    /// the brush always save as a valid document.
+    pub fn set_brush(&mut self, mut brush: BrushConfig) {
+        repair_brush(&mut brush);
         self.current_brush = brush;
     }
 
@@ -49,14 +142,107 @@ impl DrawEngine {
         &self.current_brush
     }
 
+    pub fn brush_presets(&self) -> &PresetLibrary {
+        &self.brush_presets
+    }
+
+    pub fn brush_presets_mut(&mut self) -> &mut PresetLibrary {
+        &mut self.brush_presets
+    }
+
+    /// Switch to the brush stored in a preset.
+    pub fn apply_brush_preset(&mut self, preset_id: Uuid) -> Result<(), EngineError> {
+        let preset = self
+            .brush_presets
+            .get(preset_id)
+            .ok_or(EngineError::PresetNotFound { preset_id })?;
+        self.current_brush = preset.brush.clone();
+        Ok(())
+    }
+
+    /// Make strokes trail the pen by `rope_length` canvas units for steadier curves.
+    /// Takes effect from the next stroke; 0 turns the stabilizer off.
+    pub fn set_stabilizer(&mut self, rope_length: f64) {
+        self.stabilizer = rope_length;
+    }
+
+    pub fn stabilizer(&self) -> f64 {
+        self.stabilizer
+    }
+
+    /// Replace finished strokes that look like shapes with clean geometry.
+    /// Undo right after a snap brings back the stroke as drawn.
+    pub fn set_shape_snapping(&mut self, snapping: ShapeSnapping) {
+        self.shape_snapping = snapping;
+    }
+
+    pub fn shape_snapping(&self) -> ShapeSnapping {
+        self.shape_snapping
+    }
+
+    /// Extrapolate the pen `seconds` ahead (capped at `MAX_PREDICTION_LOOKAHEAD`) and
+    /// append a `DrawPrediction` command to every `add_point` result. 0 disables it.
+    pub fn set_prediction_lookahead(&mut self, seconds: f64) {
+        self.prediction_lookahead = if seconds.is_finite() { seconds.max(0.0) } else { 0.0 };
+    }
+
+    /// Normalize raw stylus pressure for the current device; applies to new input.
+    pub fn set_device_pressure_curve(&mut self, curve: PressureCurve) {
+        self.device_pressure_curve = curve;
+    }
+
+    pub fn device_pressure_curve(&self) -> &PressureCurve {
+        &self.device_pressure_curve
+    }
+
+    /// Fit the device curve to raw pressures from a few sample strokes.
+    /// Returns false, leaving the curve unchanged, if the samples are insufficient.
+    pub fn calibrate_device_pressure(&mut self, samples: &[f64]) -> bool {
+        match PressureCurve::calibrate(samples) {
+            Some(curve) => {
+                self.device_pressure_curve = curve;
+                true
+            }
+            None => false,
+        }
+    }
+
     // --- Drawing ---
 
     /// Begin a new stroke at the given screen-space point.
+    /// In continuous layout the stroke goes to the page under the point, which becomes current.
+    /// On an infinite canvas it goes to the chunk under the point.
     pub fn begin_stroke(&mut self, screen_x: f64, screen_y: f64, pressure: f64, timestamp: f64) -> Vec<RenderCommand> {
-        let canvas_point = self.viewport.screen_to_canvas(Point::new(screen_x, screen_y));
-        let point = StrokePoint::new(canvas_point.x, canvas_point.y, pressure, timestamp);
+        self.begin_stroke_input(StrokePoint::new(screen_x, screen_y, pressure, timestamp))
+    }
+
+    /// Like `begin_stroke`, but keeps the stylus tilt and pointer type of `screen_point`.
+    pub fn begin_stroke_input(&mut self, screen_point: StrokePoint) -> Vec<RenderCommand> {
+        self.finish_shape_edit();
+        let canvas_point = self.viewport.screen_to_canvas(screen_point.position);
+        let (target, local) = match &self.infinite {
+            Some(canvas) => {
+                let (chunk, local) = canvas.anchor.locate(canvas_point);
+                (StrokeTarget::Chunk(chunk), local)
+            }
+            None => {
+                let (page_index, origin) = self.page_at(canvas_point);
+                self.current_page_index = page_index;
+                let page_id = self.pages[page_index].id;
+                (StrokeTarget::Page { page_id, origin }, canvas_point - origin)
+            }
+        };
+        self.active_target = Some(target);
+        self.hold = HoldDetector::default();
+        self.hold.push(local, screen_point.timestamp);
+        let point = StrokePoint {
+            position: local,
+            pressure: self.device_pressure_curve.apply(screen_point.pressure),
+            ..screen_point
+        };
 
-        let mut builder = StrokeBuilder::new(self.current_brush.clone());
+        let mut builder =
+            StrokeBuilder::new(self.current_brush.clone()).with_stabilizer(self.stabilizer);
         let _segments = builder.add_point(point);
         self.active_builder = Some(builder);
 
@@ -66,73 +252,278 @@ impl DrawEngine {
 
     /// Add a point to the current stroke. Returns incremental render commands.
     pub fn add_point(&mut self, screen_x: f64, screen_y: f64, pressure: f64, timestamp: f64) -> Vec<RenderCommand> {
-        let canvas_point = self.viewport.screen_to_canvas(Point::new(screen_x, screen_y));
-        let point = StrokePoint::new(canvas_point.x, canvas_point.y, pressure, timestamp);
-
-        if let Some(builder) = &mut self.active_builder {
-            let new_segments: Vec<BezierSegment> = builder.add_point(point);
-            if self.current_brush.brush_type == BrushType::Eraser {
-                // For eraser, check intersections but don't render the eraser stroke
-                return vec![];
-            }
-            generate_incremental_commands(
-                &new_segments,
-                self.current_brush.color,
-                false,
-            )
+        self.add_points(&[StrokePoint::new(screen_x, screen_y, pressure, timestamp)])
+    }
+
+    /// Add a batch of screen-space points (e.g. coalesced touches) to the current stroke.
+    /// Returns one merged incremental update covering every segment the batch touched.
+    pub fn add_points(&mut self, screen_points: &[StrokePoint]) -> Vec<RenderCommand> {
+        let origin = self.target_origin();
+        let Some(builder) = &mut self.active_builder else {
+            return vec![];
+        };
+        // The last existing segment is refined once the next point arrives, so redraw it.
+        let before = builder.segment_count();
+        for sp in screen_points {
+            let local = self.viewport.screen_to_canvas(sp.position) - origin;
+            self.hold.push(local, sp.timestamp);
+            builder.add_point(StrokePoint {
+                position: local,
+                pressure: self.device_pressure_curve.apply(sp.pressure),
+                ..*sp
+            });
+        }
+        if self.current_brush.brush_type == BrushType::Eraser {
+            // For eraser, check intersections but don't render the eraser stroke
+            return vec![];
+        }
+        let mut commands = if let Some(settings) = self.current_brush.stamp_settings() {
+            // Stamps build up where they overlap, so never redraw the refined segment;
+            // its placeholder stamps stay until the full render at `end_stroke`.
+            let stamps: Vec<Stamp> = builder
+                .stamps_from(before)
+                .into_iter()
+                .map(|s| Stamp {
+                    position: s.position + origin,
+                    ..s
+                })
+                .collect();
+            generate_incremental_stamp_commands(stamps, settings.tip)
+        } else if builder.segment_count() > before {
+            // Incremental commands are drawn under the viewport transform only, so move
+            // page- or chunk-local segments back into canvas space.
+            let canvas_segments: Vec<BezierSegment> = builder
+                .segments_from(before.saturating_sub(1))
+                .iter()
+                .map(|s| s.translated(origin))
+                .collect();
+            let brush = &self.current_brush;
+            let (color, cap, join) = (brush.color, brush.line_cap, brush.line_join);
+            if let Some(nib) = brush.nib_settings() {
+                let outline = nib_outline(&canvas_segments, &nib);
+                generate_incremental_outline_commands(outline, color)
+            } else if brush.dash_pattern().is_some() {
+                // Dashes are laid out from the stroke's start, so only the builder can
+                // place them; the refined segment is redrawn like a solid path.
+                let dashes = builder
+                    .dashes_from(before.saturating_sub(1))
+                    .into_iter()
+                    .map(|dash| dash.iter().map(|s| s.translated(origin)).collect())
+                    .collect();
+                generate_incremental_dash_commands(dashes, color, cap, join)
+            } else {
+                generate_incremental_commands(&canvas_segments, color, false, cap, join)
+            }
         } else {
             vec![]
+        };
+        if self.prediction_lookahead > 0.0 {
+            commands.push(self.prediction_command());
+        }
+        commands
+    }
+
+    /// Where the active stroke is expected to go next, in canvas coordinates.
+    /// Empty when prediction is off, no stroke is active or there is too little history.
+    pub fn predicted_points(&self) -> Vec<StrokePoint> {
+        let Some(builder) = &self.active_builder else {
+            return vec![];
+        };
+        let origin = self.target_origin();
+        predict_points(builder.points(), self.prediction_lookahead)
+            .into_iter()
+            .map(|p| StrokePoint {
+                position: p.position + origin,
+                ..p
+            })
+            .collect()
+    }
+
+    /// Prediction for the active stroke; empty segments clear a stale prediction.
+    fn prediction_command(&self) -> RenderCommand {
+        let predicted = self.predicted_points();
+        let segments = match self.active_builder.as_ref().and_then(|b| {
+            let last = b.points().last()?;
+            Some((last.position + self.target_origin(), b.last_width()?))
+        }) {
+            Some((start, width)) => prediction_segments(start, &predicted, width)
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            None => vec![],
+        };
+        RenderCommand::DrawPrediction {
+            segments,
+            color: self.current_brush.color,
         }
     }
 
     /// End the current stroke. Returns full render commands for a clean redraw.
     pub fn end_stroke(&mut self) -> Vec<RenderCommand> {
-        if let Some(builder) = self.active_builder.take() {
+        let target = self.active_target.take();
+        if let (Some(builder), Some(target)) = (self.active_builder.take(), target) {
             let stroke = builder.finish();
+            let snapped = self.snapped_stroke(&stroke).map(|shape| (stroke.clone(), shape));
+            match target {
+                StrokeTarget::Page { page_id, .. } => self.commit_page_stroke(page_id, stroke),
+                StrokeTarget::Chunk(chunk) => self.commit_chunk_stroke(chunk, stroke),
+            }
+            if let Some((before, after)) = snapped {
+                self.replace_committed_stroke(target, before, after);
+            }
+        }
 
-            if self.current_brush.brush_type == BrushType::Eraser {
-                // Erase strokes that intersect with the eraser path
-                let layer = self.layer_manager.active_layer();
-                let mut erased_ids = Vec::new();
-                for sp in &stroke.points {
-                    let width = self.current_brush.compute_width(sp.pressure, 0.0);
-                    let ids = find_strokes_to_erase(
-                        &layer.strokes,
-                        sp.position,
-                        width * 0.5,
-                    );
-                    for id in ids {
-                        if !erased_ids.contains(&id) {
-                            erased_ids.push(id);
-                        }
-                    }
+        self.full_render()
+    }
+
+    /// The recognized shape for a just-finished stroke, if snapping applies to it.
+    fn snapped_stroke(&self, stroke: &Stroke) -> Option<Stroke> {
+        let wanted = match self.shape_snapping {
+            ShapeSnapping::Off => false,
+            ShapeSnapping::OnHold => self.hold.is_held(),
+            ShapeSnapping::Always => true,
+        };
+        if !wanted || self.current_brush.brush_type == BrushType::Eraser {
+            return None;
+        }
+        snap_stroke(stroke)
+    }
+
+    /// Swap a stroke committed by `end_stroke` for its snapped shape as its own undo
+    /// step, so undo first restores the stroke as drawn.
+    fn replace_committed_stroke(&mut self, target: StrokeTarget, before: Stroke, after: Stroke) {
+        let action = match target {
+            StrokeTarget::Page { page_id, .. } => {
+                let Some(page_index) = find_page(&self.pages, page_id) else {
+                    return;
+                };
+                HistoryAction::ReplaceStroke {
+                    page_id,
+                    layer_index: self.pages[page_index].layer_manager.active_layer_index,
+                    before,
+                    after,
                 }
+            }
+            StrokeTarget::Chunk(chunk) => HistoryAction::ReplaceChunkStroke {
+                chunk,
+                before,
+                after,
+            },
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+    }
 
-                let layer_idx = self.layer_manager.active_layer_index;
-                for id in erased_ids {
-                    if let Some(removed) = self.layer_manager.active_layer_mut().remove_stroke(id) {
-                        self.history.push(HistoryAction::RemoveStroke {
-                            layer_index: layer_idx,
-                            stroke: removed,
-                        });
+    /// Canvas-space origin of the active stroke's page or chunk.
+    fn target_origin(&self) -> Point {
+        match (self.active_target, &self.infinite) {
+            (Some(StrokeTarget::Page { origin, .. }), _) => origin,
+            (Some(StrokeTarget::Chunk(chunk)), Some(canvas)) => canvas.anchor.offset_to(chunk),
+            _ => Point::new(0.0, 0.0),
+        }
+    }
+
+    fn commit_page_stroke(&mut self, page_id: Uuid, stroke: Stroke) {
+        let Some(page_index) = find_page(&self.pages, page_id) else {
+            return;
+        };
+        let layers = &mut self.pages[page_index].layer_manager;
+
+        if self.current_brush.brush_type == BrushType::Eraser {
+            // Erase strokes that intersect with the eraser path
+            let layer = layers.active_layer();
+            let mut erased_ids = Vec::new();
+            for sp in &stroke.points {
+                let width = self.current_brush.compute_width(sp.pressure, 0.0, sp.altitude);
+                let ids = find_strokes_to_erase(
+                    &layer.strokes,
+                    sp.position,
+                    width * 0.5,
+                );
+                for id in ids {
+                    if !erased_ids.contains(&id) {
+                        erased_ids.push(id);
                     }
                 }
-            } else if !stroke.segments.is_empty() {
-                let layer_idx = self.layer_manager.active_layer_index;
-                self.history.push(HistoryAction::AddStroke {
-                    layer_index: layer_idx,
-                    stroke: stroke.clone(),
-                });
-                self.layer_manager.active_layer_mut().add_stroke(stroke);
             }
+
+            let layer_idx = layers.active_layer_index;
+            for id in erased_ids {
+                if let Some(removed) = layers.active_layer_mut().remove_stroke(id) {
+                    self.journal.record(JournalEntry::RemoveStroke {
+                        page_id,
+                        layer_index: layer_idx,
+                        stroke_id: removed.id,
+                    });
+                    self.history.push(HistoryAction::RemoveStroke {
+                        page_id,
+                        layer_index: layer_idx,
+                        stroke: removed,
+                    });
+                }
+            }
+        } else if !stroke.segments.is_empty() {
+            let layer_idx = layers.active_layer_index;
+            self.history.push(HistoryAction::AddStroke {
+                page_id,
+                layer_index: layer_idx,
+                stroke: stroke.clone(),
+            });
+            self.journal.record(JournalEntry::AddStroke {
+                page_id,
+                layer_index: layer_idx,
+                stroke: stroke.clone(),
+            });
+            layers.active_layer_mut().add_stroke(stroke);
         }
+    }
 
-        self.full_render()
+    /// Store a stroke in `chunk`, or erase what it touches in nearby chunks.
+    fn commit_chunk_stroke(&mut self, chunk: ChunkCoord, stroke: Stroke) {
+        let Some(canvas) = &mut self.infinite else {
+            return;
+        };
+
+        if self.current_brush.brush_type == BrushType::Eraser {
+            let mut erased = Vec::new();
+            for sp in &stroke.points {
+                let width = self.current_brush.compute_width(sp.pressure, 0.0, sp.altitude);
+                for hit in canvas.find_strokes_to_erase(chunk, sp.position, width * 0.5) {
+                    if !erased.contains(&hit) {
+                        erased.push(hit);
+                    }
+                }
+            }
+
+            for (coord, id) in erased {
+                if let Some(removed) = canvas.remove_stroke(coord, id) {
+                    self.journal.record(JournalEntry::RemoveChunkStroke {
+                        chunk: coord,
+                        stroke_id: id,
+                    });
+                    self.history.push(HistoryAction::RemoveChunkStroke {
+                        chunk: coord,
+                        stroke: removed,
+                    });
+                }
+            }
+        } else if !stroke.segments.is_empty() {
+            self.history.push(HistoryAction::AddChunkStroke {
+                chunk,
+                stroke: stroke.clone(),
+            });
+            self.journal.record(JournalEntry::AddChunkStroke {
+                chunk,
+                stroke: stroke.clone(),
+            });
+            canvas.add_stroke(chunk, stroke);
+        }
     }
 
     // --- Undo/Redo ---
 
     pub fn undo(&mut self) -> Vec<RenderCommand> {
+        self.finish_shape_edit();
         if let Some(action) = self.history.undo() {
             self.apply_history_action(&action);
         }
@@ -154,140 +545,1311 @@ impl DrawEngine {
         self.history.can_redo()
     }
 
+    /// Actions that refer to a page which has since been removed are ignored.
     fn apply_history_action(&mut self, action: &HistoryAction) {
+        if let Some((removal, addition)) = action.split_replace() {
+            self.apply_history_action(&removal);
+            self.apply_history_action(&addition);
+            return;
+        }
+        if let HistoryAction::InsertPage { .. }
+        | HistoryAction::RemovePage { .. }
+        | HistoryAction::MovePage { .. } = action
+        {
+            self.apply_page_action(action);
+            return;
+        }
+        let Some(page_id) = action.page_id() else {
+            self.apply_chunk_action(action);
+            return;
+        };
+        let Some(page_index) = find_page(&self.pages, page_id) else {
+            return;
+        };
+        let layers = &mut self.pages[page_index].layer_manager.layers;
         match action {
             HistoryAction::AddStroke {
                 layer_index,
                 stroke,
+                ..
             } => {
-                if let Some(layer) = self.layer_manager.layers.get_mut(*layer_index) {
+                if let Some(layer) = layers.get_mut(*layer_index) {
                     layer.add_stroke(stroke.clone());
+                    self.journal.record(JournalEntry::AddStroke {
+                        page_id,
+                        layer_index: *layer_index,
+                        stroke: stroke.clone(),
+                    });
                 }
             }
             HistoryAction::RemoveStroke {
                 layer_index,
                 stroke,
+                ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.remove_stroke(stroke.id).is_some() {
+                        self.journal.record(JournalEntry::RemoveStroke {
+                            page_id,
+                            layer_index: *layer_index,
+                            stroke_id: stroke.id,
+                        });
+                    }
+                }
+            }
+            HistoryAction::AddShape {
+                layer_index,
+                shape,
+                ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    layer.add_shape(shape.clone());
+                    self.journal.record(JournalEntry::AddShape {
+                        page_id,
+                        layer_index: *layer_index,
+                        shape: shape.clone(),
+                    });
+                }
+            }
+            HistoryAction::RemoveShape {
+                layer_index,
+                shape,
+                ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.remove_shape(shape.id).is_some() {
+                        self.journal.record(JournalEntry::RemoveShape {
+                            page_id,
+                            shape_id: shape.id,
+                        });
+                    }
+                }
+            }
+            HistoryAction::ReplaceShape {
+                layer_index, after, ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.replace_shape(after.clone()).is_some() {
+                        self.journal.record(JournalEntry::UpdateShape {
+                            page_id,
+                            shape: after.clone(),
+                        });
+                    }
+                }
+            }
+            HistoryAction::AddText {
+                layer_index, text, ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    layer.add_text(text.clone());
+                    self.journal.record(JournalEntry::AddText {
+                        page_id,
+                        layer_index: *layer_index,
+                        text: text.clone(),
+                    });
+                }
+            }
+            HistoryAction::RemoveText {
+                layer_index, text, ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.remove_text(text.id).is_some() {
+                        self.journal.record(JournalEntry::RemoveText {
+                            page_id,
+                            text_id: text.id,
+                        });
+                    }
+                }
+            }
+            HistoryAction::ReplaceText {
+                layer_index, after, ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.replace_text(after.clone()).is_some() {
+                        self.journal.record(JournalEntry::UpdateText {
+                            page_id,
+                            text: after.clone(),
+                        });
+                    }
+                }
+            }
+            HistoryAction::AddImage {
+                layer_index,
+                image,
+                ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    layer.add_image(image.clone());
+                    self.journal.record(JournalEntry::AddImage {
+                        page_id,
+                        layer_index: *layer_index,
+                        image: image.clone(),
+                    });
+                }
+            }
+            HistoryAction::RemoveImage {
+                layer_index,
+                image,
+                ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.remove_image(image.id).is_some() {
+                        self.journal.record(JournalEntry::RemoveImage {
+                            page_id,
+                            image_id: image.id,
+                        });
+                    }
+                }
+            }
+            HistoryAction::ReplaceImage {
+                layer_index, after, ..
             } => {
-                if let Some(layer) = self.layer_manager.layers.get_mut(*layer_index) {
-                    layer.remove_stroke(stroke.id);
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.replace_image(after.clone()).is_some() {
+                        self.journal.record(JournalEntry::UpdateImage {
+                            page_id,
+                            image: after.clone(),
+                        });
+                    }
+                }
+            }
+            HistoryAction::AddFill {
+                layer_index, fill, ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    layer.add_fill(fill.clone());
+                    self.journal.record(JournalEntry::AddFill {
+                        page_id,
+                        layer_index: *layer_index,
+                        fill: fill.clone(),
+                    });
+                }
+            }
+            HistoryAction::RemoveFill {
+                layer_index, fill, ..
+            } => {
+                if let Some(layer) = layers.get_mut(*layer_index) {
+                    if layer.remove_fill(fill.id).is_some() {
+                        self.journal.record(JournalEntry::RemoveFill {
+                            page_id,
+                            fill_id: fill.id,
+                        });
+                    }
                 }
             }
+            HistoryAction::AddChunkStroke { .. }
+            | HistoryAction::RemoveChunkStroke { .. }
+            | HistoryAction::ReplaceStroke { .. }
+            | HistoryAction::ReplaceChunkStroke { .. }
+            | HistoryAction::InsertPage { .. }
+            | HistoryAction::RemovePage { .. }
+            | HistoryAction::MovePage { .. } => {}
         }
     }
 
-    // --- Viewport ---
+    fn apply_chunk_action(&mut self, action: &HistoryAction) {
+        let Some(canvas) = &mut self.infinite else {
+            return;
+        };
+        match action {
+            HistoryAction::AddChunkStroke { chunk, stroke } => {
+                canvas.add_stroke(*chunk, stroke.clone());
+                self.journal.record(JournalEntry::AddChunkStroke {
+                    chunk: *chunk,
+                    stroke: stroke.clone(),
+                });
+            }
+            HistoryAction::RemoveChunkStroke { chunk, stroke } => {
+                if canvas.remove_stroke(*chunk, stroke.id).is_some() {
+                    self.journal.record(JournalEntry::RemoveChunkStroke {
+                        chunk: *chunk,
+                        stroke_id: stroke.id,
+                    });
+                }
+            }
+            HistoryAction::AddStroke { .. }
+            | HistoryAction::RemoveStroke { .. }
+            | HistoryAction::ReplaceStroke { .. }
+            | HistoryAction::ReplaceChunkStroke { .. }
+            | HistoryAction::AddShape { .. }
+            | HistoryAction::RemoveShape { .. }
+            | HistoryAction::ReplaceShape { .. }
+            | HistoryAction::AddText { .. }
+            | HistoryAction::RemoveText { .. }
+            | HistoryAction::ReplaceText { .. }
+            | HistoryAction::AddImage { .. }
+            | HistoryAction::RemoveImage { .. }
+            | HistoryAction::ReplaceImage { .. }
+            | HistoryAction::AddFill { .. }
+            | HistoryAction::RemoveFill { .. }
+            | HistoryAction::InsertPage { .. }
+            | HistoryAction::RemovePage { .. }
+            | HistoryAction::MovePage { .. } => {}
+        }
+    }
 
-    pub fn zoom(&mut self, factor: f64, focal_x: f64, focal_y: f64) -> Vec<RenderCommand> {
-        self.viewport.zoom(factor, Point::new(focal_x, focal_y));
-        self.full_render()
+    /// Keeps the same page current where it still exists. Removing the last page is
+    /// ignored.
+    fn apply_page_action(&mut self, action: &HistoryAction) {
+        let current_id = self.current_page().id;
+        match action {
+            HistoryAction::InsertPage { index, page } => {
+                if find_page(&self.pages, page.id).is_some() {
+                    return;
+                }
+                let index = (*index).min(self.pages.len());
+                self.journal.record(JournalEntry::InsertPage {
+                    index,
+                    page: page.clone(),
+                });
+                self.pages.insert(index, page.clone());
+            }
+            HistoryAction::RemovePage { page, .. } => {
+                let Some(index) = find_page(&self.pages, page.id) else {
+                    return;
+                };
+                if self.pages.len() == 1 {
+                    return;
+                }
+                self.pages.remove(index);
+                self.journal.record(JournalEntry::RemovePage { page_id: page.id });
+                if page.id == current_id {
+                    self.current_page_index = index.min(self.pages.len() - 1);
+                    return;
+                }
+            }
+            HistoryAction::MovePage { page_id, to, .. } => {
+                let Some(from) = find_page(&self.pages, *page_id) else {
+                    return;
+                };
+                let page = self.pages.remove(from);
+                let to = (*to).min(self.pages.len());
+                self.pages.insert(to, page);
+                self.journal.record(JournalEntry::MovePage {
+                    page_id: *page_id,
+                    to_index: to,
+                });
+            }
+            _ => return,
+        }
+        self.current_page_index = find_page(&self.pages, current_id).unwrap_or(0);
     }
 
-    pub fn pan(&mut self, dx: f64, dy: f64) -> Vec<RenderCommand> {
-        self.viewport.pan(dx, dy);
-        self.full_render()
+    // --- Shapes ---
+
+    /// Page-local position on the current page of a point in screen coordinates. Shape
+    /// geometry is given in these coordinates.
+    pub fn screen_to_page(&self, screen_x: f64, screen_y: f64) -> Point {
+        let canvas_point = self.viewport.screen_to_canvas(Point::new(screen_x, screen_y));
+        let origins = self.page_layout.page_origins(&self.pages);
+        canvas_point - origins[self.current_page_index]
     }
 
-    pub fn reset_viewport(&mut self) -> Vec<RenderCommand> {
-        self.viewport.reset();
-        self.full_render()
+    /// Add a shape to the active layer of the current page as one undo step.
+    pub fn add_shape(&mut self, mut shape: Shape) -> Result<Uuid, EngineError> {
+        self.check_shape_document(&mut shape)?;
+        self.finish_shape_edit();
+        let page = self.current_page();
+        let action = HistoryAction::AddShape {
+            page_id: page.id,
+            layer_index: page.layer_manager.active_layer_index,
+            shape,
+        };
+        let id = action.element_id();
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(id)
     }
 
-    pub fn get_scale(&self) -> f64 {
-        self.viewport.scale
+    /// Replace the current page's shape with the same id, keeping its place in the order.
+    pub fn update_shape(&mut self, mut shape: Shape) -> Result<(), EngineError> {
+        self.check_shape_document(&mut shape)?;
+        self.finish_shape_edit();
+        let (layer_index, before) = self.find_shape(self.current_page_index, shape.id)?;
+        let action = HistoryAction::ReplaceShape {
+            page_id: self.current_page().id,
+            layer_index,
+            before: before.clone(),
+            after: shape,
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
     }
 
-    pub fn get_offset(&self) -> (f64, f64) {
-        (self.viewport.offset_x, self.viewport.offset_y)
+    pub fn remove_shape(&mut self, shape_id: Uuid) -> Result<(), EngineError> {
+        self.finish_shape_edit();
+        let (layer_index, shape) = self.find_shape(self.current_page_index, shape_id)?;
+        let action = HistoryAction::RemoveShape {
+            page_id: self.current_page().id,
+            layer_index,
+            shape: shape.clone(),
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
     }
 
-    // --- Render ---
+    pub fn shape(&self, shape_id: Uuid) -> Option<&Shape> {
+        self.find_shape(self.current_page_index, shape_id).ok().map(|(_, shape)| shape)
+    }
 
-    pub fn full_render(&self) -> Vec<RenderCommand> {
-        let strokes: Vec<&crate::stroke::Stroke> = self.layer_manager.all_visible_strokes();
-        let owned: Vec<Stroke> = strokes.into_iter().cloned().collect();
-        generate_full_render_commands(
-            &owned,
-            self.background_color,
-            self.viewport.scale,
-            self.viewport.offset_x,
-            self.viewport.offset_y,
-        )
+    /// Topmost visible shape on the current page within `tolerance` of a page-local
+    /// point, for selection.
+    pub fn shape_at(&self, point: Point, tolerance: f64) -> Option<Uuid> {
+        let layers = &self.current_page().layer_manager.layers;
+        layers
+            .iter()
+            .rev()
+            .filter(|l| l.visible)
+            .flat_map(|l| l.shapes.iter().rev())
+            .find(|s| s.hit_test(point, tolerance))
+            .map(|s| s.id)
     }
 
-    // --- Serialization ---
+    /// Drag handle `handle` (see `ShapeGeometry::handles`) of a shape to a page-local
+    /// point. The shape updates live; `finish_shape_edit` records the whole drag as
+    /// one undo step.
+    pub fn move_shape_handle(
+        &mut self,
+        shape_id: Uuid,
+        handle: usize,
+        to: Point,
+    ) -> Result<Vec<RenderCommand>, EngineError> {
+        if !(to.x.is_finite() && to.y.is_finite()) {
+            return Err(EngineError::InvalidGeometry {
+                message: format!("handle position ({}, {})", to.x, to.y),
+            });
+        }
+        let page_id = self.current_page().id;
+        if self
+            .shape_edit
+            .as_ref()
+            .is_some_and(|(page, s)| *page != page_id || s.id != shape_id)
+        {
+            self.finish_shape_edit();
+        }
+        let (layer_index, shape) = self.find_shape(self.current_page_index, shape_id)?;
+        let mut edited = shape.clone();
+        if !edited.geometry.move_handle(handle, to) {
+            return Err(EngineError::InvalidGeometry {
+                message: format!("shape has no handle {handle}"),
+            });
+        }
+        if self.shape_edit.is_none() {
+            self.shape_edit = Some((page_id, shape.clone()));
+        }
+        self.current_page_mut().layer_manager.layers[layer_index].replace_shape(edited);
+        Ok(self.full_render())
+    }
 
-    pub fn save(&self) -> Result<String, String> {
-        let data = DocumentData {
-            version: 1,
-            width: self.canvas_width,
-            height: self.canvas_height,
-            background_color: self.background_color,
-            layers: self.layer_manager.layers.clone(),
+    /// Commit a handle drag started by `move_shape_handle`. Does nothing otherwise.
+    pub fn finish_shape_edit(&mut self) {
+        let Some((page_id, before)) = self.shape_edit.take() else {
+            return;
+        };
+        let Some(page_index) = find_page(&self.pages, page_id) else {
+            return;
+        };
+        let Ok((layer_index, after)) = self.find_shape(page_index, before.id) else {
+            return;
+        };
+        if *after == before {
+            return;
+        }
+        let action = HistoryAction::ReplaceShape {
+            page_id,
+            layer_index,
+            before,
+            after: after.clone(),
         };
-        data.save_to_json().map_err(|e| e.to_string())
+        self.journal.record(JournalEntry::UpdateShape {
+            page_id,
+            shape: after.clone(),
+        });
+        self.history.push(action);
+    }
+
+    /// Layer index and shape with `shape_id` on page `page_index`.
+    fn find_shape(
+        &self,
+        page_index: usize,
+        shape_id: Uuid,
+    ) -> Result<(usize, &Shape), EngineError> {
+        let layers = &self.pages[page_index].layer_manager.layers;
+        layers
+            .iter()
+            .enumerate()
+            .find_map(|(i, l)| l.shapes.iter().find(|s| s.id == shape_id).map(|s| (i, s)))
+            .ok_or(EngineError::ShapeNotFound { shape_id })
     }
 
-    pub fn load(&mut self, json: &str) -> Result<(), String> {
-        let data = DocumentData::load_from_json(json).map_err(|e| e.to_string())?;
-        self.canvas_width = data.width;
-        self.canvas_height = data.height;
-        self.background_color = data.background_color;
-        self.layer_manager = LayerManager::new();
-        self.layer_manager.layers = data.layers;
-        if self.layer_manager.layers.is_empty() {
-            self.layer_manager = LayerManager::new();
+    /// Shapes live on pages, and their geometry must be drawable.
+    /// Reject unusable geometry and clamp colors into range.
+    fn check_shape_document(&self, shape: &mut Shape) -> Result<(), EngineError> {
+        if self.infinite.is_some() {
+            return Err(EngineError::Unsupported {
+                message: "shapes on an infinite canvas".into(),
+            });
         }
-        self.history.clear();
+        let width_ok = shape.stroke_width.is_finite() && shape.stroke_width >= 0.0;
+        if !shape.geometry.is_valid() || !width_ok {
+            return Err(EngineError::InvalidGeometry {
+                message: "shape geometry must be finite with non-negative sizes".into(),
+            });
+        }
+        shape.stroke_color = shape.stroke_color.map(sanitize_color);
+        shape.fill_color = shape.fill_color.map(sanitize_color);
         Ok(())
     }
 
-    // --- Info ---
+    // --- Text ---
 
-    pub fn stroke_count(&self) -> usize {
-        self.layer_manager
-            .layers
-            .iter()
-            .map(|l| l.strokes.len())
-            .sum()
+    /// Add a text box to the active layer of the current page as one undo step.
+    pub fn add_text(&mut self, mut text: TextBox) -> Result<Uuid, EngineError> {
+        self.check_text_document(&mut text)?;
+        self.finish_shape_edit();
+        let page = self.current_page();
+        let action = HistoryAction::AddText {
+            page_id: page.id,
+            layer_index: page.layer_manager.active_layer_index,
+            text,
+        };
+        let id = action.element_id();
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(id)
     }
 
-    pub fn active_layer_id(&self) -> Uuid {
-        self.layer_manager.active_layer_id()
+    /// Replace the current page's text box with the same id, e.g. after typing or
+    /// moving it.
+    pub fn update_text(&mut self, mut text: TextBox) -> Result<(), EngineError> {
+        self.check_text_document(&mut text)?;
+        self.finish_shape_edit();
+        let (layer_index, before) = self.find_text(text.id)?;
+        if *before == text {
+            return Ok(());
+        }
+        let action = HistoryAction::ReplaceText {
+            page_id: self.current_page().id,
+            layer_index,
+            before: before.clone(),
+            after: text,
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::brush::BrushConfig;
-    use crate::point::Color;
+    pub fn remove_text(&mut self, text_id: Uuid) -> Result<(), EngineError> {
+        self.finish_shape_edit();
+        let (layer_index, text) = self.find_text(text_id)?;
+        let action = HistoryAction::RemoveText {
+            page_id: self.current_page().id,
+            layer_index,
+            text: text.clone(),
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
+    }
 
-    #[test]
-    fn test_engine_new() {
-        let engine = DrawEngine::new(1920.0, 1080.0);
-        assert_eq!(engine.stroke_count(), 0);
-        assert!(!engine.can_undo());
+    pub fn text(&self, text_id: Uuid) -> Option<&TextBox> {
+        self.find_text(text_id).ok().map(|(_, text)| text)
     }
 
-    #[test]
-    fn test_draw_stroke() {
-        let mut engine = DrawEngine::new(1920.0, 1080.0);
-        engine.set_brush(BrushConfig::pen(Color::black(), 3.0));
+    /// Topmost visible text box on the current page within `tolerance` of a page-local
+    /// point, for selection.
+    pub fn text_at(&self, point: Point, tolerance: f64) -> Option<Uuid> {
+        let layers = &self.current_page().layer_manager.layers;
+        layers
+            .iter()
+            .rev()
+            .filter(|l| l.visible)
+            .flat_map(|l| l.texts.iter().rev())
+            .find(|t| t.hit_test(point, tolerance, self.text_measure.as_ref()))
+            .map(|t| t.id)
+    }
 
-        engine.begin_stroke(100.0, 100.0, 0.5, 0.0);
-        engine.add_point(110.0, 105.0, 0.6, 0.016);
-        engine.add_point(120.0, 110.0, 0.7, 0.032);
-        engine.add_point(130.0, 108.0, 0.6, 0.048);
-        let cmds = engine.end_stroke();
+    /// Page-local frame of a text box on the current page as it is laid out.
+    pub fn text_bounds(&self, text_id: Uuid) -> Result<BoundingBox, EngineError> {
+        let (_, text) = self.find_text(text_id)?;
+        Ok(text.bounding_box(self.text_measure.as_ref()))
+    }
 
-        assert_eq!(engine.stroke_count(), 1);
-        assert!(engine.can_undo());
-        assert!(!cmds.is_empty());
+    /// Measure text with the host's fonts from now on, so lines wrap where the native
+    /// renderer breaks them. Re-renders with the new layout.
+    pub fn set_text_measure(&mut self, measure: Arc<dyn TextMeasure>) -> Vec<RenderCommand> {
+        self.text_measure = measure;
+        self.full_render()
     }
 
-    #[test]
-    fn test_undo_redo() {
-        let mut engine = DrawEngine::new(1920.0, 1080.0);
+    /// Layer index and text box with `text_id` on the current page.
+    fn find_text(&self, text_id: Uuid) -> Result<(usize, &TextBox), EngineError> {
+        let layers = &self.current_page().layer_manager.layers;
+        layers
+            .iter()
+            .enumerate()
+            .find_map(|(i, l)| l.texts.iter().find(|t| t.id == text_id).map(|t| (i, t)))
+            .ok_or(EngineError::TextNotFound { text_id })
+    }
+
+    /// Reject unusable placement and clamp the color into range.
+    fn check_text_document(&self, text: &mut TextBox) -> Result<(), EngineError> {
+        if self.infinite.is_some() {
+            return Err(EngineError::Unsupported {
+                message: "text on an infinite canvas".into(),
+            });
+        }
+        if !text.is_valid() {
+            return Err(EngineError::InvalidGeometry {
+                message: "text needs a finite position and a positive font size".into(),
+            });
+        }
+        text.color = sanitize_color(text.color);
+        Ok(())
+    }
+
+    // --- Images ---
+
+    /// Store PNG or JPEG bytes for placing with `add_image`, returning what was stored.
+    /// `save_package` and the journal keep the bytes; `save` records only placements.
+    pub fn import_image(&mut self, bytes: Vec<u8>) -> Result<&ImageData, EngineError> {
+        let data = ImageData::from_bytes(bytes)?;
+        if self.journal.is_enabled() {
+            self.journal.record(JournalEntry::ImportImage {
+                image_id: data.id,
+                bytes: data.bytes.clone(),
+            });
+        }
+        Ok(self.images.entry(data.id).or_insert(data))
+    }
+
+    /// Bytes behind `RenderCommand::DrawImage`, for the host to decode and cache by id.
+    pub fn image_data(&self, image_id: Uuid) -> Option<&ImageData> {
+        self.images.get(&image_id)
+    }
+
+    /// Place an imported image on the active layer of the current page as one undo step.
+    pub fn add_image(&mut self, image: Image) -> Result<Uuid, EngineError> {
+        self.check_image_document(&image)?;
+        self.finish_shape_edit();
+        let page = self.current_page();
+        let action = HistoryAction::AddImage {
+            page_id: page.id,
+            layer_index: page.layer_manager.active_layer_index,
+            image,
+        };
+        let id = action.element_id();
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(id)
+    }
+
+    /// Replace the current page's image with the same id, e.g. after moving, scaling,
+    /// rotating or cropping it.
+    pub fn update_image(&mut self, image: Image) -> Result<(), EngineError> {
+        self.check_image_document(&image)?;
+        self.finish_shape_edit();
+        let (layer_index, before) = self.find_image(image.id)?;
+        if *before == image {
+            return Ok(());
+        }
+        let action = HistoryAction::ReplaceImage {
+            page_id: self.current_page().id,
+            layer_index,
+            before: before.clone(),
+            after: image,
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
+    }
+
+    pub fn remove_image(&mut self, image_id: Uuid) -> Result<(), EngineError> {
+        self.finish_shape_edit();
+        let (layer_index, image) = self.find_image(image_id)?;
+        let action = HistoryAction::RemoveImage {
+            page_id: self.current_page().id,
+            layer_index,
+            image: image.clone(),
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
+    }
+
+    pub fn image(&self, image_id: Uuid) -> Option<&Image> {
+        self.find_image(image_id).ok().map(|(_, image)| image)
+    }
+
+    /// Topmost visible image on the current page within `tolerance` of a page-local
+    /// point, for selection.
+    pub fn image_at(&self, point: Point, tolerance: f64) -> Option<Uuid> {
+        let layers = &self.current_page().layer_manager.layers;
+        layers
+            .iter()
+            .rev()
+            .filter(|l| l.visible)
+            .flat_map(|l| l.images.iter().rev())
+            .find(|i| i.hit_test(point, tolerance))
+            .map(|i| i.id)
+    }
+
+    /// Layer index and image with `image_id` on the current page.
+    fn find_image(&self, image_id: Uuid) -> Result<(usize, &Image), EngineError> {
+        let layers = &self.current_page().layer_manager.layers;
+        layers
+            .iter()
+            .enumerate()
+            .find_map(|(i, l)| l.images.iter().find(|im| im.id == image_id).map(|im| (i, im)))
+            .ok_or(EngineError::ImageNotFound { image_id })
+    }
+
+    /// Images live on pages and must show part of an imported image.
+    fn check_image_document(&self, image: &Image) -> Result<(), EngineError> {
+        if self.infinite.is_some() {
+            return Err(EngineError::Unsupported {
+                message: "images on an infinite canvas".into(),
+            });
+        }
+        let data = self
+            .images
+            .get(&image.image_id)
+            .ok_or(EngineError::ImageNotFound {
+                image_id: image.image_id,
+            })?;
+        if !image.is_valid() {
+            return Err(EngineError::InvalidGeometry {
+                message: "image needs a finite placement, a positive scale and a crop".into(),
+            });
+        }
+        let crop = &image.crop;
+        if crop.x < 0.0
+            || crop.y < 0.0
+            || crop.x + crop.width > data.width as f64
+            || crop.y + crop.height > data.height as f64
+        {
+            return Err(EngineError::InvalidGeometry {
+                message: "crop extends past the image".into(),
+            });
+        }
+        Ok(())
+    }
+
+    // --- Fills ---
+
+    /// Fill the area around a page-local point that visible strokes and shape outlines
+    /// on the current page enclose, in the current brush color, as one undo step.
+    /// Returns `None` without changing anything if the point is on ink or the area is
+    /// open to the page edge.
+    pub fn fill_enclosed_area(&mut self, point: Point) -> Result<Option<Uuid>, EngineError> {
+        self.check_fill_document()?;
+        let page = self.current_page();
+        let mut barriers = Vec::new();
+        for layer in page.layer_manager.layers.iter().filter(|l| l.visible) {
+            for stroke in layer.strokes.iter().filter(|s| !s.is_eraser) {
+                barriers.extend(stroke.segments.iter().map(|s| s.to_bezier()));
+            }
+            for shape in layer.shapes.iter().filter(|s| s.stroke_color.is_some()) {
+                barriers.extend(shape.geometry.segments(shape.stroke_width));
+            }
+        }
+        let bounds =
+            BoundingBox::from_points(&[Point::new(0.0, 0.0), Point::new(page.width, page.height)]);
+        let Some(polygons) = enclosed_region(&barriers, bounds, point) else {
+            return Ok(None);
+        };
+        let fill = FillRegion::new(polygons, self.current_brush.color);
+        Ok(Some(self.add_fill(fill)))
+    }
+
+    /// Fill the inside of a lasso through page-local points in the current brush color,
+    /// as one undo step.
+    pub fn fill_lasso(&mut self, points: &[Point]) -> Result<Uuid, EngineError> {
+        self.check_fill_document()?;
+        let fill = FillRegion::new(vec![points.to_vec()], self.current_brush.color);
+        if !fill.is_valid() {
+            return Err(EngineError::InvalidGeometry {
+                message: "a lasso needs at least three finite points".into(),
+            });
+        }
+        Ok(self.add_fill(fill))
+    }
+
+    pub fn remove_fill(&mut self, fill_id: Uuid) -> Result<(), EngineError> {
+        self.finish_shape_edit();
+        let (layer_index, fill) = self.find_fill(fill_id)?;
+        let action = HistoryAction::RemoveFill {
+            page_id: self.current_page().id,
+            layer_index,
+            fill: fill.clone(),
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
+    }
+
+    pub fn fill(&self, fill_id: Uuid) -> Option<&FillRegion> {
+        self.find_fill(fill_id).ok().map(|(_, fill)| fill)
+    }
+
+    /// Topmost visible fill on the current page covering a page-local point, for
+    /// selection.
+    pub fn fill_at(&self, point: Point) -> Option<Uuid> {
+        let layers = &self.current_page().layer_manager.layers;
+        layers
+            .iter()
+            .rev()
+            .filter(|l| l.visible)
+            .flat_map(|l| l.fills.iter().rev())
+            .find(|f| f.contains(point))
+            .map(|f| f.id)
+    }
+
+    /// Add `fill` to the active layer of the current page as one undo step.
+    fn add_fill(&mut self, fill: FillRegion) -> Uuid {
+        self.finish_shape_edit();
+        let page = self.current_page();
+        let action = HistoryAction::AddFill {
+            page_id: page.id,
+            layer_index: page.layer_manager.active_layer_index,
+            fill,
+        };
+        let id = action.element_id();
+        self.apply_history_action(&action);
+        self.history.push(action);
+        id
+    }
+
+    /// Layer index and fill with `fill_id` on the current page.
+    fn find_fill(&self, fill_id: Uuid) -> Result<(usize, &FillRegion), EngineError> {
+        let layers = &self.current_page().layer_manager.layers;
+        layers
+            .iter()
+            .enumerate()
+            .find_map(|(i, l)| l.fills.iter().find(|f| f.id == fill_id).map(|f| (i, f)))
+            .ok_or(EngineError::FillNotFound { fill_id })
+    }
+
+    fn check_fill_document(&self) -> Result<(), EngineError> {
+        if self.infinite.is_some() {
+            return Err(EngineError::Unsupported {
+                message: "fills on an infinite canvas".into(),
+            });
+        }
+        Ok(())
+    }
+
+    // --- Pages ---
+
+    pub fn current_page(&self) -> &Page {
+        &self.pages[self.current_page_index]
+    }
+
+    pub fn current_page_mut(&mut self) -> &mut Page {
+        &mut self.pages[self.current_page_index]
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn set_current_page(&mut self, page_id: Uuid) -> Result<(), EngineError> {
+        self.current_page_index = self.page_index(page_id)?;
+        Ok(())
+    }
+
+    /// Append a blank page with the current page's background color and template.
+    /// Returns its id.
+    pub fn add_page(&mut self, width: f64, height: f64) -> Result<Uuid, EngineError> {
+        if !(width.is_finite() && height.is_finite()) || width <= 0.0 || height <= 0.0 {
+            return Err(EngineError::InvalidGeometry {
+                message: format!("page size {width}x{height}"),
+            });
+        }
+        let current = self.current_page();
+        let mut page = Page::new(width, height);
+        page.background_color = current.background_color;
+        page.template = current.template.clone();
+        Ok(self.insert_page(self.pages.len(), page))
+    }
+
+    /// Insert a copy of the page right after it. Returns the copy's id.
+    pub fn duplicate_page(&mut self, page_id: Uuid) -> Result<Uuid, EngineError> {
+        let index = self.page_index(page_id)?;
+        let copy = self.pages[index].duplicate();
+        Ok(self.insert_page(index + 1, copy))
+    }
+
+    /// Remove a page as one undo step.
+    pub fn remove_page(&mut self, page_id: Uuid) -> Result<(), EngineError> {
+        let index = self.page_index(page_id)?;
+        if self.pages.len() == 1 {
+            return Err(EngineError::CannotRemoveLastPage);
+        }
+        self.finish_shape_edit();
+        let action = HistoryAction::RemovePage {
+            index,
+            page: self.pages[index].clone(),
+        };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
+    }
+
+    /// Move a page to `to_index` (clamped to the page range) as one undo step.
+    pub fn move_page(&mut self, page_id: Uuid, to_index: usize) -> Result<(), EngineError> {
+        let from = self.page_index(page_id)?;
+        let to = to_index.min(self.pages.len() - 1);
+        if from == to {
+            return Ok(());
+        }
+        self.finish_shape_edit();
+        let action = HistoryAction::MovePage { page_id, from, to };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        Ok(())
+    }
+
+    /// Set a page's background color and paper template. The color is clamped into range;
+    /// a template with unusable spacing is rejected.
+    pub fn set_page_background(
+        &mut self,
+        page_id: Uuid,
+        color: Color,
+        template: PageTemplate,
+    ) -> Result<Vec<RenderCommand>, EngineError> {
+        let index = self.page_index(page_id)?;
+        if !template.is_valid() {
+            return Err(EngineError::InvalidGeometry {
+                message: "page template spacing is too small or not finite".into(),
+            });
+        }
+        let color = sanitize_color(color);
+        let page = &mut self.pages[index];
+        page.background_color = color;
+        page.template = template.clone();
+        self.journal.record(JournalEntry::SetPageBackground {
+            page_id,
+            color,
+            template,
+        });
+        Ok(self.full_render())
+    }
+
+    pub fn set_page_layout(&mut self, layout: PageLayout) -> Vec<RenderCommand> {
+        self.page_layout = layout;
+        self.full_render()
+    }
+
+    fn insert_page(&mut self, index: usize, page: Page) -> Uuid {
+        self.finish_shape_edit();
+        let id = page.id;
+        let action = HistoryAction::InsertPage { index, page };
+        self.apply_history_action(&action);
+        self.history.push(action);
+        id
+    }
+
+    fn page_index(&self, page_id: Uuid) -> Result<usize, EngineError> {
+        find_page(&self.pages, page_id).ok_or(EngineError::PageNotFound { page_id })
+    }
+
+    /// Page that receives input at `canvas_point`, and its canvas-space origin.
+    fn page_at(&self, canvas_point: Point) -> (usize, Point) {
+        match self.page_layout {
+            PageLayout::Single => (self.current_page_index, Point::new(0.0, 0.0)),
+            PageLayout::ContinuousVertical { .. } => {
+                let origins = self.page_layout.page_origins(&self.pages);
+                // A point in the gap below a page still belongs to that page.
+                let index = origins
+                    .iter()
+                    .rposition(|o| canvas_point.y >= o.y)
+                    .unwrap_or(0);
+                (index, origins[index])
+            }
+        }
+    }
+
+    // --- Viewport ---
+
+    pub fn zoom(&mut self, factor: f64, focal_x: f64, focal_y: f64) -> Vec<RenderCommand> {
+        self.viewport.zoom(factor, Point::new(focal_x, focal_y));
+        self.rebase_anchor();
+        self.full_render()
+    }
+
+    pub fn pan(&mut self, dx: f64, dy: f64) -> Vec<RenderCommand> {
+        self.viewport.pan(dx, dy);
+        self.rebase_anchor();
+        self.full_render()
+    }
+
+    pub fn reset_viewport(&mut self) -> Vec<RenderCommand> {
+        self.viewport.reset();
+        self.full_render()
+    }
+
+    pub fn get_scale(&self) -> f64 {
+        self.viewport.scale
+    }
+
+    /// Viewport offset; on an infinite canvas it is relative to `canvas_anchor`.
+    pub fn get_offset(&self) -> (f64, f64) {
+        (self.viewport.offset_x, self.viewport.offset_y)
+    }
+
+    pub fn canvas_anchor(&self) -> Option<ChunkCoord> {
+        self.infinite.as_ref().map(|c| c.anchor)
+    }
+
+    /// Move the anchor to the chunk under the top-left of the screen so viewport offsets
+    /// stay within about one chunk of the origin however far the user pans.
+    fn rebase_anchor(&mut self) {
+        let Some(canvas) = &mut self.infinite else {
+            return;
+        };
+        let top_left = self.viewport.screen_to_canvas(Point::new(0.0, 0.0));
+        let (chunk, _) = canvas.anchor.locate(top_left);
+        if chunk == canvas.anchor {
+            return;
+        }
+        let shift = canvas.anchor.offset_to(chunk);
+        canvas.anchor = chunk;
+        self.viewport.offset_x += shift.x * self.viewport.scale;
+        self.viewport.offset_y += shift.y * self.viewport.scale;
+    }
+
+    // --- Render ---
+
+    pub fn full_render(&self) -> Vec<RenderCommand> {
+        if let Some(canvas) = &self.infinite {
+            let view = BoundingBox::from_points(&[
+                self.viewport.screen_to_canvas(Point::new(0.0, 0.0)),
+                self.viewport
+                    .screen_to_canvas(Point::new(self.view_size.0, self.view_size.1)),
+            ]);
+            let visible: Vec<_> = canvas.chunks_in_view(canvas.anchor, view).collect();
+            return generate_chunk_render_commands(
+                &visible,
+                canvas.background_color,
+                self.viewport.scale,
+                self.viewport.offset_x,
+                self.viewport.offset_y,
+            );
+        }
+        match self.page_layout {
+            PageLayout::Single => generate_page_render_commands(
+                self.current_page(),
+                self.viewport.scale,
+                self.viewport.offset_x,
+                self.viewport.offset_y,
+                self.text_measure.as_ref(),
+            ),
+            PageLayout::ContinuousVertical { background, .. } => {
+                let origins = self.page_layout.page_origins(&self.pages);
+                let placed: Vec<(&Page, Point)> = self.pages.iter().zip(origins).collect();
+                generate_multi_page_render_commands(
+                    &placed,
+                    background,
+                    self.viewport.scale,
+                    self.viewport.offset_x,
+                    self.viewport.offset_y,
+                    self.text_measure.as_ref(),
+                )
+            }
+        }
+    }
+
+    /// Render the first page scaled to fit within `max_width`×`max_height`, for previews.
+    /// On an infinite canvas all content is fitted instead.
+    pub fn thumbnail_render(&self, max_width: f64, max_height: f64) -> Vec<RenderCommand> {
+        if let Some(canvas) = &self.infinite {
+            let bounds = canvas.content_bounds(canvas.anchor);
+            if !bounds.is_valid() {
+                return generate_chunk_render_commands(&[], canvas.background_color, 1.0, 0.0, 0.0);
+            }
+            let scale = (max_width / bounds.width()).min(max_height / bounds.height());
+            let all: Vec<_> = canvas
+                .chunks()
+                .iter()
+                .map(|c| (c, canvas.anchor.offset_to(c.coord)))
+                .collect();
+            return generate_chunk_render_commands(
+                &all,
+                canvas.background_color,
+                scale,
+                -bounds.min_x * scale,
+                -bounds.min_y * scale,
+            );
+        }
+        let page = &self.pages[0];
+        let scale = (max_width / page.width).min(max_height / page.height);
+        generate_page_render_commands(page, scale, 0.0, 0.0, self.text_measure.as_ref())
+    }
+
+    // --- Serialization ---
+
+    pub fn save(&self) -> Result<String, EngineError> {
+        let data = DocumentData {
+            version: CURRENT_VERSION,
+            pages: self.pages.clone(),
+            current_page_index: self.current_page_index,
+            infinite: self.infinite.clone(),
+        };
+        data.save_to_json()
+    }
+
+    /// Load a document, rejecting it with `EngineError::InvalidDocument` if validation fails.
+    pub fn load(&mut self, json: &str) -> Result<(), EngineError> {
+        let data = DocumentData::load_from_json(json)?;
+        let report = validate_document(&data);
+        if !report.is_valid() {
+            return Err(EngineError::InvalidDocument {
+                issues: report.issues,
+            });
+        }
+        self.apply_document(data);
+        Ok(())
+    }
+
+    /// Load a document, repairing what can be repaired. Returns the issues that were fixed.
+    pub fn load_repaired(&mut self, json: &str) -> Result<ValidationReport, EngineError> {
+        let mut data = DocumentData::load_from_json(json)?;
+        let report = repair_document(&mut data);
+        self.apply_document(data);
+        Ok(report)
+    }
+
+    // --- Journal ---
+
+    /// Enable or disable recording of document mutations for autosave. Enabling records
+    /// the current pages first, so a journal replays even without a snapshot.
+    pub fn set_journaling(&mut self, enabled: bool) {
+        let starting = enabled && !self.journal.is_enabled();
+        self.journal.set_enabled(enabled);
+        if starting {
+            for (index, page) in self.pages.iter().enumerate() {
+                self.journal.record(JournalEntry::InsertPage {
+                    index,
+                    page: page.clone(),
+                });
+            }
+        }
+    }
+
+    /// Take the mutations recorded since the last drain as JSON lines for the journal file.
+    pub fn drain_journal(&mut self) -> Result<String, EngineError> {
+        self.journal.drain()
+    }
+
+    /// Restore from the last snapshot (if any) and replay the journal written since.
+    /// Without a snapshot, replay starts from no pages and rebuilds them from the journal;
+    /// if it holds none, the result is a single blank page of the current page's size.
+    pub fn recover(
+        &mut self,
+        snapshot_json: Option<&str>,
+        journal: &str,
+    ) -> Result<RecoveryReport, EngineError> {
+        let blank = Page::new(self.current_page().width, self.current_page().height);
+        let repair = match snapshot_json {
+            Some(json) => self.load_repaired(json)?,
+            None => {
+                self.pages.clear();
+                self.current_page_index = 0;
+                if self.infinite.is_some() {
+                    self.infinite = Some(InfiniteCanvas::new());
+                }
+                self.history.clear();
+                ValidationReport::default()
+            }
+        };
+        let (entries, corrupt_lines) = parse_journal(journal);
+        let mut report = RecoveryReport {
+            corrupt_lines,
+            repair,
+            ..Default::default()
+        };
+        for entry in &entries {
+            let applied = match entry {
+                JournalEntry::ImportImage { image_id, bytes } => {
+                    self.restore_image(*image_id, bytes)
+                }
+                _ => entry.apply(&mut self.pages, self.infinite.as_mut()),
+            };
+            if applied {
+                report.applied += 1;
+            } else {
+                report.skipped += 1;
+            }
+        }
+        if self.pages.is_empty() {
+            self.pages.push(blank);
+        }
+        self.current_page_index = self.current_page_index.min(self.pages.len() - 1);
+        report.missing_images = self.missing_images();
+        Ok(report)
+    }
+
+    /// Re-import journaled image bytes under their original id. `false` if the engine
+    /// already has them or they don't parse.
+    fn restore_image(&mut self, image_id: Uuid, bytes: &[u8]) -> bool {
+        if self.images.contains_key(&image_id) {
+            return false;
+        }
+        let Ok(mut data) = ImageData::from_bytes(bytes.to_vec()) else {
+            return false;
+        };
+        data.id = image_id;
+        self.images.insert(image_id, data);
+        true
+    }
+
+    /// Ids of placed images with no stored bytes, in id order.
+    fn missing_images(&self) -> Vec<Uuid> {
+        let placed: BTreeSet<Uuid> = self
+            .pages
+            .iter()
+            .flat_map(|p| &p.layer_manager.layers)
+            .flat_map(|l| &l.images)
+            .map(|i| i.image_id)
+            .collect();
+        placed
+            .into_iter()
+            .filter(|id| !self.images.contains_key(id))
+            .collect()
+    }
+
+    /// Save as a single-file package. `thumbnail` is an encoded image (e.g. PNG) the host
+    /// rasterized from `thumbnail_render`; it may be empty.
+    pub fn save_package(
+        &self,
+        metadata: &DocumentMetadata,
+        thumbnail: &[u8],
+    ) -> Result<Vec<u8>, EngineError> {
+        let metadata_json =
+            serde_json::to_vec(metadata).map_err(|e| EngineError::Serialization {
+                message: e.to_string(),
+            })?;
+        let mut writer = PackageWriter::new();
+        writer.add_entry(METADATA_ENTRY, metadata_json);
+        if !thumbnail.is_empty() {
+            writer.add_entry(THUMBNAIL_ENTRY, thumbnail.to_vec());
+        }
+        writer.add_entry(DOCUMENT_ENTRY, self.save()?.into_bytes());
+        // Only images still placed somewhere; imports that were never placed or whose
+        // placements were all removed are dropped.
+        let placed: BTreeSet<Uuid> = self
+            .pages
+            .iter()
+            .flat_map(|p| &p.layer_manager.layers)
+            .flat_map(|l| &l.images)
+            .map(|i| i.image_id)
+            .collect();
+        for id in placed {
+            if let Some(data) = self.images.get(&id) {
+                writer.add_entry(image_entry_name(id), data.bytes.clone());
+            }
+        }
+        Ok(writer.finish())
+    }
+
+    /// Load a package written by `save_package`, returning its metadata.
+    pub fn load_package(&mut self, bytes: &[u8]) -> Result<DocumentMetadata, EngineError> {
+        let reader = PackageReader::new(bytes)?;
+        let metadata = reader.metadata()?;
+        let mut images = HashMap::new();
+        for (id, bytes) in reader.images() {
+            let mut data = ImageData::from_bytes(bytes.to_vec())?;
+            data.id = id;
+            images.insert(id, data);
+        }
+        self.load(reader.document_json()?)?;
+        self.images = images;
+        Ok(metadata)
+    }
+
+    fn apply_document(&mut self, data: DocumentData) {
+        self.pages = data.pages;
+        for page in &mut self.pages {
+            let layers = &mut page.layer_manager;
+            if layers.layers.is_empty() {
+                *layers = LayerManager::new();
+            }
+            layers.active_layer_index = layers.active_layer_index.min(layers.layers.len() - 1);
+        }
+        self.current_page_index = data.current_page_index.min(self.pages.len() - 1);
+        self.infinite = data.infinite;
+        if let Some(canvas) = &mut self.infinite {
+            canvas.normalize();
+        }
+        self.active_builder = None;
+        self.active_target = None;
+        self.shape_edit = None;
+        self.history.clear();
+    }
+
+    // --- Info ---
+
+    /// Total number of strokes across all pages and infinite-canvas chunks.
+    pub fn stroke_count(&self) -> usize {
+        let chunk_strokes = self.infinite.as_ref().map_or(0, InfiniteCanvas::stroke_count);
+        self.pages.iter().map(Page::stroke_count).sum::<usize>() + chunk_strokes
+    }
+
+    pub fn active_layer_id(&self) -> Uuid {
+        self.current_page().layer_manager.active_layer_id()
+    }
+
+    pub fn set_active_layer(&mut self, layer_id: Uuid) -> Result<(), EngineError> {
+        let page = self.current_page_mut();
+        page.layer_manager.set_active_layer(layer_id)?;
+        let page_id = page.id;
+        self.journal
+            .record(JournalEntry::SetActiveLayer { page_id, layer_id });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brush::BrushConfig;
+    use crate::dash::{DashPattern, LineCap};
+    use crate::infinite::CHUNK_SIZE;
+    use crate::nib::NibSettings;
+    use crate::point::PointerType;
+    use crate::recognition::ShapeSnapping;
+    use crate::shape::ShapeGeometry;
+    use crate::stamp::StampSettings;
+
+    #[test]
+    fn test_engine_new() {
+        let engine = DrawEngine::new(1920.0, 1080.0);
+        assert_eq!(engine.stroke_count(), 0);
+        assert!(!engine.can_undo());
+    }
+
+    #[test]
+    fn test_draw_stroke() {
+        let mut engine = DrawEngine::new(1920.0, 1080.0);
+        engine.set_brush(BrushConfig::pen(Color::black(), 3.0));
+
+        engine.begin_stroke(100.0, 100.0, 0.5, 0.0);
+        engine.add_point(110.0, 105.0, 0.6, 0.016);
+        engine.add_point(120.0, 110.0, 0.7, 0.032);
+        engine.add_point(130.0, 108.0, 0.6, 0.048);
+        let cmds = engine.end_stroke();
+
+        assert_eq!(engine.stroke_count(), 1);
+        assert!(engine.can_undo());
+        assert!(!cmds.is_empty());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut engine = DrawEngine::new(1920.0, 1080.0);
 
         engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
         engine.add_point(20.0, 20.0, 0.5, 0.016);
@@ -325,7 +1887,292 @@ mod tests {
         let mut engine2 = DrawEngine::new(800.0, 600.0);
         engine2.load(&json).unwrap();
         assert_eq!(engine2.stroke_count(), 1);
-        assert!((engine2.canvas_width - 1920.0).abs() < 1e-9);
+        assert!((engine2.current_page().width - 1920.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_load_invalid_json_reports_location() {
+        let mut engine = DrawEngine::new(1920.0, 1080.0);
+        match engine.load("{\"version\": 1,\n  \"width\": }") {
+            Err(EngineError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("Expected Parse, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_then_repairs_corrupt_document() {
+        let mut engine = DrawEngine::new(1920.0, 1080.0);
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(20.0, 20.0, 0.5, 0.016);
+        engine.end_stroke();
+        engine.pages[0].layer_manager.layers[0].strokes[0].bounding_box.min_x = -1000.0;
+        let json = engine.save().unwrap();
+
+        let mut engine2 = DrawEngine::new(800.0, 600.0);
+        assert!(matches!(
+            engine2.load(&json),
+            Err(EngineError::InvalidDocument { .. })
+        ));
+        let report = engine2.load_repaired(&json).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(engine2.stroke_count(), 1);
+    }
+
+    #[test]
+    fn test_out_of_range_input_saves_a_loadable_document() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let mut brush = BrushConfig::pen(Color::new(1.05, -0.1, 0.5, 1.0), f64::NAN);
+        brush.smoothing = 3.0;
+        engine.set_brush(brush);
+        assert_eq!(engine.current_brush().color, Color::new(1.0, 0.0, 0.5, 1.0));
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(40.0, 40.0, 0.5, 0.016);
+        engine.end_stroke();
+
+        let circle = ShapeGeometry::Ellipse {
+            center: Point::new(100.0, 100.0),
+            radius_x: 20.0,
+            radius_y: 20.0,
+            rotation: 0.0,
+        };
+        let mut shape = Shape::new(circle, Color::new(2.0, 0.0, 0.0, 1.0), 1.0);
+        shape.fill_color = Some(Color::new(0.0, f32::NAN, 0.0, 1.0));
+        engine.add_shape(shape).unwrap();
+        let mut text = TextBox::new(Point::new(50.0, 300.0), None, "hi");
+        text.color = Color::new(0.0, 0.0, 0.0, 1.5);
+        engine.add_text(text).unwrap();
+        let page_id = engine.current_page().id;
+        engine
+            .set_page_background(page_id, Color::new(-1.0, 1.0, 1.0, 1.0), PageTemplate::Blank)
+            .unwrap();
+        let bad_grid = PageTemplate::Grid {
+            spacing: 0.0,
+            line_color: Color::black(),
+            line_width: 1.0,
+        };
+        assert!(engine
+            .set_page_background(page_id, Color::white(), bad_grid)
+            .is_err());
+
+        let json = engine.save().unwrap();
+        let mut engine2 = DrawEngine::new(800.0, 600.0);
+        engine2.load(&json).unwrap();
+        assert_eq!(engine2.stroke_count(), 1);
+    }
+
+    #[test]
+    fn test_recover_without_snapshot_rebuilds_pages() {
+        let mut engine = DrawEngine::new(1920.0, 1080.0);
+        engine.begin_stroke(5.0, 5.0, 0.5, 0.0);
+        engine.add_point(15.0, 15.0, 0.5, 0.016);
+        engine.end_stroke();
+        engine.set_journaling(true);
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.1);
+        engine.add_point(20.0, 20.0, 0.5, 0.116);
+        engine.end_stroke();
+        let page_id = engine.current_page().id;
+        let journal = engine.drain_journal().unwrap();
+        drop(engine);
+
+        let mut recovered = DrawEngine::new(1920.0, 1080.0);
+        let report = recovered.recover(None, &journal).unwrap();
+        assert_eq!(recovered.page_count(), 1);
+        assert_eq!(recovered.current_page().id, page_id);
+        // The stroke drawn before journaling comes back with the initial page.
+        assert_eq!(recovered.stroke_count(), 2);
+        assert_eq!(report.applied, 2);
+        assert_eq!(report.skipped, 0);
+
+        let mut empty = DrawEngine::new(1920.0, 1080.0);
+        empty.recover(None, "").unwrap();
+        assert_eq!(empty.page_count(), 1);
+        assert_eq!(empty.stroke_count(), 0);
+    }
+
+    #[test]
+    fn test_recover_replays_journal_onto_snapshot() {
+        let mut engine = DrawEngine::new(1920.0, 1080.0);
+        engine.set_journaling(true);
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(20.0, 20.0, 0.5, 0.016);
+        engine.end_stroke();
+        let snapshot = engine.save().unwrap();
+        let mut journal = engine.drain_journal().unwrap();
+
+        engine.begin_stroke(50.0, 50.0, 0.5, 0.1);
+        engine.add_point(60.0, 60.0, 0.5, 0.116);
+        engine.end_stroke();
+        engine.undo();
+        engine.redo();
+        journal.push_str(&engine.drain_journal().unwrap());
+
+        let mut recovered = DrawEngine::new(1920.0, 1080.0);
+        let report = recovered.recover(Some(&snapshot), &journal).unwrap();
+        assert_eq!(recovered.stroke_count(), 2);
+        // The initial page and first stroke are already in the snapshot.
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.applied, 3);
+        assert_eq!(report.corrupt_lines, 0);
+    }
+
+    #[test]
+    fn test_package_roundtrip() {
+        let mut engine = DrawEngine::new(1920.0, 1080.0);
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(20.0, 20.0, 0.5, 0.016);
+        engine.end_stroke();
+        let metadata = DocumentMetadata {
+            title: "Sketch".to_string(),
+            ..Default::default()
+        };
+        let bytes = engine.save_package(&metadata, &[1, 2, 3]).unwrap();
+
+        let reader = PackageReader::new(&bytes).unwrap();
+        assert_eq!(reader.metadata().unwrap().title, "Sketch");
+        assert_eq!(reader.thumbnail(), &[1, 2, 3]);
+
+        let mut engine2 = DrawEngine::new(800.0, 600.0);
+        let loaded = engine2.load_package(&bytes).unwrap();
+        assert_eq!(loaded, metadata);
+        assert_eq!(engine2.stroke_count(), 1);
+    }
+
+    #[test]
+    fn test_thumbnail_render_fits() {
+        let engine = DrawEngine::new(1000.0, 500.0);
+        let cmds = engine.thumbnail_render(200.0, 200.0);
+        match cmds[2] {
+            RenderCommand::SetTransform { scale, .. } => assert!((scale - 0.2).abs() < 1e-9),
+            ref other => panic!("Expected SetTransform, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_page_operations() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let first = engine.current_page().id;
+        let second = engine.add_page(800.0, 600.0).unwrap();
+        assert_eq!(engine.page_count(), 2);
+        assert_eq!(engine.current_page().id, first);
+
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(20.0, 20.0, 0.5, 0.016);
+        engine.end_stroke();
+        let copy = engine.duplicate_page(first).unwrap();
+        assert_eq!(engine.pages[1].id, copy);
+        assert_eq!(engine.stroke_count(), 2);
+
+        engine.move_page(second, 0).unwrap();
+        assert_eq!(engine.pages[0].id, second);
+        assert_eq!(engine.current_page().id, first);
+
+        engine.remove_page(first).unwrap();
+        engine.remove_page(copy).unwrap();
+        assert_eq!(
+            engine.remove_page(second),
+            Err(EngineError::CannotRemoveLastPage)
+        );
+        assert!(matches!(
+            engine.set_current_page(first),
+            Err(EngineError::PageNotFound { .. })
+        ));
+
+
+        // Undo brings removed pages back in place, with their strokes.
+        engine.undo();
+        engine.undo();
+        let ids: Vec<_> = engine.pages.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![second, first, copy]);
+        assert_eq!(engine.stroke_count(), 2);
+        assert_eq!(engine.current_page().id, second);
+
+        engine.undo();
+        let ids: Vec<_> = engine.pages.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![first, copy, second]);
+        engine.redo();
+        assert_eq!(engine.pages[0].id, second);
+
+        engine.undo();
+        engine.undo();
+        assert_eq!(engine.page_count(), 2);
+        assert_eq!(engine.stroke_count(), 1);
+    }
+
+    #[test]
+    fn test_add_page_copies_settings_and_undoes() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let first = engine.current_page().id;
+        engine
+            .set_page_background(first, Color::from_hex(0xFFF8E0), PageTemplate::ruled(24.0))
+            .unwrap();
+        let second = engine.add_page(400.0, 300.0).unwrap();
+        let page = &engine.pages[1];
+        assert_eq!(page.id, second);
+        assert_eq!(page.background_color, Color::from_hex(0xFFF8E0));
+        assert_eq!(page.template, PageTemplate::ruled(24.0));
+
+        engine.set_current_page(second).unwrap();
+        engine.undo();
+        assert_eq!(engine.page_count(), 1);
+        assert_eq!(engine.current_page().id, first);
+        engine.redo();
+        assert_eq!(engine.pages[1].id, second);
+    }
+
+    #[test]
+    fn test_continuous_layout_routes_input_to_page() {
+        let mut engine = DrawEngine::new(100.0, 100.0);
+        let second = engine.add_page(100.0, 100.0).unwrap();
+        engine.set_page_layout(PageLayout::ContinuousVertical {
+            gap: 20.0,
+            background: Color::black(),
+        });
+
+        engine.begin_stroke(10.0, 130.0, 0.5, 0.0);
+        let cmds = engine.add_point(20.0, 140.0, 0.5, 0.016);
+        engine.end_stroke();
+        assert_eq!(engine.current_page().id, second);
+        assert_eq!(engine.pages[1].stroke_count(), 1);
+
+        // Stored page-local, emitted incrementally in canvas space.
+        let stroke = &engine.pages[1].layer_manager.layers[0].strokes[0];
+        assert!((stroke.points[0].position.y - 10.0).abs() < 1e-9);
+        match &cmds[0] {
+            RenderCommand::DrawVariableWidthPath { segments, .. } => {
+                assert!((segments[0].p0.y - 130.0).abs() < 1e-9);
+            }
+            other => panic!("Expected DrawVariableWidthPath, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_save_load_multi_page() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let second = engine.add_page(400.0, 300.0).unwrap();
+        engine.set_current_page(second).unwrap();
+        let json = engine.save().unwrap();
+
+        let mut engine2 = DrawEngine::new(100.0, 100.0);
+        engine2.load(&json).unwrap();
+        assert_eq!(engine2.page_count(), 2);
+        assert_eq!(engine2.current_page().id, second);
+    }
+
+    #[test]
+    fn test_page_template_persists() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let page_id = engine.current_page().id;
+        let cmds = engine
+            .set_page_background(page_id, Color::white(), PageTemplate::ruled(24.0))
+            .unwrap();
+        assert!(cmds
+            .iter()
+            .any(|c| matches!(c, RenderCommand::DrawLines { .. })));
+
+        let json = engine.save().unwrap();
+        let mut engine2 = DrawEngine::new(100.0, 100.0);
+        engine2.load(&json).unwrap();
+        assert_eq!(engine2.current_page().template, PageTemplate::ruled(24.0));
     }
 
     #[test]
@@ -351,4 +2198,493 @@ mod tests {
         engine.undo();
         assert_eq!(engine.stroke_count(), 1);
     }
+
+    #[test]
+    fn test_infinite_canvas_far_pan_stays_precise() {
+        let mut engine = DrawEngine::new_infinite(800.0, 600.0);
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        brush.smoothing = 0.0;
+        engine.set_brush(brush);
+        // Pan roughly a billion units; the viewport offset must stay within a chunk.
+        for _ in 0..1000 {
+            engine.pan(-1_000_000.0, 0.0);
+        }
+        let (ox, _) = engine.get_offset();
+        assert!(ox.abs() <= CHUNK_SIZE);
+        let anchor = engine.canvas_anchor().unwrap();
+        assert!(anchor.x > 900_000);
+
+        engine.begin_stroke(100.0, 100.0, 0.5, 0.0);
+        engine.add_point(100.25, 100.0, 0.5, 0.016);
+        engine.add_point(100.5, 100.0, 0.5, 0.032);
+        let cmds = engine.end_stroke();
+        assert_eq!(engine.stroke_count(), 1);
+        assert!(cmds
+            .iter()
+            .any(|c| matches!(c, RenderCommand::DrawVariableWidthPath { .. })));
+
+        // Sub-point input survives intact because it is stored chunk-local.
+        let canvas = engine.infinite.as_ref().unwrap();
+        let stroke = &canvas.chunks()[0].strokes[0];
+        let dx = stroke.points[1].position.x - stroke.points[0].position.x;
+        assert!((dx - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_infinite_canvas_culls_and_roundtrips() {
+        let mut engine = DrawEngine::new_infinite(800.0, 600.0);
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(20.0, 20.0, 0.5, 0.016);
+        engine.end_stroke();
+        engine.pan(-CHUNK_SIZE * 10.0, 0.0);
+        let cmds = engine.full_render();
+        assert!(!cmds
+            .iter()
+            .any(|c| matches!(c, RenderCommand::DrawVariableWidthPath { .. })));
+
+        engine.undo();
+        assert_eq!(engine.stroke_count(), 0);
+        engine.redo();
+        let json = engine.save().unwrap();
+
+        let mut engine2 = DrawEngine::new(100.0, 100.0);
+        engine2.load(&json).unwrap();
+        assert!(engine2.is_infinite());
+        assert_eq!(engine2.stroke_count(), 1);
+        assert_eq!(engine2.canvas_anchor(), engine.canvas_anchor());
+    }
+
+    #[test]
+    fn test_infinite_canvas_eraser() {
+        let mut engine = DrawEngine::new_infinite(800.0, 600.0);
+        engine.set_brush(BrushConfig::pen(Color::black(), 3.0));
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(20.0, 10.0, 0.5, 0.016);
+        engine.add_point(30.0, 10.0, 0.5, 0.032);
+        engine.end_stroke();
+
+        engine.set_brush(BrushConfig::eraser(20.0));
+        engine.begin_stroke(15.0, 10.0, 0.5, 0.1);
+        engine.add_point(25.0, 10.0, 0.5, 0.116);
+        engine.end_stroke();
+        assert_eq!(engine.stroke_count(), 0);
+
+        engine.undo();
+        assert_eq!(engine.stroke_count(), 1);
+    }
+
+    #[test]
+    fn test_prediction_is_appended_to_add_point() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        brush.smoothing = 0.0;
+        engine.set_brush(brush);
+        engine.set_prediction_lookahead(0.02);
+
+        engine.begin_stroke(0.0, 0.0, 0.5, 0.0);
+        let cmds = engine.add_point(10.0, 0.0, 0.5, 0.01);
+        match cmds.last() {
+            Some(RenderCommand::DrawPrediction { segments, .. }) => {
+                assert_eq!(segments.last().unwrap().p3, Point::new(30.0, 0.0));
+            }
+            other => panic!("Expected DrawPrediction, got {other:?}"),
+        }
+        let predicted = engine.predicted_points();
+        assert!((predicted.last().unwrap().position.x - 30.0).abs() < 1e-9);
+
+        // Predictions are never stored.
+        engine.end_stroke();
+        let stroke = &engine.current_page().layer_manager.layers[0].strokes[0];
+        assert_eq!(stroke.points.len(), 2);
+        assert!(engine.predicted_points().is_empty());
+    }
+
+    #[test]
+    fn test_add_points_merges_batch() {
+        let points: Vec<StrokePoint> = (1..=8)
+            .map(|i| StrokePoint::new(i as f64 * 5.0, (i % 3) as f64, 0.5, i as f64 * 0.004))
+            .collect();
+
+        let mut single = DrawEngine::new(800.0, 600.0);
+        single.begin_stroke(0.0, 0.0, 0.5, 0.0);
+        for p in &points {
+            single.add_point(p.position.x, p.position.y, p.pressure, p.timestamp);
+        }
+        single.end_stroke();
+
+        let mut batched = DrawEngine::new(800.0, 600.0);
+        batched.begin_stroke(0.0, 0.0, 0.5, 0.0);
+        batched.add_points(&points[..2]);
+        let cmds = batched.add_points(&points[2..]);
+        assert_eq!(cmds.len(), 1);
+        match &cmds[0] {
+            // Six new segments plus the refined one before the batch.
+            RenderCommand::DrawVariableWidthPath { segments, .. } => assert_eq!(segments.len(), 7),
+            other => panic!("Expected DrawVariableWidthPath, got {other:?}"),
+        }
+        batched.end_stroke();
+
+        let a = &single.current_page().layer_manager.layers[0].strokes[0];
+        let b = &batched.current_page().layer_manager.layers[0].strokes[0];
+        assert_eq!(a.points, b.points);
+        assert_eq!(a.segments.len(), b.segments.len());
+    }
+
+    #[test]
+    fn test_tilt_and_pointer_type_persist() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let pen = |x: f64, t: f64| {
+            StrokePoint::new(x, 10.0, 0.5, t)
+                .with_tilt(0.4, 1.0)
+                .with_pointer_type(PointerType::Pen)
+        };
+        engine.begin_stroke_input(pen(0.0, 0.0));
+        engine.add_points(&[pen(10.0, 0.01), pen(20.0, 0.02)]);
+        engine.end_stroke();
+
+        let stroke = &engine.current_page().layer_manager.layers[0].strokes[0];
+        assert!(stroke.points.iter().all(|p| p.altitude == Some(0.4)
+            && p.azimuth == Some(1.0)
+            && p.pointer_type == PointerType::Pen));
+        let json = serde_json::to_string(stroke).unwrap();
+        let loaded: Stroke = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.points, stroke.points);
+    }
+
+    #[test]
+    fn test_device_pressure_curve_normalizes_input() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let samples: Vec<f64> = (0..64).map(|i| 0.1 + 0.2 * i as f64 / 63.0).collect();
+        assert!(!engine.calibrate_device_pressure(&samples[..4]));
+        assert!(engine.device_pressure_curve().is_linear());
+        assert!(engine.calibrate_device_pressure(&samples));
+
+        engine.begin_stroke(0.0, 0.0, 0.3, 0.0);
+        engine.add_point(10.0, 0.0, 0.3, 0.01);
+        engine.end_stroke();
+        let stroke = &engine.current_page().layer_manager.layers[0].strokes[0];
+        assert!(stroke.points.iter().all(|p| p.pressure > 0.95));
+    }
+
+    #[test]
+    fn test_apply_brush_preset() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let preset = engine.brush_presets().presets()[0].clone();
+        engine.apply_brush_preset(preset.id).unwrap();
+        assert_eq!(engine.current_brush(), &preset.brush);
+
+        engine.brush_presets_mut().remove(preset.id).unwrap();
+        assert!(matches!(
+            engine.apply_brush_preset(preset.id),
+            Err(EngineError::PresetNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_stamp_brush_renders_stamps() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let settings = StampSettings {
+            flow: 0.2,
+            ..StampSettings::default()
+        };
+        engine.set_brush(BrushConfig::stamp(Color::black(), 6.0, settings));
+        engine.begin_stroke(0.0, 0.0, 0.5, 0.0);
+        engine.add_point(10.0, 0.0, 0.5, 0.016);
+        let cmds = engine.add_point(20.0, 0.0, 0.5, 0.032);
+        let incremental = match &cmds[..] {
+            [RenderCommand::DrawStamps { stamps, .. }] => stamps.clone(),
+            other => panic!("Expected DrawStamps, got {other:?}"),
+        };
+        assert!(incremental.iter().all(|s| (s.color.a - 0.2).abs() < 1e-6));
+
+        let full = engine.end_stroke();
+        let stamps = full
+            .iter()
+            .find_map(|c| match c {
+                RenderCommand::DrawStamps { stamps, .. } => Some(stamps),
+                _ => None,
+            })
+            .expect("full render should draw the stamp stroke");
+        assert!(stamps.len() > incremental.len());
+        assert!(!full
+            .iter()
+            .any(|c| matches!(c, RenderCommand::DrawVariableWidthPath { .. })));
+    }
+
+    #[test]
+    fn test_calligraphy_brush_fills_outline() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        engine.set_brush(BrushConfig::calligraphy(Color::black(), 8.0, NibSettings::default()));
+        engine.begin_stroke(0.0, 0.0, 0.5, 0.0);
+        engine.add_point(10.0, 0.0, 0.5, 0.016);
+        let cmds = engine.add_point(20.0, 5.0, 0.5, 0.032);
+        assert!(matches!(
+            &cmds[..],
+            [RenderCommand::FillPolygons { polygons, .. }] if !polygons.is_empty()
+        ));
+
+        let full = engine.end_stroke();
+        assert!(full
+            .iter()
+            .any(|c| matches!(c, RenderCommand::FillPolygons { .. })));
+        assert!(!full
+            .iter()
+            .any(|c| matches!(c, RenderCommand::DrawVariableWidthPath { .. })));
+    }
+
+    #[test]
+    fn test_dashed_brush_draws_dashes() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        brush.dash = Some(DashPattern::dashed());
+        brush.line_cap = LineCap::Butt;
+        engine.set_brush(brush);
+        engine.begin_stroke(0.0, 0.0, 0.5, 0.0);
+        for i in 1..=10 {
+            engine.add_point(i as f64 * 10.0, 0.0, 0.5, i as f64 * 0.016);
+        }
+        let full = engine.end_stroke();
+        let (dashes, cap) = full
+            .iter()
+            .find_map(|c| match c {
+                RenderCommand::DrawDashedPath { dashes, cap, .. } => Some((dashes, *cap)),
+                _ => None,
+            })
+            .expect("full render should draw the dashed stroke");
+        // Dashes of 6 and gaps of 4 along a stroke about 100 long.
+        assert!((9..=11).contains(&dashes.len()));
+        assert_eq!(cap, LineCap::Butt);
+    }
+
+    #[test]
+    fn test_shape_snapping_keeps_original_for_undo() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        engine.set_shape_snapping(ShapeSnapping::OnHold);
+        let draw_line = |engine: &mut DrawEngine, hold: bool| {
+            engine.begin_stroke(100.0, 100.0, 0.5, 0.0);
+            for i in 1..=20 {
+                let wobble = if i % 2 == 0 { 1.0 } else { -1.0 };
+                engine.add_point(100.0 + i as f64 * 10.0, 100.0 + wobble, 0.5, i as f64 * 0.016);
+            }
+            if hold {
+                engine.add_point(300.0, 100.0, 0.5, 1.0);
+            }
+            engine.end_stroke();
+        };
+
+        // Without a hold the stroke stays as drawn.
+        draw_line(&mut engine, false);
+        let strokes = &engine.current_page().layer_manager.active_layer().strokes;
+        assert!(strokes[0].segments.len() > 1);
+
+        draw_line(&mut engine, true);
+        let strokes = &engine.current_page().layer_manager.active_layer().strokes;
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[1].segments.len(), 1);
+        let raw_len = strokes[0].segments.len();
+
+        engine.undo();
+        let strokes = &engine.current_page().layer_manager.active_layer().strokes;
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[1].segments.len(), raw_len + 1);
+        engine.redo();
+        let strokes = &engine.current_page().layer_manager.active_layer().strokes;
+        assert_eq!(strokes[1].segments.len(), 1);
+    }
+
+    #[test]
+    fn test_shape_handle_drag_is_one_undo_step() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        let rect = ShapeGeometry::Rectangle {
+            center: Point::new(100.0, 100.0),
+            width: 40.0,
+            height: 20.0,
+            rotation: 0.0,
+        };
+        let id = engine
+            .add_shape(Shape::new(rect.clone(), Color::black(), 2.0))
+            .unwrap();
+        assert_eq!(engine.shape_at(Point::new(80.0, 100.0), 1.0), Some(id));
+        assert_eq!(engine.shape_at(Point::new(100.0, 100.0), 1.0), None);
+
+        engine.move_shape_handle(id, 2, Point::new(130.0, 115.0)).unwrap();
+        engine.move_shape_handle(id, 2, Point::new(160.0, 130.0)).unwrap();
+        engine.finish_shape_edit();
+        let resized = engine.shape(id).unwrap().geometry.clone();
+        assert_eq!(resized.handles()[0], Point::new(80.0, 90.0));
+        assert_eq!(resized.handles()[2], Point::new(160.0, 130.0));
+
+        let json = engine.save().unwrap();
+        engine.undo();
+        assert_eq!(engine.shape(id).unwrap().geometry, rect);
+        engine.undo();
+        assert!(engine.shape(id).is_none());
+
+        engine.load(&json).unwrap();
+        assert_eq!(engine.shape(id).unwrap().geometry, resized);
+        assert!(matches!(
+            engine.move_shape_handle(id, 9, Point::new(0.0, 0.0)),
+            Err(EngineError::InvalidGeometry { .. })
+        ));
+        assert_eq!(
+            engine.remove_shape(Uuid::nil()),
+            Err(EngineError::ShapeNotFound {
+                shape_id: Uuid::nil()
+            })
+        );
+    }
+
+    #[test]
+    fn test_text_boxes_edit_undo_and_recover() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        engine.set_journaling(true);
+        let snapshot = engine.save().unwrap();
+        let label = TextBox::new(Point::new(100.0, 100.0), Some(200.0), "Mitochondria");
+        let id = engine.add_text(label.clone()).unwrap();
+        assert_eq!(engine.text_at(Point::new(110.0, 110.0), 0.0), Some(id));
+        assert_eq!(engine.text_at(Point::new(110.0, 90.0), 2.0), None);
+
+        let mut edited = label.clone();
+        edited.text.push_str(" (powerhouse)");
+        edited.position = Point::new(150.0, 120.0);
+        engine.update_text(edited.clone()).unwrap();
+        assert_eq!(engine.text(id), Some(&edited));
+        let journal = engine.drain_journal().unwrap();
+
+        engine.undo();
+        assert_eq!(engine.text(id), Some(&label));
+        engine.remove_text(id).unwrap();
+        assert!(engine.text(id).is_none());
+        assert_eq!(
+            engine.update_text(edited.clone()),
+            Err(EngineError::TextNotFound { text_id: id })
+        );
+
+        engine.recover(Some(&snapshot), &journal).unwrap();
+        assert_eq!(engine.text(id), Some(&edited));
+        let mut bad = edited;
+        bad.font_size = f64::NAN;
+        assert!(matches!(
+            engine.update_text(bad),
+            Err(EngineError::InvalidGeometry { .. })
+        ));
+    }
+
+    #[test]
+    fn test_images_place_undo_and_save_in_package() {
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        // A JPEG frame header for 200×100 pixels.
+        let jpeg = vec![
+            0xFF, 0xD8, 0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0x64, 0x00, 0xC8, 0x01, 0x01,
+            0x11, 0x00,
+        ];
+        let image_id = engine.import_image(jpeg.clone()).unwrap().id;
+        let unplaced = engine.import_image(jpeg.clone()).unwrap().id;
+        let photo = Image::new(engine.image_data(image_id).unwrap(), Point::new(200.0, 200.0));
+        let id = engine.add_image(photo.clone()).unwrap();
+        assert_eq!(engine.image_at(Point::new(290.0, 240.0), 0.0), Some(id));
+
+        let mut cropped = photo.clone();
+        cropped.crop.width = 100.0;
+        cropped.scale = 2.0;
+        engine.update_image(cropped.clone()).unwrap();
+        engine.undo();
+        assert_eq!(engine.image(id), Some(&photo));
+        engine.redo();
+        cropped.crop.x = 150.0;
+        assert!(matches!(
+            engine.update_image(cropped.clone()),
+            Err(EngineError::InvalidGeometry { .. })
+        ));
+
+        let bytes = engine
+            .save_package(&DocumentMetadata::default(), &[])
+            .unwrap();
+        let mut engine2 = DrawEngine::new(800.0, 600.0);
+        engine2.load_package(&bytes).unwrap();
+        assert_eq!(engine2.image(id).unwrap().scale, 2.0);
+        assert_eq!(engine2.image_data(image_id).unwrap().bytes, jpeg);
+        assert!(engine2.image_data(unplaced).is_none());
+    }
+
+    #[test]
+    fn test_recover_restores_journaled_image_bytes() {
+        let jpeg = vec![
+            0xFF, 0xD8, 0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0x64, 0x00, 0xC8, 0x01, 0x01,
+            0x11, 0x00,
+        ];
+        let mut engine = DrawEngine::new(800.0, 600.0);
+        engine.set_journaling(true);
+        let snapshot = engine.save().unwrap();
+        let image_id = engine.import_image(jpeg.clone()).unwrap().id;
+        let photo = Image::new(engine.image_data(image_id).unwrap(), Point::new(20.0, 20.0));
+        engine.add_image(photo).unwrap();
+        let journal = engine.drain_journal().unwrap();
+        let placed_only = engine.save().unwrap();
+        drop(engine);
+
+        let mut recovered = DrawEngine::new(800.0, 600.0);
+        let report = recovered.recover(Some(&snapshot), &journal).unwrap();
+        assert_eq!(report.applied, 2);
+        assert!(report.missing_images.is_empty());
+        assert_eq!(recovered.image_data(image_id).unwrap().bytes, jpeg);
+        assert!(recovered
+            .full_render()
+            .iter()
+            .any(|c| matches!(c, RenderCommand::DrawImage { .. })));
+
+        // Without the journal the placement comes back but its bytes don't.
+        let mut fresh = DrawEngine::new(800.0, 600.0);
+        let report = fresh.recover(Some(&placed_only), "").unwrap();
+        assert_eq!(report.missing_images, vec![image_id]);
+    }
+
+    #[test]
+    fn test_fill_tool_fills_enclosed_area_and_lasso() {
+        use crate::shape::ShapeGeometry;
+
+        let mut engine = DrawEngine::new(400.0, 300.0);
+        let ring = ShapeGeometry::Ellipse {
+            center: Point::new(200.0, 150.0),
+            radius_x: 80.0,
+            radius_y: 60.0,
+            rotation: 0.0,
+        };
+        engine
+            .add_shape(Shape::new(ring, Color::black(), 2.0))
+            .unwrap();
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        engine.set_brush(BrushConfig::pen(red, 3.0));
+
+        assert_eq!(engine.fill_enclosed_area(Point::new(20.0, 20.0)).unwrap(), None);
+        let id = engine
+            .fill_enclosed_area(Point::new(200.0, 150.0))
+            .unwrap()
+            .unwrap();
+        let fill = engine.fill(id).unwrap();
+        assert_eq!(fill.color, red);
+        assert!(fill.contains(Point::new(260.0, 150.0)));
+        assert!(!fill.contains(Point::new(290.0, 150.0)));
+        assert_eq!(engine.fill_at(Point::new(150.0, 150.0)), Some(id));
+
+        engine.undo();
+        assert!(engine.fill(id).is_none());
+
+        let lasso = [
+            Point::new(10.0, 10.0),
+            Point::new(60.0, 10.0),
+            Point::new(35.0, 50.0),
+        ];
+        let lasso_id = engine.fill_lasso(&lasso).unwrap();
+        assert_eq!(engine.fill_at(Point::new(35.0, 20.0)), Some(lasso_id));
+        assert!(matches!(
+            engine.fill_lasso(&lasso[..2]),
+            Err(EngineError::InvalidGeometry { .. })
+        ));
+        engine.remove_fill(lasso_id).unwrap();
+        assert_eq!(
+            engine.remove_fill(lasso_id),
+            Err(EngineError::FillNotFound { fill_id: lasso_id })
+        );
+    }
 }
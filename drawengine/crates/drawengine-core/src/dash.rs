@@ -0,0 +1,254 @@
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::EngineError;
+use crate::geometry::BezierSegment;
+
+/// Straight pieces each segment is split into when measuring arc length.
+const ARC_STEPS: usize = 16;
+
+/// Shortest pattern period in canvas units; anything finer is drawn solid.
+const MIN_DASH_PERIOD: f64 = 0.5;
+
+/// Shape drawn at the open ends of a path and of every dash.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineCap {
+    Butt,
+    #[default]
+    Round,
+    /// Extends past the end by half the width.
+    Square,
+}
+
+/// Shape drawn where two segments of a path meet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineJoin {
+    Miter,
+    #[default]
+    Round,
+    Bevel,
+}
+
+/// Alternating dash and gap lengths, repeated along the stroke.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashPattern {
+    /// Dash, gap, dash, gap… as multiples of the brush's base width. An odd-length list
+    /// is repeated once so dashes and gaps alternate. A zero-length dash is a dot,
+    /// drawn by its line cap.
+    pub intervals: Vec<f64>,
+    /// How far into the pattern the stroke starts, in the same units.
+    #[serde(default)]
+    pub offset: f64,
+}
+
+impl DashPattern {
+    /// Pattern from `intervals`, which must be finite, non-negative and not all zero.
+    pub fn new(intervals: Vec<f64>) -> Result<Self, EngineError> {
+        if intervals.is_empty() || !intervals.iter().all(|v| v.is_finite() && *v >= 0.0) {
+            return Err(EngineError::InvalidGeometry {
+                message: "dash intervals must be finite and non-negative".into(),
+            });
+        }
+        if intervals.iter().sum::<f64>() <= 0.0 {
+            return Err(EngineError::InvalidGeometry {
+                message: "dash pattern has zero length".into(),
+            });
+        }
+        Ok(Self {
+            intervals,
+            offset: 0.0,
+        })
+    }
+
+    pub fn dashed() -> Self {
+        Self {
+            intervals: vec![3.0, 2.0],
+            offset: 0.0,
+        }
+    }
+
+    /// Round dots one width apart; needs `LineCap::Round` to show.
+    pub fn dotted() -> Self {
+        Self {
+            intervals: vec![0.0, 2.0],
+            offset: 0.0,
+        }
+    }
+
+    pub fn dash_dot() -> Self {
+        Self {
+            intervals: vec![3.0, 2.0, 0.0, 2.0],
+            offset: 0.0,
+        }
+    }
+
+    /// Intervals in canvas units with an even count, or `None` if the pattern can't be
+    /// drawn and the stroke should stay solid.
+    fn scaled(&self, unit: f64) -> Option<Vec<f64>> {
+        let mut intervals: Vec<f64> = self.intervals.iter().map(|v| v * unit).collect();
+        if intervals.len() % 2 == 1 {
+            intervals.extend_from_within(..);
+        }
+        let period: f64 = intervals.iter().sum();
+        let usable = intervals.iter().all(|v| v.is_finite() && *v >= 0.0);
+        (usable && period >= MIN_DASH_PERIOD).then_some(intervals)
+    }
+}
+
+/// Split `segments` into dashes by arc length; each dash is a run of (partial) segments
+/// to draw as one open path. `unit` scales the pattern, normally the brush's base width.
+/// Only dashes reaching segments from `from_segment` on are returned, clipped to them,
+/// but the pattern is laid out from the start of the stroke so an incremental update
+/// lines up with a full redraw. An unusable pattern yields the segments as one dash.
+pub fn dash_segments(
+    segments: &[BezierSegment],
+    pattern: &DashPattern,
+    unit: f64,
+    from_segment: usize,
+) -> Vec<Vec<BezierSegment>> {
+    let Some(intervals) = pattern.scaled(unit) else {
+        let tail = &segments[from_segment.min(segments.len())..];
+        return if tail.is_empty() { vec![] } else { vec![tail.to_vec()] };
+    };
+    let period: f64 = intervals.iter().sum();
+
+    let mut index = 0;
+    let mut left = intervals[0];
+    let mut skip = if pattern.offset.is_finite() {
+        (pattern.offset * unit).rem_euclid(period)
+    } else {
+        0.0
+    };
+    while skip > left {
+        skip -= left;
+        index = (index + 1) % intervals.len();
+        left = intervals[index];
+    }
+    left -= skip;
+
+    let mut dashes = Vec::new();
+    let mut current = Vec::new();
+    for (i, seg) in segments.iter().enumerate() {
+        let lengths = arc_lengths(seg);
+        let total = lengths[ARC_STEPS];
+        let visible = i >= from_segment;
+        let mut pos = 0.0;
+        loop {
+            let on = index % 2 == 0;
+            if pos + left > total {
+                if on && visible && pos < total {
+                    current.push(seg.subsegment(param_at(&lengths, pos), 1.0));
+                }
+                left -= total - pos;
+                break;
+            }
+            let end = pos + left;
+            if on {
+                if visible {
+                    current.push(seg.subsegment(param_at(&lengths, pos), param_at(&lengths, end)));
+                }
+                if !current.is_empty() {
+                    dashes.push(mem::take(&mut current));
+                }
+            }
+            pos = end;
+            index = (index + 1) % intervals.len();
+            left = intervals[index];
+        }
+    }
+    if !current.is_empty() {
+        dashes.push(current);
+    }
+    dashes
+}
+
+/// Cumulative arc length at `t = k / ARC_STEPS` for each `k`.
+fn arc_lengths(seg: &BezierSegment) -> [f64; ARC_STEPS + 1] {
+    let mut lengths = [0.0; ARC_STEPS + 1];
+    let mut prev = seg.p0;
+    for step in 1..=ARC_STEPS {
+        let point = seg.evaluate(step as f64 / ARC_STEPS as f64);
+        lengths[step] = lengths[step - 1] + prev.distance_to(&point);
+        prev = point;
+    }
+    lengths
+}
+
+/// Curve parameter at arc length `distance`, interpolating the table linearly.
+fn param_at(lengths: &[f64; ARC_STEPS + 1], distance: f64) -> f64 {
+    let k = lengths[1..].partition_point(|&l| l < distance).min(ARC_STEPS - 1);
+    let span = lengths[k + 1] - lengths[k];
+    let f = if span > 0.0 {
+        ((distance - lengths[k]) / span).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (k as f64 + f) / ARC_STEPS as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point::Point;
+
+    fn line(from: f64, to: f64) -> BezierSegment {
+        let (a, b) = (Point::new(from, 0.0), Point::new(to, 0.0));
+        BezierSegment {
+            p0: a,
+            p1: a.lerp(&b, 1.0 / 3.0),
+            p2: a.lerp(&b, 2.0 / 3.0),
+            p3: b,
+            start_width: 2.0,
+            end_width: 2.0,
+            colors: None,
+        }
+    }
+
+    fn span(dash: &[BezierSegment]) -> (f64, f64) {
+        (dash[0].p0.x, dash[dash.len() - 1].p3.x)
+    }
+
+    #[test]
+    fn test_dashes_span_segment_boundaries() {
+        // Dashes of 6 and gaps of 4 over 0..25, split into two segments at x = 4.
+        let segments = [line(0.0, 4.0), line(4.0, 25.0)];
+        let dashes = dash_segments(&segments, &DashPattern::dashed(), 2.0, 0);
+        assert_eq!(dashes.len(), 3);
+        let (a, b) = span(&dashes[1]);
+        assert!((a - 10.0).abs() < 1e-6 && (b - 16.0).abs() < 1e-6);
+        assert_eq!(dashes[0].len(), 2);
+        let (a, b) = span(&dashes[2]);
+        assert!((a - 20.0).abs() < 1e-6 && (b - 25.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dots_and_offset() {
+        let dots = dash_segments(&[line(0.0, 20.0)], &DashPattern::dotted(), 2.0, 0);
+        assert_eq!(dots.len(), 6);
+        assert!(dots.iter().all(|d| d.len() == 1 && d[0].p0.distance_to(&d[0].p3) < 1e-9));
+
+        let shifted = DashPattern {
+            offset: 1.0,
+            ..DashPattern::dashed()
+        };
+        let dashes = dash_segments(&[line(0.0, 20.0)], &shifted, 2.0, 0);
+        let (a, b) = span(&dashes[0]);
+        assert!(a.abs() < 1e-6 && (b - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_incremental_dashes_match_full_layout() {
+        let segments = [line(0.0, 7.0), line(7.0, 13.0), line(13.0, 30.0)];
+        let full = dash_segments(&segments, &DashPattern::dash_dot(), 1.0, 0);
+        let tail = dash_segments(&segments, &DashPattern::dash_dot(), 1.0, 2);
+        assert!(tail[0][0].p0.x >= 13.0);
+        let full_spans: Vec<_> = full[full.len() - tail.len()..].iter().map(|d| span(d)).collect();
+        let tail_spans: Vec<_> = tail.iter().map(|d| span(d)).collect();
+        assert_eq!(full_spans, tail_spans);
+        assert!(DashPattern::new(vec![0.0, 0.0]).is_err());
+        assert_eq!(dash_segments(&segments, &DashPattern::dashed(), 0.0, 0).len(), 1);
+    }
+}
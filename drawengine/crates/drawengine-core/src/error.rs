@@ -0,0 +1,127 @@
+use std::fmt;
+
+use uuid::Uuid;
+
+use crate::validation::ValidationIssue;
+
+/// Errors produced by the engine core.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineError {
+    /// The document could not be parsed. `line`/`column` are 1-based (0 when unknown).
+    Parse {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// The document was written by a newer format version than this engine understands.
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// Geometry values that cannot be rendered (non-finite or non-positive sizes, etc.).
+    InvalidGeometry { message: String },
+    /// The document parsed but failed validation; see `validation::repair_document`.
+    InvalidDocument { issues: Vec<ValidationIssue> },
+    /// The package container is malformed or missing a required entry.
+    InvalidPackage { message: String },
+    /// No layer with the given id exists in the document.
+    LayerNotFound { layer_id: Uuid },
+    /// No page with the given id exists in the document.
+    PageNotFound { page_id: Uuid },
+    /// A document must keep at least one page.
+    CannotRemoveLastPage,
+    /// The document could not be serialized.
+    Serialization { message: String },
+    /// No brush preset with the given id exists in the library.
+    PresetNotFound { preset_id: Uuid },
+    /// No shape with the given id exists on the current page.
+    ShapeNotFound { shape_id: Uuid },
+    /// No text box with the given id exists on the current page.
+    TextNotFound { text_id: Uuid },
+    /// No filled region with the given id exists on the current page.
+    FillNotFound { fill_id: Uuid },
+    /// No placed image, or no imported image data, with the given id exists.
+    ImageNotFound { image_id: Uuid },
+    /// The bytes aren't a PNG or JPEG image the engine can read.
+    InvalidImage { message: String },
+    /// The operation isn't available for this kind of document (e.g. shapes on an
+    /// infinite canvas).
+    Unsupported { message: String },
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Parse {
+                message,
+                line,
+                column,
+            } => write!(f, "parse error at line {line}, column {column}: {message}"),
+            EngineError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported document version {found} (supported up to {supported})"
+            ),
+            EngineError::InvalidGeometry { message } => write!(f, "invalid geometry: {message}"),
+            EngineError::InvalidDocument { issues } => {
+                write!(f, "invalid document: {} issue(s)", issues.len())
+            }
+            EngineError::InvalidPackage { message } => write!(f, "invalid package: {message}"),
+            EngineError::LayerNotFound { layer_id } => write!(f, "layer not found: {layer_id}"),
+            EngineError::PageNotFound { page_id } => write!(f, "page not found: {page_id}"),
+            EngineError::CannotRemoveLastPage => write!(f, "cannot remove the last page"),
+            EngineError::Serialization { message } => write!(f, "serialization error: {message}"),
+            EngineError::PresetNotFound { preset_id } => {
+                write!(f, "brush preset not found: {preset_id}")
+            }
+            EngineError::ShapeNotFound { shape_id } => write!(f, "shape not found: {shape_id}"),
+            EngineError::TextNotFound { text_id } => write!(f, "text box not found: {text_id}"),
+            EngineError::FillNotFound { fill_id } => write!(f, "fill not found: {fill_id}"),
+            EngineError::ImageNotFound { image_id } => write!(f, "image not found: {image_id}"),
+            EngineError::InvalidImage { message } => write!(f, "invalid image: {message}"),
+            EngineError::Unsupported { message } => write!(f, "unsupported: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<serde_json::Error> for EngineError {
+    fn from(e: serde_json::Error) -> Self {
+        use serde_json::error::Category;
+        match e.classify() {
+            Category::Syntax | Category::Data | Category::Eof => EngineError::Parse {
+                message: e.to_string(),
+                line: e.line(),
+                column: e.column(),
+            },
+            Category::Io => EngineError::Serialization {
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_has_location() {
+        let err: EngineError = serde_json::from_str::<serde_json::Value>("{\n  \"a\": }")
+            .unwrap_err()
+            .into();
+        match err {
+            EngineError::Parse { line, column, .. } => {
+                assert_eq!(line, 2);
+                assert!(column > 0);
+            }
+            other => panic!("Expected Parse, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let err = EngineError::UnsupportedVersion {
+            found: 9,
+            supported: 1,
+        };
+        assert!(err.to_string().contains('9'));
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::geometry::BezierSegment;
+use crate::point::{BoundingBox, Color, Point};
+use crate::recognition::simplify;
+
+/// Most grid cells used to find a region; larger areas use coarser cells.
+const MAX_CELLS: f64 = 1_000_000.0;
+
+/// Smallest grid cell, in page units.
+const MIN_CELL: f64 = 1.0;
+
+/// Gaps in the ink up to this wide (page units) still close a region.
+const GAP_TOLERANCE: f64 = 2.0;
+
+/// How far a detected region is grown under the surrounding ink, in page units, so no
+/// seam shows between the fill and the strokes drawn over it.
+const UNDERLAP: f64 = 3.0;
+
+/// A filled area on a layer, e.g. from the fill tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FillRegion {
+    pub id: Uuid,
+    /// Outlines in page-local coordinates, filled together with the nonzero rule. Holes
+    /// wind the opposite way to the outlines around them.
+    pub polygons: Vec<Vec<Point>>,
+    pub color: Color,
+}
+
+impl FillRegion {
+    pub fn new(polygons: Vec<Vec<Point>>, color: Color) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            polygons,
+            color,
+        }
+    }
+
+    /// At least one polygon, each with three or more finite points.
+    pub fn is_valid(&self) -> bool {
+        !self.polygons.is_empty()
+            && self.polygons.iter().all(|polygon| {
+                polygon.len() >= 3 && polygon.iter().all(|p| p.x.is_finite() && p.y.is_finite())
+            })
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        let mut bb = BoundingBox::empty();
+        for p in self.polygons.iter().flatten() {
+            bb.expand_to_include(p);
+        }
+        bb
+    }
+
+    /// Whether `point` is filled under the nonzero rule.
+    pub fn contains(&self, point: Point) -> bool {
+        self.polygons
+            .iter()
+            .map(|polygon| winding_number(polygon, point))
+            .sum::<i32>()
+            != 0
+    }
+}
+
+/// Outlines of the area around `point` that `barriers` enclose within `bounds`, for
+/// `FillRegion::polygons`. `None` if `point` is on the ink or the area reaches the edge
+/// of `bounds`.
+pub fn enclosed_region(
+    barriers: &[BezierSegment],
+    bounds: BoundingBox,
+    point: Point,
+) -> Option<Vec<Vec<Point>>> {
+    if !bounds.is_valid() || !bounds.contains_point(&point) {
+        return None;
+    }
+    let mut grid = Grid::new(bounds);
+    for segment in barriers {
+        grid.draw_ink(segment);
+    }
+    let start = grid.index_at(point);
+    if grid.cells[start] != Cell::Open || !grid.flood(start) {
+        return None;
+    }
+    grid.grow_into_ink((UNDERLAP / grid.cell).ceil() as usize);
+    let polygons: Vec<_> = grid
+        .outlines()
+        .iter()
+        .map(|outline| simplify_closed(outline, grid.cell))
+        .filter(|polygon| polygon.len() >= 3)
+        .collect();
+    (!polygons.is_empty()).then_some(polygons)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cell {
+    Open,
+    Ink,
+    Filled,
+}
+
+/// Square cells covering the search area, row by row.
+struct Grid {
+    origin: Point,
+    cell: f64,
+    cols: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    fn new(bounds: BoundingBox) -> Self {
+        let cell = (bounds.width() * bounds.height() / MAX_CELLS)
+            .sqrt()
+            .max(MIN_CELL);
+        let cols = ((bounds.width() / cell).ceil() as usize).max(1);
+        let rows = ((bounds.height() / cell).ceil() as usize).max(1);
+        Self {
+            origin: Point::new(bounds.min_x, bounds.min_y),
+            cell,
+            cols,
+            rows,
+            cells: vec![Cell::Open; cols * rows],
+        }
+    }
+
+    fn index_at(&self, p: Point) -> usize {
+        let col = ((p.x - self.origin.x) / self.cell) as usize;
+        let row = ((p.y - self.origin.y) / self.cell) as usize;
+        row.min(self.rows - 1) * self.cols + col.min(self.cols - 1)
+    }
+
+    fn is_filled(&self, col: i64, row: i64) -> bool {
+        (0..self.cols as i64).contains(&col)
+            && (0..self.rows as i64).contains(&row)
+            && self.cells[row as usize * self.cols + col as usize] == Cell::Filled
+    }
+
+    /// Mark the cells the segment's ink covers, widened by half the gap tolerance on
+    /// each side.
+    fn draw_ink(&mut self, segment: &BezierSegment) {
+        let length = segment.p0.distance_to(&segment.p1)
+            + segment.p1.distance_to(&segment.p2)
+            + segment.p2.distance_to(&segment.p3);
+        let steps = ((length / (self.cell * 0.5)).ceil() as usize).max(1);
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            let radius = segment.width_at(t).max(0.0) / 2.0 + GAP_TOLERANCE / 2.0;
+            self.draw_disk(segment.evaluate(t), radius);
+        }
+    }
+
+    fn draw_disk(&mut self, center: Point, radius: f64) {
+        let reach = radius + self.cell * 0.5;
+        let span = |center: f64, origin: f64, count: usize| {
+            let first = ((center - reach - origin) / self.cell).floor().max(0.0) as i64;
+            let last = ((center + reach - origin) / self.cell).floor() as i64;
+            first..=last.min(count as i64 - 1)
+        };
+        let cols = span(center.x, self.origin.x, self.cols);
+        let rows = span(center.y, self.origin.y, self.rows);
+        for row in rows {
+            for col in cols.clone() {
+                let cell_center = self.origin
+                    + Point::new(col as f64 + 0.5, row as f64 + 0.5) * self.cell;
+                if cell_center.distance_to(&center) <= reach {
+                    self.cells[row as usize * self.cols + col as usize] = Cell::Ink;
+                }
+            }
+        }
+    }
+
+    /// Fill the open cells connected to `start`. Returns `false` as soon as the fill
+    /// reaches the edge of the grid.
+    fn flood(&mut self, start: usize) -> bool {
+        self.cells[start] = Cell::Filled;
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            let (col, row) = (index % self.cols, index / self.cols);
+            if col == 0 || row == 0 || col == self.cols - 1 || row == self.rows - 1 {
+                return false;
+            }
+            for next in [index - 1, index + 1, index - self.cols, index + self.cols] {
+                if self.cells[next] == Cell::Open {
+                    self.cells[next] = Cell::Filled;
+                    stack.push(next);
+                }
+            }
+        }
+        true
+    }
+
+    /// Grow the filled area into neighboring ink, one cell per step.
+    fn grow_into_ink(&mut self, steps: usize) {
+        for _ in 0..steps {
+            let grown: Vec<usize> = (0..self.cells.len())
+                .filter(|&i| self.cells[i] == Cell::Ink)
+                .filter(|&i| {
+                    let (col, row) = ((i % self.cols) as i64, (i / self.cols) as i64);
+                    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                        .iter()
+                        .any(|(dc, dr)| self.is_filled(col + dc, row + dr))
+                })
+                .collect();
+            if grown.is_empty() {
+                break;
+            }
+            for i in grown {
+                self.cells[i] = Cell::Filled;
+            }
+        }
+    }
+
+    /// Closed outlines of the filled cells in page coordinates, with the filled side on
+    /// the left of travel, so holes wind the other way.
+    fn outlines(&self) -> Vec<Vec<Point>> {
+        // Boundary edges between grid vertices, keyed by start vertex.
+        let mut edges: BTreeMap<(i64, i64), Vec<(i64, i64)>> = BTreeMap::new();
+        for row in 0..self.rows as i64 {
+            for col in 0..self.cols as i64 {
+                if !self.is_filled(col, row) {
+                    continue;
+                }
+                let sides = [
+                    ((0, -1), (col + 1, row), (col, row)),
+                    ((0, 1), (col, row + 1), (col + 1, row + 1)),
+                    ((-1, 0), (col, row), (col, row + 1)),
+                    ((1, 0), (col + 1, row + 1), (col + 1, row)),
+                ];
+                for ((dc, dr), from, to) in sides {
+                    if !self.is_filled(col + dc, row + dr) {
+                        edges.entry(from).or_default().push(to);
+                    }
+                }
+            }
+        }
+
+        let mut outlines = Vec::new();
+        while let Some(&start) = edges.keys().next() {
+            let mut vertices = vec![start];
+            let (mut at, mut direction) = (start, (0, 0));
+            while let Some(ends) = edges.get_mut(&at) {
+                // Where two outlines touch at a corner, turn toward the filled side so
+                // diagonal neighbors stay separate.
+                let left = (direction.1, -direction.0);
+                let index = ends
+                    .iter()
+                    .position(|end| (end.0 - at.0, end.1 - at.1) == left)
+                    .unwrap_or(0);
+                let end = ends.swap_remove(index);
+                if ends.is_empty() {
+                    edges.remove(&at);
+                }
+                direction = (end.0 - at.0, end.1 - at.1);
+                at = end;
+                if at == start {
+                    break;
+                }
+                vertices.push(at);
+            }
+            let n = vertices.len();
+            let corners = (0..n).filter(|&i| {
+                let (prev, v) = (vertices[(i + n - 1) % n], vertices[i]);
+                let next = vertices[(i + 1) % n];
+                (v.0 - prev.0, v.1 - prev.1) != (next.0 - v.0, next.1 - v.1)
+            });
+            outlines.push(
+                corners
+                    .map(|i| {
+                        let (col, row) = vertices[i];
+                        self.origin + Point::new(col as f64, row as f64) * self.cell
+                    })
+                    .collect(),
+            );
+        }
+        outlines
+    }
+}
+
+/// Ramer–Douglas–Peucker simplification of a closed outline.
+fn simplify_closed(outline: &[Point], epsilon: f64) -> Vec<Point> {
+    if outline.len() < 3 {
+        return outline.to_vec();
+    }
+    let mut path = outline.to_vec();
+    path.push(outline[0]);
+    let mut simplified = simplify(&path, epsilon);
+    simplified.pop();
+    simplified
+}
+
+fn winding_number(polygon: &[Point], p: Point) -> i32 {
+    let mut winding = 0;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let cross = (b.x - a.x) * (p.y - a.y) - (p.x - a.x) * (b.y - a.y);
+        if a.y <= p.y && b.y > p.y && cross > 0.0 {
+            winding += 1;
+        } else if a.y > p.y && b.y <= p.y && cross < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::ShapeGeometry;
+
+    fn square(center: Point, size: f64) -> Vec<BezierSegment> {
+        ShapeGeometry::Rectangle {
+            center,
+            width: size,
+            height: size,
+            rotation: 0.0,
+        }
+        .segments(2.0)
+    }
+
+    fn page() -> BoundingBox {
+        BoundingBox::from_points(&[Point::new(0.0, 0.0), Point::new(100.0, 100.0)])
+    }
+
+    #[test]
+    fn test_fills_inside_closed_strokes_only() {
+        let barriers = square(Point::new(50.0, 50.0), 60.0);
+        let polygons = enclosed_region(&barriers, page(), Point::new(50.0, 50.0)).unwrap();
+        let region = FillRegion::new(polygons, Color::black());
+        assert!(region.is_valid());
+        assert!(region.contains(Point::new(25.0, 75.0)));
+        assert!(!region.contains(Point::new(10.0, 50.0)));
+        // The fill reaches under the ink but not past it.
+        let bb = region.bounding_box();
+        assert!(bb.min_x > 17.0 && bb.min_x < 21.0, "{bb:?}");
+        assert!(bb.max_y > 79.0 && bb.max_y < 83.0, "{bb:?}");
+
+        assert!(enclosed_region(&barriers, page(), Point::new(10.0, 10.0)).is_none());
+        assert!(enclosed_region(&barriers, page(), Point::new(20.0, 50.0)).is_none());
+        assert!(enclosed_region(&[], page(), Point::new(50.0, 50.0)).is_none());
+    }
+
+    #[test]
+    fn test_islands_become_holes_and_small_gaps_close() {
+        let mut barriers = square(Point::new(50.0, 50.0), 60.0);
+        barriers.extend(square(Point::new(50.0, 50.0), 20.0));
+        let polygons = enclosed_region(&barriers, page(), Point::new(30.0, 30.0)).unwrap();
+        assert_eq!(polygons.len(), 2);
+        let ring = FillRegion::new(polygons, Color::black());
+        assert!(ring.contains(Point::new(30.0, 30.0)));
+        assert!(!ring.contains(Point::new(50.0, 50.0)));
+
+        // Two L-shaped strokes leaving 3-unit gaps: with 2-unit ink, 1 unit of space
+        // remains open, within the tolerance.
+        let line = |a: Point, b: Point| BezierSegment {
+            p0: a,
+            p1: a.lerp(&b, 1.0 / 3.0),
+            p2: a.lerp(&b, 2.0 / 3.0),
+            p3: b,
+            start_width: 2.0,
+            end_width: 2.0,
+            colors: None,
+        };
+        let (a, b, c, d) = (
+            Point::new(20.0, 20.0),
+            Point::new(80.0, 20.0),
+            Point::new(80.0, 80.0),
+            Point::new(20.0, 80.0),
+        );
+        let gap = Point::new(0.0, 3.0);
+        let nearly_closed = [line(a, b), line(b, c - gap), line(c, d), line(d, a + gap)];
+        assert!(enclosed_region(&nearly_closed, page(), Point::new(50.0, 50.0)).is_some());
+        let wide_gap = Point::new(0.0, 10.0);
+        let open = [line(a, b), line(b, c - wide_gap), line(c, d), line(d, a + wide_gap)];
+        assert!(enclosed_region(&open, page(), Point::new(50.0, 50.0)).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::point::Point;
+use crate::point::{Color, Point, StrokePoint};
 
 /// A cubic Bezier segment with associated start/end widths for variable-width rendering.
 #[derive(Debug, Clone, Copy)]
@@ -9,6 +9,8 @@ pub struct BezierSegment {
     pub p3: Point,
     pub start_width: f64,
     pub end_width: f64,
+    /// Start and end color for strokes with color dynamics; `None` uses the stroke color.
+    pub colors: Option<(Color, Color)>,
 }
 
 impl BezierSegment {
@@ -27,6 +29,42 @@ impl BezierSegment {
     pub fn width_at(&self, t: f64) -> f64 {
         self.start_width + (self.end_width - self.start_width) * t
     }
+
+    /// First derivative of the curve at `t`.
+    pub fn derivative(&self, t: f64) -> Point {
+        let mt = 1.0 - t;
+        (self.p1 - self.p0) * (3.0 * mt * mt)
+            + (self.p2 - self.p1) * (6.0 * mt * t)
+            + (self.p3 - self.p2) * (3.0 * t * t)
+    }
+
+    /// The part of the segment between `t0` and `t1`, with widths and colors
+    /// interpolated to match.
+    pub fn subsegment(&self, t0: f64, t1: f64) -> BezierSegment {
+        let scale = (t1 - t0) / 3.0;
+        let (start, end) = (self.evaluate(t0), self.evaluate(t1));
+        BezierSegment {
+            p0: start,
+            p1: start + self.derivative(t0) * scale,
+            p2: end - self.derivative(t1) * scale,
+            p3: end,
+            start_width: self.width_at(t0),
+            end_width: self.width_at(t1),
+            colors: self
+                .colors
+                .map(|(a, b)| (a.lerp(&b, t0 as f32), a.lerp(&b, t1 as f32))),
+        }
+    }
+
+    pub fn translated(&self, offset: Point) -> BezierSegment {
+        BezierSegment {
+            p0: self.p0 + offset,
+            p1: self.p1 + offset,
+            p2: self.p2 + offset,
+            p3: self.p3 + offset,
+            ..*self
+        }
+    }
 }
 
 /// Convert four Catmull-Rom control points to a cubic Bezier segment for the middle segment (p1→p2).
@@ -92,6 +130,145 @@ pub fn calculate_velocity(p1: Point, t1: f64, p2: Point, t2: f64) -> f64 {
     p1.distance_to(&p2) / dt
 }
 
+/// Interval assumed between samples whose timestamps are missing or out of order (120 Hz).
+const FALLBACK_SAMPLE_INTERVAL: f64 = 1.0 / 120.0;
+
+/// Streaming One Euro filter for pen positions: heavy smoothing when the pen moves slowly
+/// (where jitter is visible) and little lag when it moves fast.
+#[derive(Debug, Clone)]
+pub struct OneEuroFilter {
+    /// Cutoff frequency (Hz) at rest. Lower means smoother.
+    min_cutoff: f64,
+    /// How quickly the cutoff rises with speed (Hz per unit/s).
+    beta: f64,
+    /// Cutoff used to smooth the speed estimate itself.
+    derivative_cutoff: f64,
+    last: Option<(Point, f64)>,
+    last_speed: f64,
+}
+
+impl OneEuroFilter {
+    pub fn new(min_cutoff: f64, beta: f64) -> Self {
+        Self {
+            min_cutoff,
+            beta,
+            derivative_cutoff: 1.0,
+            last: None,
+            last_speed: 0.0,
+        }
+    }
+
+    /// Filter tuned from a brush `smoothing` value in `0.0..=1.0`. Returns `None` for 0,
+    /// meaning input should pass through untouched.
+    pub fn from_smoothing(smoothing: f64) -> Option<Self> {
+        if !smoothing.is_finite() || smoothing <= 0.0 {
+            return None;
+        }
+        let s = smoothing.min(1.0);
+        // Interpolate the resting cutoff geometrically from 30 Hz (barely smoothed) to 0.5 Hz.
+        let min_cutoff = 30.0_f64.powf(1.0 - s) * 0.5_f64.powf(s);
+        Some(Self::new(min_cutoff, 0.01))
+    }
+
+    /// Feed the next raw sample and get the smoothed position.
+    pub fn filter(&mut self, point: Point, timestamp: f64) -> Point {
+        let Some((prev, prev_time)) = self.last else {
+            self.last = Some((point, timestamp));
+            return point;
+        };
+        let dt = if timestamp > prev_time {
+            timestamp - prev_time
+        } else {
+            FALLBACK_SAMPLE_INTERVAL
+        };
+        let speed = prev.distance_to(&point) / dt;
+        let speed = lerp(self.last_speed, speed, smoothing_alpha(self.derivative_cutoff, dt));
+        self.last_speed = speed;
+        let cutoff = self.min_cutoff + self.beta * speed;
+        let smoothed = prev.lerp(&point, smoothing_alpha(cutoff, dt));
+        self.last = Some((smoothed, timestamp));
+        smoothed
+    }
+}
+
+/// "Lazy rope" stabilizer: the drawn point trails the pen on a rope of fixed length and
+/// only moves when the rope is pulled taut, which irons out wobble in long curves.
+#[derive(Debug, Clone)]
+pub struct LazyRope {
+    length: f64,
+    /// Last emitted (drawn) point.
+    anchor: Option<StrokePoint>,
+    /// Latest raw pen sample.
+    pen: Option<StrokePoint>,
+}
+
+impl LazyRope {
+    /// Returns `None` for a zero, negative or non-finite length (stabilizer off).
+    pub fn new(length: f64) -> Option<Self> {
+        (length.is_finite() && length > 0.0).then_some(Self {
+            length,
+            anchor: None,
+            pen: None,
+        })
+    }
+
+    /// Feed a pen sample. Returns the point to draw, or `None` while the rope is slack.
+    /// The first sample is always drawn.
+    pub fn pull(&mut self, input: StrokePoint) -> Option<StrokePoint> {
+        self.pen = Some(input);
+        let Some(anchor) = self.anchor else {
+            self.anchor = Some(input);
+            return Some(input);
+        };
+        let distance = anchor.position.distance_to(&input.position);
+        if distance <= self.length {
+            return None;
+        }
+        let t = (distance - self.length) / distance;
+        let drawn = StrokePoint {
+            position: anchor.position.lerp(&input.position, t),
+            ..input
+        };
+        self.anchor = Some(drawn);
+        Some(drawn)
+    }
+
+    /// Points that close the gap between the drawn point and the pen, spaced at most one
+    /// rope length apart, so the stroke ends where the pen lifted.
+    pub fn catch_up(&mut self) -> Vec<StrokePoint> {
+        let (Some(anchor), Some(pen)) = (self.anchor, self.pen) else {
+            return vec![];
+        };
+        let distance = anchor.position.distance_to(&pen.position);
+        if distance < 1e-9 {
+            return vec![];
+        }
+        let steps = (distance / self.length).ceil().max(1.0) as usize;
+        let points: Vec<StrokePoint> = (1..=steps)
+            .map(|i| {
+                let t = i as f64 / steps as f64;
+                StrokePoint {
+                    position: anchor.position.lerp(&pen.position, t),
+                    pressure: lerp(anchor.pressure, pen.pressure, t),
+                    timestamp: lerp(anchor.timestamp, pen.timestamp, t),
+                    ..pen
+                }
+            })
+            .collect();
+        self.anchor = Some(pen);
+        points
+    }
+}
+
+fn smoothing_alpha(cutoff: f64, dt: f64) -> f64 {
+    let tau = 1.0 / (2.0 * std::f64::consts::PI * cutoff);
+    1.0 / (1.0 + tau / dt)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +282,7 @@ mod tests {
             p3: Point::new(4.0, 0.0),
             start_width: 2.0,
             end_width: 4.0,
+            colors: None,
         };
         let start = seg.evaluate(0.0);
         let end = seg.evaluate(1.0);
@@ -151,6 +329,50 @@ mod tests {
         assert!((v - 5.0).abs() < 1e-9);
     }
 
+    #[test]
+    fn test_one_euro_reduces_jitter() {
+        let mut filter = OneEuroFilter::from_smoothing(0.8).unwrap();
+        let mut raw_dev: f64 = 0.0;
+        let mut smooth_dev: f64 = 0.0;
+        for i in 0..120 {
+            // Slow horizontal line with ±1 unit of alternating noise.
+            let noise = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let t = i as f64 / 120.0;
+            let p = filter.filter(Point::new(t * 30.0, noise), t);
+            if i > 10 {
+                raw_dev = raw_dev.max(noise.abs());
+                smooth_dev = smooth_dev.max(p.y.abs());
+            }
+        }
+        assert!(smooth_dev < raw_dev * 0.5);
+    }
+
+    #[test]
+    fn test_one_euro_zero_smoothing_is_passthrough() {
+        assert!(OneEuroFilter::from_smoothing(0.0).is_none());
+        let mut filter = OneEuroFilter::from_smoothing(0.5).unwrap();
+        // Repeated timestamps must not produce NaN.
+        filter.filter(Point::new(0.0, 0.0), 1.0);
+        let p = filter.filter(Point::new(10.0, 0.0), 1.0);
+        assert!(p.x.is_finite() && p.x > 0.0);
+    }
+
+    #[test]
+    fn test_lazy_rope_trails_then_catches_up() {
+        let mut rope = LazyRope::new(10.0).unwrap();
+        assert!(rope.pull(StrokePoint::new(0.0, 0.0, 0.5, 0.0)).is_some());
+        // Slack rope: small moves draw nothing.
+        assert!(rope.pull(StrokePoint::new(6.0, 0.0, 0.5, 0.01)).is_none());
+        let drawn = rope.pull(StrokePoint::new(25.0, 0.0, 0.5, 0.02)).unwrap();
+        assert!((drawn.position.x - 15.0).abs() < 1e-9);
+
+        let tail = rope.catch_up();
+        assert_eq!(tail.len(), 1);
+        assert!((tail[0].position.x - 25.0).abs() < 1e-9);
+        assert!(rope.catch_up().is_empty());
+        assert!(LazyRope::new(0.0).is_none());
+    }
+
     #[test]
     fn test_width_at() {
         let seg = BezierSegment {
@@ -160,6 +382,7 @@ mod tests {
             p3: Point::new(3.0, 0.0),
             start_width: 2.0,
             end_width: 6.0,
+            colors: None,
         };
         assert!((seg.width_at(0.0) - 2.0).abs() < 1e-9);
         assert!((seg.width_at(0.5) - 4.0).abs() < 1e-9);
@@ -1,43 +1,383 @@
 use uuid::Uuid;
 
+use crate::fill::FillRegion;
+use crate::image::Image;
+use crate::infinite::ChunkCoord;
+use crate::page::Page;
+use crate::shape::Shape;
 use crate::stroke::Stroke;
+use crate::text::TextBox;
 
 #[derive(Debug, Clone)]
 pub enum HistoryAction {
     AddStroke {
+        page_id: Uuid,
         layer_index: usize,
         stroke: Stroke,
     },
     RemoveStroke {
+        page_id: Uuid,
         layer_index: usize,
         stroke: Stroke,
     },
+    /// Stroke added to an infinite-canvas chunk, in chunk-local coordinates.
+    AddChunkStroke {
+        chunk: ChunkCoord,
+        stroke: Stroke,
+    },
+    RemoveChunkStroke {
+        chunk: ChunkCoord,
+        stroke: Stroke,
+    },
+    /// `before` swapped for `after` in one step, e.g. a stroke snapped to a shape.
+    ReplaceStroke {
+        page_id: Uuid,
+        layer_index: usize,
+        before: Stroke,
+        after: Stroke,
+    },
+    ReplaceChunkStroke {
+        chunk: ChunkCoord,
+        before: Stroke,
+        after: Stroke,
+    },
+    AddShape {
+        page_id: Uuid,
+        layer_index: usize,
+        shape: Shape,
+    },
+    RemoveShape {
+        page_id: Uuid,
+        layer_index: usize,
+        shape: Shape,
+    },
+    /// A shape edited in place; `before` and `after` share an id.
+    ReplaceShape {
+        page_id: Uuid,
+        layer_index: usize,
+        before: Shape,
+        after: Shape,
+    },
+    AddText {
+        page_id: Uuid,
+        layer_index: usize,
+        text: TextBox,
+    },
+    RemoveText {
+        page_id: Uuid,
+        layer_index: usize,
+        text: TextBox,
+    },
+    /// A text box edited in place; `before` and `after` share an id.
+    ReplaceText {
+        page_id: Uuid,
+        layer_index: usize,
+        before: TextBox,
+        after: TextBox,
+    },
+    AddImage {
+        page_id: Uuid,
+        layer_index: usize,
+        image: Image,
+    },
+    RemoveImage {
+        page_id: Uuid,
+        layer_index: usize,
+        image: Image,
+    },
+    /// An image moved, scaled, rotated or cropped in place; `before` and `after` share
+    /// an id.
+    ReplaceImage {
+        page_id: Uuid,
+        layer_index: usize,
+        before: Image,
+        after: Image,
+    },
+    AddFill {
+        page_id: Uuid,
+        layer_index: usize,
+        fill: FillRegion,
+    },
+    RemoveFill {
+        page_id: Uuid,
+        layer_index: usize,
+        fill: FillRegion,
+    },
+    /// A page added at `index`, e.g. a new or duplicated page.
+    InsertPage {
+        index: usize,
+        page: Page,
+    },
+    RemovePage {
+        index: usize,
+        page: Page,
+    },
+    MovePage {
+        page_id: Uuid,
+        from: usize,
+        to: usize,
+    },
 }
 
 impl HistoryAction {
     pub fn inverse(&self) -> HistoryAction {
         match self {
             HistoryAction::AddStroke {
+                page_id,
                 layer_index,
                 stroke,
             } => HistoryAction::RemoveStroke {
+                page_id: *page_id,
                 layer_index: *layer_index,
                 stroke: stroke.clone(),
             },
             HistoryAction::RemoveStroke {
+                page_id,
                 layer_index,
                 stroke,
             } => HistoryAction::AddStroke {
+                page_id: *page_id,
                 layer_index: *layer_index,
                 stroke: stroke.clone(),
             },
+            HistoryAction::AddChunkStroke { chunk, stroke } => HistoryAction::RemoveChunkStroke {
+                chunk: *chunk,
+                stroke: stroke.clone(),
+            },
+            HistoryAction::RemoveChunkStroke { chunk, stroke } => HistoryAction::AddChunkStroke {
+                chunk: *chunk,
+                stroke: stroke.clone(),
+            },
+            HistoryAction::ReplaceStroke {
+                page_id,
+                layer_index,
+                before,
+                after,
+            } => HistoryAction::ReplaceStroke {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                before: after.clone(),
+                after: before.clone(),
+            },
+            HistoryAction::ReplaceChunkStroke {
+                chunk,
+                before,
+                after,
+            } => HistoryAction::ReplaceChunkStroke {
+                chunk: *chunk,
+                before: after.clone(),
+                after: before.clone(),
+            },
+            HistoryAction::AddShape {
+                page_id,
+                layer_index,
+                shape,
+            } => HistoryAction::RemoveShape {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                shape: shape.clone(),
+            },
+            HistoryAction::RemoveShape {
+                page_id,
+                layer_index,
+                shape,
+            } => HistoryAction::AddShape {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                shape: shape.clone(),
+            },
+            HistoryAction::ReplaceShape {
+                page_id,
+                layer_index,
+                before,
+                after,
+            } => HistoryAction::ReplaceShape {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                before: after.clone(),
+                after: before.clone(),
+            },
+            HistoryAction::AddText {
+                page_id,
+                layer_index,
+                text,
+            } => HistoryAction::RemoveText {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                text: text.clone(),
+            },
+            HistoryAction::RemoveText {
+                page_id,
+                layer_index,
+                text,
+            } => HistoryAction::AddText {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                text: text.clone(),
+            },
+            HistoryAction::ReplaceText {
+                page_id,
+                layer_index,
+                before,
+                after,
+            } => HistoryAction::ReplaceText {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                before: after.clone(),
+                after: before.clone(),
+            },
+            HistoryAction::AddImage {
+                page_id,
+                layer_index,
+                image,
+            } => HistoryAction::RemoveImage {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                image: image.clone(),
+            },
+            HistoryAction::RemoveImage {
+                page_id,
+                layer_index,
+                image,
+            } => HistoryAction::AddImage {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                image: image.clone(),
+            },
+            HistoryAction::ReplaceImage {
+                page_id,
+                layer_index,
+                before,
+                after,
+            } => HistoryAction::ReplaceImage {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                before: after.clone(),
+                after: before.clone(),
+            },
+            HistoryAction::AddFill {
+                page_id,
+                layer_index,
+                fill,
+            } => HistoryAction::RemoveFill {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                fill: fill.clone(),
+            },
+            HistoryAction::RemoveFill {
+                page_id,
+                layer_index,
+                fill,
+            } => HistoryAction::AddFill {
+                page_id: *page_id,
+                layer_index: *layer_index,
+                fill: fill.clone(),
+            },
+            HistoryAction::InsertPage { index, page } => HistoryAction::RemovePage {
+                index: *index,
+                page: page.clone(),
+            },
+            HistoryAction::RemovePage { index, page } => HistoryAction::InsertPage {
+                index: *index,
+                page: page.clone(),
+            },
+            HistoryAction::MovePage { page_id, from, to } => HistoryAction::MovePage {
+                page_id: *page_id,
+                from: *to,
+                to: *from,
+            },
+        }
+    }
+
+    /// A replacement as the removal and addition it is made of.
+    pub fn split_replace(&self) -> Option<(HistoryAction, HistoryAction)> {
+        match self {
+            HistoryAction::ReplaceStroke {
+                page_id,
+                layer_index,
+                before,
+                after,
+            } => Some((
+                HistoryAction::RemoveStroke {
+                    page_id: *page_id,
+                    layer_index: *layer_index,
+                    stroke: before.clone(),
+                },
+                HistoryAction::AddStroke {
+                    page_id: *page_id,
+                    layer_index: *layer_index,
+                    stroke: after.clone(),
+                },
+            )),
+            HistoryAction::ReplaceChunkStroke {
+                chunk,
+                before,
+                after,
+            } => Some((
+                HistoryAction::RemoveChunkStroke {
+                    chunk: *chunk,
+                    stroke: before.clone(),
+                },
+                HistoryAction::AddChunkStroke {
+                    chunk: *chunk,
+                    stroke: after.clone(),
+                },
+            )),
+            _ => None,
+        }
+    }
+
+    /// Page the action applies to, or `None` for infinite-canvas actions.
+    pub fn page_id(&self) -> Option<Uuid> {
+        match self {
+            HistoryAction::AddStroke { page_id, .. } => Some(*page_id),
+            HistoryAction::RemoveStroke { page_id, .. } => Some(*page_id),
+            HistoryAction::ReplaceStroke { page_id, .. } => Some(*page_id),
+            HistoryAction::AddShape { page_id, .. }
+            | HistoryAction::RemoveShape { page_id, .. }
+            | HistoryAction::ReplaceShape { page_id, .. }
+            | HistoryAction::AddText { page_id, .. }
+            | HistoryAction::RemoveText { page_id, .. }
+            | HistoryAction::ReplaceText { page_id, .. }
+            | HistoryAction::AddImage { page_id, .. }
+            | HistoryAction::RemoveImage { page_id, .. }
+            | HistoryAction::ReplaceImage { page_id, .. }
+            | HistoryAction::AddFill { page_id, .. }
+            | HistoryAction::RemoveFill { page_id, .. }
+            | HistoryAction::MovePage { page_id, .. } => Some(*page_id),
+            HistoryAction::InsertPage { page, .. } | HistoryAction::RemovePage { page, .. } => {
+                Some(page.id)
+            }
+            HistoryAction::AddChunkStroke { .. }
+            | HistoryAction::RemoveChunkStroke { .. }
+            | HistoryAction::ReplaceChunkStroke { .. } => None,
         }
     }
 
-    pub fn stroke_id(&self) -> Uuid {
+    /// Id of the stroke, shape, text box, image or fill the action changes; for page
+    /// actions, the page's.
+    pub fn element_id(&self) -> Uuid {
         match self {
             HistoryAction::AddStroke { stroke, .. } => stroke.id,
             HistoryAction::RemoveStroke { stroke, .. } => stroke.id,
+            HistoryAction::AddChunkStroke { stroke, .. } => stroke.id,
+            HistoryAction::RemoveChunkStroke { stroke, .. } => stroke.id,
+            HistoryAction::ReplaceStroke { after, .. } => after.id,
+            HistoryAction::ReplaceChunkStroke { after, .. } => after.id,
+            HistoryAction::AddShape { shape, .. } => shape.id,
+            HistoryAction::RemoveShape { shape, .. } => shape.id,
+            HistoryAction::ReplaceShape { after, .. } => after.id,
+            HistoryAction::AddText { text, .. } => text.id,
+            HistoryAction::RemoveText { text, .. } => text.id,
+            HistoryAction::ReplaceText { after, .. } => after.id,
+            HistoryAction::AddImage { image, .. } => image.id,
+            HistoryAction::RemoveImage { image, .. } => image.id,
+            HistoryAction::ReplaceImage { after, .. } => after.id,
+            HistoryAction::AddFill { fill, .. } => fill.id,
+            HistoryAction::RemoveFill { fill, .. } => fill.id,
+            HistoryAction::InsertPage { page, .. } => page.id,
+            HistoryAction::RemovePage { page, .. } => page.id,
+            HistoryAction::MovePage { page_id, .. } => *page_id,
         }
     }
 }
@@ -120,6 +460,7 @@ mod tests {
         let mut history = History::new(10);
         let stroke = make_stroke();
         history.push(HistoryAction::AddStroke {
+            page_id: Uuid::nil(),
             layer_index: 0,
             stroke: stroke.clone(),
         });
@@ -149,6 +490,7 @@ mod tests {
     fn test_push_clears_redo() {
         let mut history = History::new(10);
         history.push(HistoryAction::AddStroke {
+            page_id: Uuid::nil(),
             layer_index: 0,
             stroke: make_stroke(),
         });
@@ -156,6 +498,7 @@ mod tests {
         assert!(history.can_redo());
 
         history.push(HistoryAction::AddStroke {
+            page_id: Uuid::nil(),
             layer_index: 0,
             stroke: make_stroke(),
         });
@@ -167,6 +510,7 @@ mod tests {
         let mut history = History::new(3);
         for _ in 0..5 {
             history.push(HistoryAction::AddStroke {
+                page_id: Uuid::nil(),
                 layer_index: 0,
                 stroke: make_stroke(),
             });
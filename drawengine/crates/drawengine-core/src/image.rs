@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::EngineError;
+use crate::point::{BoundingBox, Point};
+use crate::shape::rotate;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+/// Encoded bytes of an imported image. Stored once per document and shared by every
+/// `Image` placed from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageData {
+    pub id: Uuid,
+    pub format: ImageFormat,
+    /// Size in pixels, read from the header.
+    pub width: u32,
+    pub height: u32,
+    pub bytes: Vec<u8>,
+}
+
+impl ImageData {
+    /// Read the format and pixel size of PNG or JPEG bytes. The pixels themselves are
+    /// decoded by the host.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, EngineError> {
+        let (format, width, height) = if bytes.starts_with(&PNG_SIGNATURE) {
+            let (width, height) = png_size(&bytes).ok_or_else(|| invalid("truncated PNG"))?;
+            (ImageFormat::Png, width, height)
+        } else if bytes.starts_with(&[0xFF, 0xD8]) {
+            let (width, height) =
+                jpeg_size(&bytes).ok_or_else(|| invalid("JPEG has no frame header"))?;
+            (ImageFormat::Jpeg, width, height)
+        } else {
+            return Err(invalid("not a PNG or JPEG image"));
+        };
+        if width == 0 || height == 0 {
+            return Err(invalid("image has no pixels"));
+        }
+        Ok(Self {
+            id: Uuid::new_v4(),
+            format,
+            width,
+            height,
+            bytes,
+        })
+    }
+}
+
+/// A rectangle of image pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Maps `(x, y)` to `(a·x + c·y + tx, b·x + d·y + ty)`, as `CGAffineTransform` does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AffineTransform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub tx: f64,
+    pub ty: f64,
+}
+
+impl AffineTransform {
+    pub fn apply(&self, p: Point) -> Point {
+        Point::new(
+            self.a * p.x + self.c * p.y + self.tx,
+            self.b * p.x + self.d * p.y + self.ty,
+        )
+    }
+}
+
+/// An imported image placed on a layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Image {
+    pub id: Uuid,
+    /// The `ImageData` to draw.
+    pub image_id: Uuid,
+    /// Center of the visible part in page-local coordinates.
+    pub center: Point,
+    /// Page units per image pixel.
+    pub scale: f64,
+    /// Rotation about the center, in radians, in the same direction as shape rotation.
+    pub rotation: f64,
+    /// Visible part of the image, in pixels.
+    pub crop: CropRect,
+}
+
+impl Image {
+    /// The whole of `data` at one page unit per pixel, unrotated.
+    pub fn new(data: &ImageData, center: Point) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            image_id: data.id,
+            center,
+            scale: 1.0,
+            rotation: 0.0,
+            crop: CropRect {
+                x: 0.0,
+                y: 0.0,
+                width: data.width as f64,
+                height: data.height as f64,
+            },
+        }
+    }
+
+    /// Finite placement with a positive scale and a non-empty crop.
+    pub fn is_valid(&self) -> bool {
+        let crop = &self.crop;
+        [self.center.x, self.center.y, self.rotation, crop.x, crop.y]
+            .iter()
+            .all(|v| v.is_finite())
+            && [self.scale, crop.width, crop.height]
+                .iter()
+                .all(|v| v.is_finite() && *v > 0.0)
+    }
+
+    /// Maps pixel offsets from the crop's top-left corner to page-local coordinates.
+    pub fn transform(&self) -> AffineTransform {
+        let (sin, cos) = self.rotation.sin_cos();
+        let half = Point::new(self.crop.width, self.crop.height) * (0.5 * self.scale);
+        let origin = self.center - rotate(half, self.rotation);
+        AffineTransform {
+            a: self.scale * cos,
+            b: self.scale * sin,
+            c: -self.scale * sin,
+            d: self.scale * cos,
+            tx: origin.x,
+            ty: origin.y,
+        }
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        let transform = self.transform();
+        let (w, h) = (self.crop.width, self.crop.height);
+        BoundingBox::from_points(&[
+            transform.apply(Point::new(0.0, 0.0)),
+            transform.apply(Point::new(w, 0.0)),
+            transform.apply(Point::new(w, h)),
+            transform.apply(Point::new(0.0, h)),
+        ])
+    }
+
+    /// Whether `point` is on the rotated image or within `tolerance` of it.
+    pub fn hit_test(&self, point: Point, tolerance: f64) -> bool {
+        let local = rotate(point - self.center, -self.rotation);
+        local.x.abs() <= self.crop.width * self.scale / 2.0 + tolerance
+            && local.y.abs() <= self.crop.height * self.scale / 2.0 + tolerance
+    }
+}
+
+/// Width and height from the IHDR chunk, which must come first.
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some((read_u32_be(bytes, 16)?, read_u32_be(bytes, 20)?))
+}
+
+/// Width and height from the first start-of-frame segment.
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+    loop {
+        if *bytes.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(pos + 1)?;
+        pos += 2;
+        match marker {
+            // Fill bytes and markers without a length.
+            0xFF => pos -= 1,
+            0x01 | 0xD0..=0xD7 => {}
+            // Scan data before any frame header.
+            0xD9 | 0xDA => return None,
+            _ => {
+                let length = read_u16_be(bytes, pos)? as usize;
+                // SOF0-SOF15, except DHT, JPG and DAC, which share the range.
+                if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+                    let height = read_u16_be(bytes, pos + 3)?;
+                    let width = read_u16_be(bytes, pos + 5)?;
+                    return Some((width as u32, height as u32));
+                }
+                pos += length;
+            }
+        }
+    }
+}
+
+fn read_u16_be(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32_be(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn invalid(message: &str) -> EngineError {
+    EngineError::InvalidImage {
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Header of a `width`×`height` PNG; enough for `ImageData::from_bytes`.
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = PNG_SIGNATURE.to_vec();
+        bytes.extend_from_slice(&13u32.to_be_bytes());
+        bytes.extend_from_slice(b"IHDR");
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&[8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_reads_png_and_jpeg_sizes() {
+        let png = ImageData::from_bytes(png_header(640, 480)).unwrap();
+        assert_eq!((png.format, png.width, png.height), (ImageFormat::Png, 640, 480));
+
+        // SOI, an APP0 segment, then SOF2 (progressive) with height 300 and width 200.
+        let jpeg = vec![
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC2, 0x00, 0x0B, 0x08,
+            0x01, 0x2C, 0x00, 0xC8, 0x01, 0x01, 0x11, 0x00,
+        ];
+        let jpeg = ImageData::from_bytes(jpeg).unwrap();
+        assert_eq!((jpeg.format, jpeg.width, jpeg.height), (ImageFormat::Jpeg, 200, 300));
+
+        assert!(matches!(
+            ImageData::from_bytes(b"GIF89a".to_vec()),
+            Err(EngineError::InvalidImage { .. })
+        ));
+        assert!(ImageData::from_bytes(vec![0xFF, 0xD8, 0xFF, 0xDA]).is_err());
+        assert!(ImageData::from_bytes(png_header(0, 10)).is_err());
+    }
+
+    #[test]
+    fn test_transform_places_crop_around_center() {
+        let data = ImageData::from_bytes(png_header(100, 50)).unwrap();
+        let mut image = Image::new(&data, Point::new(200.0, 100.0));
+        image.scale = 2.0;
+        image.crop = CropRect {
+            x: 10.0,
+            y: 0.0,
+            width: 40.0,
+            height: 20.0,
+        };
+        let transform = image.transform();
+        assert_eq!(transform.apply(Point::new(0.0, 0.0)), Point::new(160.0, 80.0));
+        assert_eq!(transform.apply(Point::new(40.0, 20.0)), Point::new(240.0, 120.0));
+
+        image.rotation = std::f64::consts::FRAC_PI_2;
+        let bb = image.bounding_box();
+        assert!((bb.width() - 40.0).abs() < 1e-9 && (bb.height() - 80.0).abs() < 1e-9);
+        assert!(image.hit_test(Point::new(210.0, 130.0), 0.0));
+        assert!(!image.hit_test(Point::new(230.0, 100.0), 5.0));
+        let corner = image.transform().apply(Point::new(0.0, 0.0));
+        assert!(corner.distance_to(&Point::new(220.0, 60.0)) < 1e-9);
+    }
+}
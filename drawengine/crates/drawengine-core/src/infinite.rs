@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::eraser::find_strokes_to_erase;
+use crate::point::{BoundingBox, Color, Point};
+use crate::stroke::Stroke;
+
+/// Side length of a chunk in canvas units.
+pub const CHUNK_SIZE: f64 = 1024.0;
+
+/// Integer position of a chunk in the unbounded grid.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct ChunkCoord {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl ChunkCoord {
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    /// Origin of `other` relative to this chunk's origin. Only meaningful for nearby
+    /// chunks, which is all the engine ever asks for, so the result stays small.
+    pub fn offset_to(self, other: ChunkCoord) -> Point {
+        Point::new(
+            (other.x - self.x) as f64 * CHUNK_SIZE,
+            (other.y - self.y) as f64 * CHUNK_SIZE,
+        )
+    }
+
+    /// Chunk containing `point` (relative to this chunk's origin), and the point in
+    /// that chunk's local coordinates.
+    pub fn locate(self, point: Point) -> (ChunkCoord, Point) {
+        let dx = (point.x / CHUNK_SIZE).floor();
+        let dy = (point.y / CHUNK_SIZE).floor();
+        let chunk = ChunkCoord::new(self.x + dx as i64, self.y + dy as i64);
+        let local = Point::new(point.x - dx * CHUNK_SIZE, point.y - dy * CHUNK_SIZE);
+        (chunk, local)
+    }
+}
+
+/// Strokes that start inside one chunk, stored in chunk-local coordinates.
+/// Strokes may extend past the chunk edge; `bounds` covers all of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    pub coord: ChunkCoord,
+    pub strokes: Vec<Stroke>,
+    #[serde(skip, default = "BoundingBox::empty")]
+    bounds: BoundingBox,
+}
+
+impl Chunk {
+    pub fn new(coord: ChunkCoord) -> Self {
+        Self {
+            coord,
+            strokes: Vec::new(),
+            bounds: BoundingBox::empty(),
+        }
+    }
+
+    /// Union of the stroke bounding boxes, in chunk-local coordinates.
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn refresh_bounds(&mut self) {
+        self.bounds = self
+            .strokes
+            .iter()
+            .map(|s| s.bounding_box)
+            .filter(BoundingBox::is_valid)
+            .fold(BoundingBox::empty(), |acc, bb| acc.union(&bb));
+    }
+}
+
+/// Unbounded drawing surface made of chunks. Positions are always expressed relative to
+/// some nearby chunk, so `f64` coordinates stay small no matter how far the user pans.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfiniteCanvas {
+    pub background_color: Color,
+    /// Chunk the viewport is measured from; moves with the view.
+    pub anchor: ChunkCoord,
+    /// Non-empty chunks, sorted by coordinate.
+    chunks: Vec<Chunk>,
+}
+
+impl InfiniteCanvas {
+    pub fn new() -> Self {
+        Self {
+            background_color: Color::white(),
+            anchor: ChunkCoord::default(),
+            chunks: Vec::new(),
+        }
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    pub(crate) fn chunks_mut(&mut self) -> &mut Vec<Chunk> {
+        &mut self.chunks
+    }
+
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&Chunk> {
+        self.chunk_index(coord).ok().map(|i| &self.chunks[i])
+    }
+
+    pub fn stroke_count(&self) -> usize {
+        self.chunks.iter().map(|c| c.strokes.len()).sum()
+    }
+
+    pub fn contains_stroke(&self, coord: ChunkCoord, stroke_id: Uuid) -> bool {
+        self.chunk(coord)
+            .is_some_and(|c| c.strokes.iter().any(|s| s.id == stroke_id))
+    }
+
+    /// Add a stroke in `coord`-local coordinates.
+    pub fn add_stroke(&mut self, coord: ChunkCoord, stroke: Stroke) {
+        let index = match self.chunk_index(coord) {
+            Ok(i) => i,
+            Err(i) => {
+                self.chunks.insert(i, Chunk::new(coord));
+                i
+            }
+        };
+        let chunk = &mut self.chunks[index];
+        if stroke.bounding_box.is_valid() {
+            chunk.bounds = chunk.bounds.union(&stroke.bounding_box);
+        }
+        chunk.strokes.push(stroke);
+    }
+
+    /// Remove a stroke, dropping its chunk if it becomes empty.
+    pub fn remove_stroke(&mut self, coord: ChunkCoord, stroke_id: Uuid) -> Option<Stroke> {
+        let index = self.chunk_index(coord).ok()?;
+        let chunk = &mut self.chunks[index];
+        let pos = chunk.strokes.iter().position(|s| s.id == stroke_id)?;
+        let stroke = chunk.strokes.remove(pos);
+        if chunk.strokes.is_empty() {
+            self.chunks.remove(index);
+        } else {
+            chunk.refresh_bounds();
+        }
+        Some(stroke)
+    }
+
+    /// Chunks whose content intersects `view` (relative to `origin`'s chunk), each with
+    /// its own origin relative to `origin`.
+    pub fn chunks_in_view(
+        &self,
+        origin: ChunkCoord,
+        view: BoundingBox,
+    ) -> impl Iterator<Item = (&Chunk, Point)> {
+        self.chunks.iter().filter_map(move |chunk| {
+            let offset = origin.offset_to(chunk.coord);
+            translated(chunk.bounds, offset)
+                .intersects(&view)
+                .then_some((chunk, offset))
+        })
+    }
+
+    /// Strokes within `radius` of `point` (relative to `origin`'s chunk).
+    pub fn find_strokes_to_erase(
+        &self,
+        origin: ChunkCoord,
+        point: Point,
+        radius: f64,
+    ) -> Vec<(ChunkCoord, Uuid)> {
+        let mut area = BoundingBox::from_points(&[point]);
+        area.expand_by(radius);
+        self.chunks_in_view(origin, area)
+            .flat_map(|(chunk, offset)| {
+                find_strokes_to_erase(&chunk.strokes, point - offset, radius)
+                    .into_iter()
+                    .map(move |id| (chunk.coord, id))
+            })
+            .collect()
+    }
+
+    /// Bounds of all content relative to `origin`'s chunk.
+    pub fn content_bounds(&self, origin: ChunkCoord) -> BoundingBox {
+        self.chunks
+            .iter()
+            .filter(|c| c.bounds.is_valid())
+            .fold(BoundingBox::empty(), |acc, c| {
+                acc.union(&translated(c.bounds, origin.offset_to(c.coord)))
+            })
+    }
+
+    /// Restore derived state after deserializing or editing chunks directly.
+    pub(crate) fn normalize(&mut self) {
+        self.chunks.retain(|c| !c.strokes.is_empty());
+        self.chunks.sort_by_key(|c| c.coord);
+        // Merge duplicate coordinates (hand-edited files) instead of shadowing strokes.
+        let mut merged: Vec<Chunk> = Vec::with_capacity(self.chunks.len());
+        for chunk in self.chunks.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.coord == chunk.coord => last.strokes.extend(chunk.strokes),
+                _ => merged.push(chunk),
+            }
+        }
+        for chunk in &mut merged {
+            chunk.refresh_bounds();
+        }
+        self.chunks = merged;
+    }
+
+    fn chunk_index(&self, coord: ChunkCoord) -> Result<usize, usize> {
+        self.chunks.binary_search_by_key(&coord, |c| c.coord)
+    }
+}
+
+impl Default for InfiniteCanvas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn translated(bb: BoundingBox, offset: Point) -> BoundingBox {
+    BoundingBox {
+        min_x: bb.min_x + offset.x,
+        min_y: bb.min_y + offset.y,
+        max_x: bb.max_x + offset.x,
+        max_y: bb.max_y + offset.y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brush::BrushConfig;
+    use crate::point::StrokePoint;
+    use crate::stroke::StrokeBuilder;
+
+    fn line_stroke(from: Point, to: Point) -> Stroke {
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        brush.smoothing = 0.0;
+        let mut builder = StrokeBuilder::new(brush);
+        for i in 0..4 {
+            let p = from.lerp(&to, i as f64 / 3.0);
+            builder.add_point(StrokePoint::new(p.x, p.y, 0.5, i as f64 * 0.016));
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn test_locate_negative_coordinates() {
+        let (chunk, local) = ChunkCoord::new(5, 5).locate(Point::new(-10.0, CHUNK_SIZE + 3.0));
+        assert_eq!(chunk, ChunkCoord::new(4, 6));
+        assert!((local.x - (CHUNK_SIZE - 10.0)).abs() < 1e-9);
+        assert!((local.y - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_far_chunks_keep_local_precision() {
+        let far = ChunkCoord::new(1 << 40, -(1 << 40));
+        let (chunk, local) = far.locate(Point::new(0.25, 0.5));
+        assert_eq!(chunk, far);
+        assert_eq!(local, Point::new(0.25, 0.5));
+        assert_eq!(far.offset_to(ChunkCoord::new(far.x + 1, far.y)), Point::new(CHUNK_SIZE, 0.0));
+    }
+
+    #[test]
+    fn test_view_culls_distant_chunks() {
+        let mut canvas = InfiniteCanvas::new();
+        let stroke = line_stroke(Point::new(10.0, 10.0), Point::new(50.0, 10.0));
+        canvas.add_stroke(ChunkCoord::new(0, 0), stroke.clone());
+        canvas.add_stroke(ChunkCoord::new(100, 0), stroke.with_id(Uuid::new_v4()));
+
+        let view = BoundingBox::from_points(&[Point::new(0.0, 0.0), Point::new(800.0, 600.0)]);
+        let visible: Vec<_> = canvas.chunks_in_view(ChunkCoord::new(0, 0), view).collect();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].0.coord, ChunkCoord::new(0, 0));
+    }
+
+    #[test]
+    fn test_erase_across_chunk_edge_and_drop_empty_chunk() {
+        let mut canvas = InfiniteCanvas::new();
+        // Starts near the right edge of chunk (0, 0) and crosses into (1, 0).
+        let stroke = line_stroke(
+            Point::new(CHUNK_SIZE - 20.0, 10.0),
+            Point::new(CHUNK_SIZE + 40.0, 10.0),
+        );
+        let id = stroke.id;
+        canvas.add_stroke(ChunkCoord::new(0, 0), stroke);
+
+        // Hit-test from chunk (1, 0) at its local x = 20.
+        let hits = canvas.find_strokes_to_erase(ChunkCoord::new(1, 0), Point::new(20.0, 10.0), 3.0);
+        assert_eq!(hits, vec![(ChunkCoord::new(0, 0), id)]);
+
+        assert!(canvas.remove_stroke(ChunkCoord::new(0, 0), id).is_some());
+        assert!(canvas.chunks().is_empty());
+    }
+
+    #[test]
+    fn test_serde_roundtrip_rebuilds_bounds() {
+        let mut canvas = InfiniteCanvas::new();
+        canvas.add_stroke(
+            ChunkCoord::new(-3, 7),
+            line_stroke(Point::new(0.0, 0.0), Point::new(30.0, 30.0)),
+        );
+        let json = serde_json::to_string(&canvas).unwrap();
+        let mut loaded: InfiniteCanvas = serde_json::from_str(&json).unwrap();
+        assert!(!loaded.chunks()[0].bounds().is_valid());
+        loaded.normalize();
+        assert!(loaded.chunks()[0].bounds().is_valid());
+        assert_eq!(loaded.stroke_count(), 1);
+    }
+}
@@ -0,0 +1,602 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::EngineError;
+use crate::fill::FillRegion;
+use crate::image::Image;
+use crate::infinite::{ChunkCoord, InfiniteCanvas};
+use crate::page::{find_page, Page};
+use crate::point::Color;
+use crate::shape::Shape;
+use crate::stroke::Stroke;
+use crate::template::PageTemplate;
+use crate::text::TextBox;
+use crate::validation::ValidationReport;
+
+/// A single document mutation, written as one line of JSON (JSON Lines).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalEntry {
+    AddStroke {
+        page_id: Uuid,
+        layer_index: usize,
+        stroke: Stroke,
+    },
+    RemoveStroke {
+        page_id: Uuid,
+        layer_index: usize,
+        stroke_id: Uuid,
+    },
+    SetActiveLayer {
+        page_id: Uuid,
+        layer_id: Uuid,
+    },
+    InsertPage {
+        index: usize,
+        page: Page,
+    },
+    RemovePage {
+        page_id: Uuid,
+    },
+    MovePage {
+        page_id: Uuid,
+        to_index: usize,
+    },
+    SetPageBackground {
+        page_id: Uuid,
+        color: Color,
+        template: PageTemplate,
+    },
+    AddChunkStroke {
+        chunk: ChunkCoord,
+        stroke: Stroke,
+    },
+    RemoveChunkStroke {
+        chunk: ChunkCoord,
+        stroke_id: Uuid,
+    },
+    AddShape {
+        page_id: Uuid,
+        layer_index: usize,
+        shape: Shape,
+    },
+    RemoveShape {
+        page_id: Uuid,
+        shape_id: Uuid,
+    },
+    /// Replaces the shape with the same id wherever it is on the page.
+    UpdateShape {
+        page_id: Uuid,
+        shape: Shape,
+    },
+    AddText {
+        page_id: Uuid,
+        layer_index: usize,
+        text: TextBox,
+    },
+    RemoveText {
+        page_id: Uuid,
+        text_id: Uuid,
+    },
+    /// Replaces the text box with the same id wherever it is on the page.
+    UpdateText {
+        page_id: Uuid,
+        text: TextBox,
+    },
+    /// Bytes of a newly imported image, recorded once so recovery can restore them.
+    /// The engine replays it; `apply` skips it.
+    ImportImage {
+        image_id: Uuid,
+        #[serde(with = "base64_bytes")]
+        bytes: Vec<u8>,
+    },
+    /// Only the placement; the bytes come from `ImportImage` or the package.
+    AddImage {
+        page_id: Uuid,
+        layer_index: usize,
+        image: Image,
+    },
+    RemoveImage {
+        page_id: Uuid,
+        image_id: Uuid,
+    },
+    /// Replaces the image with the same id wherever it is on the page.
+    UpdateImage {
+        page_id: Uuid,
+        image: Image,
+    },
+    AddFill {
+        page_id: Uuid,
+        layer_index: usize,
+        fill: FillRegion,
+    },
+    RemoveFill {
+        page_id: Uuid,
+        fill_id: Uuid,
+    },
+}
+
+impl JournalEntry {
+    /// Apply the entry to `pages`. Replay is idempotent: entries already reflected in the
+    /// snapshot (re-added strokes or pages, already-removed ones) are skipped and return `false`.
+    /// Chunk entries are skipped when the document has no infinite canvas.
+    pub fn apply(&self, pages: &mut Vec<Page>, canvas: Option<&mut InfiniteCanvas>) -> bool {
+        match self {
+            JournalEntry::AddStroke {
+                page_id,
+                layer_index,
+                stroke,
+            } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                if layers.find_stroke_layer(stroke.id).is_some() {
+                    return false;
+                }
+                match layers.layers.get_mut(*layer_index) {
+                    Some(layer) => {
+                        layer.add_stroke(stroke.clone());
+                        true
+                    }
+                    None => false,
+                }
+            }
+            JournalEntry::RemoveStroke {
+                page_id,
+                layer_index,
+                stroke_id,
+            } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                let idx = match layers.layers.get(*layer_index) {
+                    Some(l) if l.strokes.iter().any(|s| s.id == *stroke_id) => Some(*layer_index),
+                    _ => layers.find_stroke_layer(*stroke_id),
+                };
+                match idx {
+                    Some(i) => layers.layers[i].remove_stroke(*stroke_id).is_some(),
+                    None => false,
+                }
+            }
+            JournalEntry::SetActiveLayer { page_id, layer_id } => page_mut(pages, *page_id)
+                .is_some_and(|p| p.layer_manager.set_active_layer(*layer_id).is_ok()),
+            JournalEntry::InsertPage { index, page } => {
+                if find_page(pages, page.id).is_some() {
+                    return false;
+                }
+                pages.insert((*index).min(pages.len()), page.clone());
+                true
+            }
+            JournalEntry::RemovePage { page_id } => {
+                // Never leave a document without pages.
+                match find_page(pages, *page_id) {
+                    Some(idx) if pages.len() > 1 => {
+                        pages.remove(idx);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            JournalEntry::MovePage { page_id, to_index } => match find_page(pages, *page_id) {
+                Some(from) => {
+                    let to = (*to_index).min(pages.len() - 1);
+                    if from == to {
+                        return false;
+                    }
+                    let page = pages.remove(from);
+                    pages.insert(to, page);
+                    true
+                }
+                None => false,
+            },
+            JournalEntry::SetPageBackground {
+                page_id,
+                color,
+                template,
+            } => match page_mut(pages, *page_id) {
+                Some(page) if page.background_color != *color || page.template != *template => {
+                    page.background_color = *color;
+                    page.template = template.clone();
+                    true
+                }
+                _ => false,
+            },
+            JournalEntry::AddChunkStroke { chunk, stroke } => match canvas {
+                Some(canvas) if !canvas.contains_stroke(*chunk, stroke.id) => {
+                    canvas.add_stroke(*chunk, stroke.clone());
+                    true
+                }
+                _ => false,
+            },
+            JournalEntry::RemoveChunkStroke { chunk, stroke_id } => {
+                canvas.is_some_and(|c| c.remove_stroke(*chunk, *stroke_id).is_some())
+            }
+            JournalEntry::AddShape {
+                page_id,
+                layer_index,
+                shape,
+            } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                if layers.find_shape_layer(shape.id).is_some() {
+                    return false;
+                }
+                match layers.layers.get_mut(*layer_index) {
+                    Some(layer) => {
+                        layer.add_shape(shape.clone());
+                        true
+                    }
+                    None => false,
+                }
+            }
+            JournalEntry::RemoveShape { page_id, shape_id } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                match layers.find_shape_layer(*shape_id) {
+                    Some(i) => layers.layers[i].remove_shape(*shape_id).is_some(),
+                    None => false,
+                }
+            }
+            JournalEntry::UpdateShape { page_id, shape } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                let Some(i) = layers.find_shape_layer(shape.id) else {
+                    return false;
+                };
+                let old = layers.layers[i].replace_shape(shape.clone());
+                old.is_some_and(|old| old != *shape)
+            }
+            JournalEntry::AddText {
+                page_id,
+                layer_index,
+                text,
+            } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                if layers.find_text_layer(text.id).is_some() {
+                    return false;
+                }
+                match layers.layers.get_mut(*layer_index) {
+                    Some(layer) => {
+                        layer.add_text(text.clone());
+                        true
+                    }
+                    None => false,
+                }
+            }
+            JournalEntry::RemoveText { page_id, text_id } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                match layers.find_text_layer(*text_id) {
+                    Some(i) => layers.layers[i].remove_text(*text_id).is_some(),
+                    None => false,
+                }
+            }
+            JournalEntry::UpdateText { page_id, text } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                let Some(i) = layers.find_text_layer(text.id) else {
+                    return false;
+                };
+                let old = layers.layers[i].replace_text(text.clone());
+                old.is_some_and(|old| old != *text)
+            }
+            JournalEntry::AddImage {
+                page_id,
+                layer_index,
+                image,
+            } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                if layers.find_image_layer(image.id).is_some() {
+                    return false;
+                }
+                match layers.layers.get_mut(*layer_index) {
+                    Some(layer) => {
+                        layer.add_image(image.clone());
+                        true
+                    }
+                    None => false,
+                }
+            }
+            JournalEntry::RemoveImage { page_id, image_id } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                match layers.find_image_layer(*image_id) {
+                    Some(i) => layers.layers[i].remove_image(*image_id).is_some(),
+                    None => false,
+                }
+            }
+            JournalEntry::UpdateImage { page_id, image } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                let Some(i) = layers.find_image_layer(image.id) else {
+                    return false;
+                };
+                let old = layers.layers[i].replace_image(image.clone());
+                old.is_some_and(|old| old != *image)
+            }
+            JournalEntry::AddFill {
+                page_id,
+                layer_index,
+                fill,
+            } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                if layers.find_fill_layer(fill.id).is_some() {
+                    return false;
+                }
+                match layers.layers.get_mut(*layer_index) {
+                    Some(layer) => {
+                        layer.add_fill(fill.clone());
+                        true
+                    }
+                    None => false,
+                }
+            }
+            JournalEntry::RemoveFill { page_id, fill_id } => {
+                let Some(page) = page_mut(pages, *page_id) else {
+                    return false;
+                };
+                let layers = &mut page.layer_manager;
+                match layers.find_fill_layer(*fill_id) {
+                    Some(i) => layers.layers[i].remove_fill(*fill_id).is_some(),
+                    None => false,
+                }
+            }
+            JournalEntry::ImportImage { .. } => false,
+        }
+    }
+}
+
+fn page_mut(pages: &mut [Page], page_id: Uuid) -> Option<&mut Page> {
+    pages.iter_mut().find(|p| p.id == page_id)
+}
+
+/// Buffers entries between drains. Disabled by default so engines that never drain
+/// don't accumulate memory.
+#[derive(Debug, Default)]
+pub struct Journal {
+    pending: Vec<JournalEntry>,
+    enabled: bool,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.pending.clear();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn record(&mut self, entry: JournalEntry) {
+        if self.enabled {
+            self.pending.push(entry);
+        }
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Take all pending entries as newline-terminated JSON lines, ready to append to a file.
+    pub fn drain(&mut self) -> Result<String, EngineError> {
+        let mut out = String::new();
+        for entry in self.pending.drain(..) {
+            let line = serde_json::to_string(&entry).map_err(|e| EngineError::Serialization {
+                message: e.to_string(),
+            })?;
+            out.push_str(&line);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+/// Outcome of replaying a journal onto a snapshot.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecoveryReport {
+    /// Entries that changed the document.
+    pub applied: usize,
+    /// Entries already reflected in the snapshot or referring to missing pages or layers.
+    pub skipped: usize,
+    /// Lines that could not be parsed (typically a partial last write).
+    pub corrupt_lines: usize,
+    /// Issues fixed while loading the snapshot.
+    pub repair: ValidationReport,
+    /// Images placed in the recovered document whose bytes are in neither the engine,
+    /// the snapshot nor the journal. They render as `DrawImage` commands the host can't
+    /// resolve until it imports the bytes again.
+    pub missing_images: Vec<Uuid>,
+}
+
+/// Parse journal text, tolerating blank and corrupt lines. Returns the entries and
+/// the number of lines that failed to parse.
+pub fn parse_journal(text: &str) -> (Vec<JournalEntry>, usize) {
+    let mut entries = Vec::new();
+    let mut corrupt = 0;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) => corrupt += 1,
+        }
+    }
+    (entries, corrupt)
+}
+
+/// Image bytes as standard, padded base64 text, a third the size of a JSON number array.
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        STANDARD.decode(text).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brush::BrushConfig;
+
+    #[test]
+    fn test_disabled_journal_records_nothing() {
+        let mut journal = Journal::new();
+        journal.record(JournalEntry::SetActiveLayer {
+            page_id: Uuid::new_v4(),
+            layer_id: Uuid::new_v4(),
+        });
+        assert_eq!(journal.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_drain_roundtrip_with_partial_tail() {
+        let mut journal = Journal::new();
+        journal.set_enabled(true);
+        journal.record(JournalEntry::AddStroke {
+            page_id: Uuid::new_v4(),
+            layer_index: 0,
+            stroke: Stroke::new(BrushConfig::default()),
+        });
+        journal.record(JournalEntry::SetActiveLayer {
+            page_id: Uuid::new_v4(),
+            layer_id: Uuid::new_v4(),
+        });
+        let mut text = journal.drain().unwrap();
+        assert_eq!(journal.pending_len(), 0);
+        assert_eq!(text.lines().count(), 2);
+
+        text.push_str("{\"op\":\"add_str");
+        let (entries, corrupt) = parse_journal(&text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(corrupt, 1);
+    }
+
+    #[test]
+    fn test_image_bytes_roundtrip_as_base64() {
+        for len in 0..6 {
+            let bytes: Vec<u8> = (0..len).map(|i| 250 - i as u8 * 61).collect();
+            let entry = JournalEntry::ImportImage {
+                image_id: Uuid::new_v4(),
+                bytes: bytes.clone(),
+            };
+            let line = serde_json::to_string(&entry).unwrap();
+            match serde_json::from_str(&line).unwrap() {
+                JournalEntry::ImportImage { bytes: decoded, .. } => assert_eq!(decoded, bytes),
+                other => panic!("unexpected entry {other:?}"),
+            }
+        }
+        let line = r#"{"op":"import_image","image_id":"00000000-0000-0000-0000-000000000000","bytes":"/9j/"}"#;
+        let (entries, corrupt) = parse_journal(line);
+        assert_eq!(corrupt, 0);
+        assert!(matches!(&entries[0], JournalEntry::ImportImage { bytes, .. } if bytes == &[0xFF, 0xD8, 0xFF]));
+    }
+
+    #[test]
+    fn test_rejects_malformed_image_bytes() {
+        let line = |bytes: &str| {
+            format!(
+                r#"{{"op":"import_image","image_id":"00000000-0000-0000-0000-000000000000","bytes":"{bytes}"}}"#
+            )
+        };
+        for bad in ["/9j*", "/9j", "/9j/=", "/w==/w==", "/w=", "////_-8="] {
+            let (entries, corrupt) = parse_journal(&line(bad));
+            assert!(entries.is_empty(), "{bad:?} decoded");
+            assert_eq!(corrupt, 1);
+        }
+        let (entries, corrupt) = parse_journal(&line("/w=="));
+        assert_eq!(corrupt, 0);
+        assert!(matches!(&entries[0], JournalEntry::ImportImage { bytes, .. } if bytes == &[0xFF]));
+    }
+
+    #[test]
+    fn test_replay_is_idempotent() {
+        let mut pages = vec![Page::new(100.0, 100.0)];
+        let page_id = pages[0].id;
+        let stroke = Stroke::new(BrushConfig::default());
+        let add = JournalEntry::AddStroke {
+            page_id,
+            layer_index: 0,
+            stroke: stroke.clone(),
+        };
+        assert!(add.apply(&mut pages, None));
+        assert!(!add.apply(&mut pages, None));
+
+        let remove = JournalEntry::RemoveStroke {
+            page_id,
+            layer_index: 0,
+            stroke_id: stroke.id,
+        };
+        assert!(remove.apply(&mut pages, None));
+        assert!(!remove.apply(&mut pages, None));
+        assert_eq!(pages[0].stroke_count(), 0);
+    }
+
+    #[test]
+    fn test_replay_page_operations() {
+        let mut pages = vec![Page::new(100.0, 100.0)];
+        let first = pages[0].id;
+        let extra = Page::new(50.0, 50.0);
+        let extra_id = extra.id;
+        let insert = JournalEntry::InsertPage {
+            index: 0,
+            page: extra,
+        };
+        assert!(insert.apply(&mut pages, None));
+        assert!(!insert.apply(&mut pages, None));
+        assert_eq!(pages[0].id, extra_id);
+
+        let move_first = JournalEntry::MovePage {
+            page_id: first,
+            to_index: 0,
+        };
+        assert!(move_first.apply(&mut pages, None));
+        assert!(!move_first.apply(&mut pages, None));
+        assert_eq!(pages[0].id, first);
+
+        assert!(JournalEntry::RemovePage { page_id: extra_id }.apply(&mut pages, None));
+        assert!(!JournalEntry::RemovePage { page_id: first }.apply(&mut pages, None));
+        assert_eq!(pages.len(), 1);
+    }
+}
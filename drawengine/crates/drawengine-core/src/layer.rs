@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::error::EngineError;
+use crate::fill::FillRegion;
+use crate::image::Image;
 use crate::point::BoundingBox;
+use crate::shape::Shape;
 use crate::stroke::Stroke;
+use crate::text::{TextBox, TextMeasure};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Layer {
@@ -11,6 +16,18 @@ pub struct Layer {
     pub visible: bool,
     pub opacity: f32,
     pub strokes: Vec<Stroke>,
+    /// Drawn beneath the layer's shapes and strokes, in order.
+    #[serde(default)]
+    pub images: Vec<Image>,
+    /// Drawn above the layer's images and beneath everything else, in order.
+    #[serde(default)]
+    pub fills: Vec<FillRegion>,
+    /// Drawn beneath the layer's strokes, in order.
+    #[serde(default)]
+    pub shapes: Vec<Shape>,
+    /// Drawn above the layer's strokes, in order.
+    #[serde(default)]
+    pub texts: Vec<TextBox>,
 }
 
 impl Layer {
@@ -21,6 +38,10 @@ impl Layer {
             visible: true,
             opacity: 1.0,
             strokes: Vec::new(),
+            images: Vec::new(),
+            fills: Vec::new(),
+            shapes: Vec::new(),
+            texts: Vec::new(),
         }
     }
 
@@ -36,17 +57,87 @@ impl Layer {
         }
     }
 
-    pub fn bounding_box(&self) -> BoundingBox {
+    pub fn add_shape(&mut self, shape: Shape) {
+        self.shapes.push(shape);
+    }
+
+    pub fn remove_shape(&mut self, shape_id: Uuid) -> Option<Shape> {
+        let idx = self.shapes.iter().position(|s| s.id == shape_id)?;
+        Some(self.shapes.remove(idx))
+    }
+
+    /// Swap in `shape` for the shape with the same id, keeping its place in the order.
+    /// Returns the old shape.
+    pub fn replace_shape(&mut self, shape: Shape) -> Option<Shape> {
+        let existing = self.shapes.iter_mut().find(|s| s.id == shape.id)?;
+        Some(std::mem::replace(existing, shape))
+    }
+
+    pub fn add_text(&mut self, text: TextBox) {
+        self.texts.push(text);
+    }
+
+    pub fn remove_text(&mut self, text_id: Uuid) -> Option<TextBox> {
+        let idx = self.texts.iter().position(|t| t.id == text_id)?;
+        Some(self.texts.remove(idx))
+    }
+
+    /// Swap in `text` for the text box with the same id, keeping its place in the order.
+    /// Returns the old text box.
+    pub fn replace_text(&mut self, text: TextBox) -> Option<TextBox> {
+        let existing = self.texts.iter_mut().find(|t| t.id == text.id)?;
+        Some(std::mem::replace(existing, text))
+    }
+
+    pub fn add_image(&mut self, image: Image) {
+        self.images.push(image);
+    }
+
+    pub fn remove_image(&mut self, image_id: Uuid) -> Option<Image> {
+        let idx = self.images.iter().position(|i| i.id == image_id)?;
+        Some(self.images.remove(idx))
+    }
+
+    /// Swap in `image` for the image with the same id, keeping its place in the order.
+    /// Returns the old image.
+    pub fn replace_image(&mut self, image: Image) -> Option<Image> {
+        let existing = self.images.iter_mut().find(|i| i.id == image.id)?;
+        Some(std::mem::replace(existing, image))
+    }
+
+    pub fn add_fill(&mut self, fill: FillRegion) {
+        self.fills.push(fill);
+    }
+
+    pub fn remove_fill(&mut self, fill_id: Uuid) -> Option<FillRegion> {
+        let idx = self.fills.iter().position(|f| f.id == fill_id)?;
+        Some(self.fills.remove(idx))
+    }
+
+    pub fn bounding_box(&self, text_measure: &dyn TextMeasure) -> BoundingBox {
         let mut bb = BoundingBox::empty();
         for stroke in &self.strokes {
             if stroke.bounding_box.is_valid() {
                 bb = bb.union(&stroke.bounding_box);
             }
         }
+        for image in &self.images {
+            bb = bb.union(&image.bounding_box());
+        }
+        for fill in &self.fills {
+            bb = bb.union(&fill.bounding_box());
+        }
+        for shape in &self.shapes {
+            bb = bb.union(&shape.bounding_box());
+        }
+        for text in &self.texts {
+            bb = bb.union(&text.bounding_box(text_measure));
+        }
         bb
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayerManager {
     pub layers: Vec<Layer>,
     pub active_layer_index: usize,
@@ -73,6 +164,16 @@ impl LayerManager {
         self.layers[self.active_layer_index].id
     }
 
+    pub fn set_active_layer(&mut self, layer_id: Uuid) -> Result<(), EngineError> {
+        let idx = self
+            .layers
+            .iter()
+            .position(|l| l.id == layer_id)
+            .ok_or(EngineError::LayerNotFound { layer_id })?;
+        self.active_layer_index = idx;
+        Ok(())
+    }
+
     pub fn all_visible_strokes(&self) -> Vec<&Stroke> {
         self.layers
             .iter()
@@ -81,6 +182,30 @@ impl LayerManager {
             .collect()
     }
 
+    pub fn find_shape_layer(&self, shape_id: Uuid) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|l| l.shapes.iter().any(|s| s.id == shape_id))
+    }
+
+    pub fn find_image_layer(&self, image_id: Uuid) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|l| l.images.iter().any(|i| i.id == image_id))
+    }
+
+    pub fn find_fill_layer(&self, fill_id: Uuid) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|l| l.fills.iter().any(|f| f.id == fill_id))
+    }
+
+    pub fn find_text_layer(&self, text_id: Uuid) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|l| l.texts.iter().any(|t| t.id == text_id))
+    }
+
     pub fn find_stroke_layer(&self, stroke_id: Uuid) -> Option<usize> {
         self.layers
             .iter()
@@ -127,4 +252,16 @@ mod tests {
         let visible = mgr.all_visible_strokes();
         assert_eq!(visible.len(), 1);
     }
+
+    #[test]
+    fn test_set_active_layer_unknown_id() {
+        let mut mgr = LayerManager::new();
+        let missing = Uuid::new_v4();
+        assert_eq!(
+            mgr.set_active_layer(missing),
+            Err(EngineError::LayerNotFound { layer_id: missing })
+        );
+        let id = mgr.active_layer_id();
+        assert!(mgr.set_active_layer(id).is_ok());
+    }
 }
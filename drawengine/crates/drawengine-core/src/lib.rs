@@ -1,11 +1,29 @@
 pub mod brush;
 pub mod canvas;
+pub mod dash;
 pub mod eraser;
+pub mod fill;
+pub mod error;
 pub mod geometry;
 pub mod history;
+pub mod image;
+pub mod infinite;
+pub mod journal;
 pub mod layer;
+pub mod nib;
+pub mod package;
+pub mod page;
 pub mod point;
+pub mod prediction;
+pub mod preset;
+pub mod pressure;
+pub mod recognition;
 pub mod render;
 pub mod serialization;
+pub mod shape;
+pub mod stamp;
 pub mod stroke;
+pub mod template;
+pub mod text;
 pub mod transform;
+pub mod validation;
@@ -0,0 +1,152 @@
+use std::f64::consts::FRAC_PI_4;
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::BezierSegment;
+use crate::point::Point;
+
+/// Nib positions sampled per segment when sweeping the outline.
+const NIB_SAMPLES: usize = 8;
+
+/// A flat calligraphy nib. Its length is the brush width; it stays at `angle` however
+/// the stroke turns, so strokes are broad across the nib and thin along it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NibSettings {
+    /// Radians, clockwise from the positive x axis.
+    pub angle: f64,
+    /// Nib thickness as a fraction of its length; the thinnest line it can draw.
+    pub thickness: f64,
+}
+
+impl Default for NibSettings {
+    fn default() -> Self {
+        Self {
+            angle: FRAC_PI_4,
+            thickness: 0.1,
+        }
+    }
+}
+
+impl NibSettings {
+    /// How far the nib reaches from the centerline, as a multiple of the stroke width.
+    pub fn reach(&self) -> f64 {
+        0.5 * (1.0 + self.thickness.clamp(0.0, 1.0).powi(2)).sqrt()
+    }
+}
+
+/// Outline of a nib swept along `segments`, as convex polygons to fill together with
+/// the nonzero rule. All polygons have the same winding, so overlaps don't cancel.
+pub fn nib_outline(segments: &[BezierSegment], nib: &NibSettings) -> Vec<Vec<Point>> {
+    let along = Point::new(nib.angle.cos(), nib.angle.sin());
+    let across = Point::new(-along.y, along.x);
+    let corners = |center: Point, width: f64| {
+        let half_length = along * (width * 0.5);
+        let half_thickness = across * (width * nib.thickness.clamp(0.0, 1.0) * 0.5);
+        [
+            center + half_length + half_thickness,
+            center + half_length - half_thickness,
+            center - half_length - half_thickness,
+            center - half_length + half_thickness,
+        ]
+    };
+
+    let mut polygons = Vec::with_capacity(segments.len() * NIB_SAMPLES);
+    for seg in segments {
+        let mut prev = corners(seg.p0, seg.start_width);
+        for step in 1..=NIB_SAMPLES {
+            let t = step as f64 / NIB_SAMPLES as f64;
+            let next = corners(seg.evaluate(t), seg.width_at(t));
+            let mut points = prev.to_vec();
+            points.extend_from_slice(&next);
+            polygons.push(convex_hull(points));
+            prev = next;
+        }
+    }
+    polygons
+}
+
+/// Counter-clockwise (in y-up terms) hull via the monotone chain algorithm.
+fn convex_hull(mut points: Vec<Point>) -> Vec<Point> {
+    if points.len() < 3 {
+        return points;
+    }
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    let turns_left = |hull: &[Point], p: Point| {
+        let (o, a) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+        (a.x - o.x) * (p.y - o.y) - (a.y - o.y) * (p.x - o.x) > 0.0
+    };
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() * 2);
+    for &p in &points {
+        while hull.len() >= 2 && !turns_left(&hull, p) {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    let lower = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        while hull.len() >= lower && !turns_left(&hull, p) {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // The upper chain ends where the lower one started.
+    hull.pop();
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn line(from: Point, to: Point, width: f64) -> BezierSegment {
+        BezierSegment {
+            p0: from,
+            p1: from.lerp(&to, 1.0 / 3.0),
+            p2: from.lerp(&to, 2.0 / 3.0),
+            p3: to,
+            start_width: width,
+            end_width: width,
+            colors: None,
+        }
+    }
+
+    fn extent(polygons: &[Vec<Point>], axis: fn(&Point) -> f64) -> f64 {
+        let values = polygons.iter().flatten().map(axis);
+        let (min, max) = values.fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        max - min
+    }
+
+    #[test]
+    fn test_width_depends_on_direction() {
+        // Vertical nib: horizontal strokes are broad, vertical strokes are thin.
+        let nib = NibSettings {
+            angle: FRAC_PI_2,
+            thickness: 0.1,
+        };
+        let across = nib_outline(&[line(Point::new(0.0, 0.0), Point::new(50.0, 0.0), 10.0)], &nib);
+        let along = nib_outline(&[line(Point::new(0.0, 0.0), Point::new(0.0, 50.0), 10.0)], &nib);
+        assert!((extent(&across, |p| p.y) - 10.0).abs() < 1e-9);
+        assert!((extent(&along, |p| p.x) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hull_is_convex_and_consistently_wound() {
+        let hull = convex_hull(vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 2.0),
+            Point::new(0.0, 2.0),
+        ]);
+        assert_eq!(hull.len(), 4);
+        let area: f64 = (0..hull.len())
+            .map(|i| {
+                let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum();
+        assert!((area * 0.5 - 4.0).abs() < 1e-9);
+    }
+}
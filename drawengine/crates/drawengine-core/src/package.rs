@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::EngineError;
+
+/// File signature at the start of every package.
+pub const PACKAGE_MAGIC: [u8; 4] = *b"DEPK";
+/// Container layout version (independent of the document format version).
+pub const PACKAGE_VERSION: u32 = 1;
+
+pub const METADATA_ENTRY: &str = "metadata.json";
+pub const THUMBNAIL_ENTRY: &str = "thumbnail.png";
+pub const DOCUMENT_ENTRY: &str = "document.json";
+/// Imported image bytes are stored as `images/<image id>`.
+pub const IMAGE_ENTRY_PREFIX: &str = "images/";
+
+pub fn image_entry_name(image_id: Uuid) -> String {
+    format!("{IMAGE_ENTRY_PREFIX}{image_id}")
+}
+
+/// Lightweight description of a document, readable without parsing strokes.
+/// Times are seconds since the Unix epoch, supplied by the host app.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    pub title: String,
+    pub created_at: f64,
+    pub modified_at: f64,
+    pub tags: Vec<String>,
+    pub app_version: String,
+}
+
+/// Builds a package: a table of contents followed by the raw entry bytes.
+///
+/// Layout (little-endian):
+/// `magic[4] version:u32 count:u32 { name_len:u16 name offset:u64 len:u64 }* data`
+#[derive(Debug, Default)]
+pub struct PackageWriter {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl PackageWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_entry(&mut self, name: impl Into<String>, bytes: Vec<u8>) {
+        self.entries.push((name.into(), bytes));
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        let header_len: usize = 12
+            + self
+                .entries
+                .iter()
+                .map(|(name, _)| 2 + name.len() + 16)
+                .sum::<usize>();
+        let data_len: usize = self.entries.iter().map(|(_, b)| b.len()).sum();
+
+        let mut out = Vec::with_capacity(header_len + data_len);
+        out.extend_from_slice(&PACKAGE_MAGIC);
+        out.extend_from_slice(&PACKAGE_VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        let mut offset = header_len as u64;
+        for (name, bytes) in &self.entries {
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            offset += bytes.len() as u64;
+        }
+        for (_, bytes) in &self.entries {
+            out.extend_from_slice(bytes);
+        }
+        out
+    }
+}
+
+/// Reads the table of contents and hands out entry slices without copying.
+#[derive(Debug)]
+pub struct PackageReader<'a> {
+    bytes: &'a [u8],
+    entries: Vec<(String, usize, usize)>,
+}
+
+impl<'a> PackageReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, EngineError> {
+        let mut cursor = Cursor { bytes, pos: 0 };
+        if cursor.take(4)? != PACKAGE_MAGIC {
+            return Err(invalid("not a document package"));
+        }
+        let version = cursor.read_u32()?;
+        if version > PACKAGE_VERSION {
+            return Err(EngineError::UnsupportedVersion {
+                found: version,
+                supported: PACKAGE_VERSION,
+            });
+        }
+        let count = cursor.read_u32()?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let name_len = cursor.read_u16()? as usize;
+            let name = std::str::from_utf8(cursor.take(name_len)?)
+                .map_err(|_| invalid("entry name is not UTF-8"))?
+                .to_string();
+            let offset = cursor.read_u64()? as usize;
+            let len = cursor.read_u64()? as usize;
+            if offset.checked_add(len).is_none_or(|end| end > bytes.len()) {
+                return Err(invalid(&format!("entry {name} extends past end of file")));
+            }
+            entries.push((name, offset, len));
+        }
+        Ok(Self { bytes, entries })
+    }
+
+    pub fn entry_names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _, _)| name.as_str())
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&'a [u8]> {
+        self.entries
+            .iter()
+            .find(|(n, _, _)| n == name)
+            .map(|&(_, offset, len)| &self.bytes[offset..offset + len])
+    }
+
+    pub fn require(&self, name: &str) -> Result<&'a [u8], EngineError> {
+        self.entry(name)
+            .ok_or_else(|| invalid(&format!("missing entry {name}")))
+    }
+
+    pub fn metadata(&self) -> Result<DocumentMetadata, EngineError> {
+        Ok(serde_json::from_slice(self.require(METADATA_ENTRY)?)?)
+    }
+
+    /// The preview image, or an empty slice if the package has none.
+    pub fn thumbnail(&self) -> &'a [u8] {
+        self.entry(THUMBNAIL_ENTRY).unwrap_or(&[])
+    }
+
+    /// Image ids and encoded bytes of every image entry.
+    pub fn images(&self) -> impl Iterator<Item = (Uuid, &'a [u8])> + '_ {
+        self.entries.iter().filter_map(|(name, offset, len)| {
+            let id = Uuid::parse_str(name.strip_prefix(IMAGE_ENTRY_PREFIX)?).ok()?;
+            Some((id, &self.bytes[*offset..offset + len]))
+        })
+    }
+
+    pub fn document_json(&self) -> Result<&'a str, EngineError> {
+        std::str::from_utf8(self.require(DOCUMENT_ENTRY)?)
+            .map_err(|_| invalid("document body is not UTF-8"))
+    }
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], EngineError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("truncated header"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, EngineError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, EngineError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, EngineError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+fn invalid(message: &str) -> EngineError {
+    EngineError::InvalidPackage {
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_metadata() -> DocumentMetadata {
+        DocumentMetadata {
+            title: "Lecture 3".to_string(),
+            created_at: 1_700_000_000.0,
+            modified_at: 1_700_000_500.0,
+            tags: vec!["math".to_string()],
+            app_version: "1.2.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_entries() {
+        let mut writer = PackageWriter::new();
+        writer.add_entry(
+            METADATA_ENTRY,
+            serde_json::to_vec(&sample_metadata()).unwrap(),
+        );
+        writer.add_entry(THUMBNAIL_ENTRY, vec![0x89, b'P', b'N', b'G']);
+        writer.add_entry(DOCUMENT_ENTRY, b"{}".to_vec());
+        let image_id = Uuid::new_v4();
+        writer.add_entry(image_entry_name(image_id), vec![0xFF, 0xD8]);
+        let bytes = writer.finish();
+
+        let reader = PackageReader::new(&bytes).unwrap();
+        assert_eq!(reader.metadata().unwrap(), sample_metadata());
+        assert_eq!(reader.thumbnail(), &[0x89, b'P', b'N', b'G']);
+        assert_eq!(reader.document_json().unwrap(), "{}");
+        assert_eq!(reader.entry_names().count(), 4);
+        let images: Vec<_> = reader.images().collect();
+        assert_eq!(images, [(image_id, &[0xFF, 0xD8][..])]);
+    }
+
+    #[test]
+    fn test_rejects_bad_magic_and_truncation() {
+        assert!(matches!(
+            PackageReader::new(b"NOPE\x01\x00\x00\x00"),
+            Err(EngineError::InvalidPackage { .. })
+        ));
+
+        let mut writer = PackageWriter::new();
+        writer.add_entry(DOCUMENT_ENTRY, b"{\"x\":1}".to_vec());
+        let bytes = writer.finish();
+        assert!(matches!(
+            PackageReader::new(&bytes[..bytes.len() - 2]),
+            Err(EngineError::InvalidPackage { .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_thumbnail_is_empty() {
+        let bytes = PackageWriter::new().finish();
+        let reader = PackageReader::new(&bytes).unwrap();
+        assert!(reader.thumbnail().is_empty());
+        assert!(reader.metadata().is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::layer::LayerManager;
+use crate::point::{Color, Point};
+use crate::template::PageTemplate;
+
+/// A single page of a document with its own size, background and layers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page {
+    pub id: Uuid,
+    pub width: f64,
+    pub height: f64,
+    pub background_color: Color,
+    #[serde(default)]
+    pub template: PageTemplate,
+    pub layer_manager: LayerManager,
+}
+
+impl Page {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            width,
+            height,
+            background_color: Color::white(),
+            template: PageTemplate::Blank,
+            layer_manager: LayerManager::new(),
+        }
+    }
+
+    /// Deep copy with fresh ids for the page and everything on it, so the copy can live
+    /// in the same document.
+    pub fn duplicate(&self) -> Self {
+        let mut copy = self.clone();
+        copy.id = Uuid::new_v4();
+        for layer in &mut copy.layer_manager.layers {
+            layer.id = Uuid::new_v4();
+            for stroke in &mut layer.strokes {
+                stroke.id = Uuid::new_v4();
+            }
+            for image in &mut layer.images {
+                image.id = Uuid::new_v4();
+            }
+            for fill in &mut layer.fills {
+                fill.id = Uuid::new_v4();
+            }
+            for shape in &mut layer.shapes {
+                shape.id = Uuid::new_v4();
+            }
+            for text in &mut layer.texts {
+                text.id = Uuid::new_v4();
+            }
+        }
+        copy
+    }
+
+    pub fn stroke_count(&self) -> usize {
+        self.layer_manager
+            .layers
+            .iter()
+            .map(|l| l.strokes.len())
+            .sum()
+    }
+}
+
+/// How pages are arranged in canvas space for rendering and input.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PageLayout {
+    /// Only the current page is shown, with its top-left corner at the canvas origin.
+    #[default]
+    Single,
+    /// All pages stacked top to bottom, horizontally centered, separated by `gap`.
+    /// `background` fills the area around the pages.
+    ContinuousVertical { gap: f64, background: Color },
+}
+
+impl PageLayout {
+    /// Canvas-space origin of each page. In `Single` layout every page sits at the origin.
+    pub fn page_origins(&self, pages: &[Page]) -> Vec<Point> {
+        match *self {
+            PageLayout::Single => vec![Point::new(0.0, 0.0); pages.len()],
+            PageLayout::ContinuousVertical { gap, .. } => {
+                let max_width = pages.iter().map(|p| p.width).fold(0.0, f64::max);
+                let mut y = 0.0;
+                pages
+                    .iter()
+                    .map(|p| {
+                        let origin = Point::new((max_width - p.width) * 0.5, y);
+                        y += p.height + gap;
+                        origin
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+pub fn find_page(pages: &[Page], page_id: Uuid) -> Option<usize> {
+    pages.iter().position(|p| p.id == page_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brush::BrushConfig;
+    use crate::stroke::Stroke;
+
+    #[test]
+    fn test_duplicate_assigns_new_ids() {
+        let mut page = Page::new(100.0, 200.0);
+        page.layer_manager
+            .active_layer_mut()
+            .add_stroke(Stroke::new(BrushConfig::default()));
+        let copy = page.duplicate();
+        assert_ne!(copy.id, page.id);
+        assert_ne!(copy.layer_manager.layers[0].id, page.layer_manager.layers[0].id);
+        assert_ne!(
+            copy.layer_manager.layers[0].strokes[0].id,
+            page.layer_manager.layers[0].strokes[0].id
+        );
+        assert_eq!(copy.stroke_count(), 1);
+    }
+
+    #[test]
+    fn test_continuous_origins() {
+        let pages = vec![Page::new(100.0, 200.0), Page::new(50.0, 100.0)];
+        let layout = PageLayout::ContinuousVertical {
+            gap: 10.0,
+            background: Color::white(),
+        };
+        let origins = layout.page_origins(&pages);
+        assert_eq!(origins[0], Point::new(0.0, 0.0));
+        assert_eq!(origins[1], Point::new(25.0, 210.0));
+    }
+}
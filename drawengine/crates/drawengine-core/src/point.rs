@@ -53,11 +53,38 @@ impl std::ops::Mul<f64> for Point {
     }
 }
 
+/// Kind of device that produced an input sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointerType {
+    #[default]
+    Unknown,
+    Finger,
+    Pen,
+    Mouse,
+    /// The eraser end of a stylus.
+    PenEraser,
+}
+
+impl PointerType {
+    fn is_unknown(&self) -> bool {
+        *self == PointerType::Unknown
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct StrokePoint {
     pub position: Point,
     pub pressure: f64,
     pub timestamp: f64,
+    /// Stylus altitude in radians: π/2 is perpendicular to the screen, 0 lies flat.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+    /// Stylus azimuth in radians, clockwise from the positive x axis.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azimuth: Option<f64>,
+    #[serde(default, skip_serializing_if = "PointerType::is_unknown")]
+    pub pointer_type: PointerType,
 }
 
 impl StrokePoint {
@@ -66,9 +93,23 @@ impl StrokePoint {
             position: Point::new(x, y),
             pressure,
             timestamp,
+            altitude: None,
+            azimuth: None,
+            pointer_type: PointerType::Unknown,
         }
     }
 
+    pub fn with_tilt(mut self, altitude: f64, azimuth: f64) -> Self {
+        self.altitude = Some(altitude);
+        self.azimuth = Some(azimuth);
+        self
+    }
+
+    pub fn with_pointer_type(mut self, pointer_type: PointerType) -> Self {
+        self.pointer_type = pointer_type;
+        self
+    }
+
     pub fn speed_to(&self, other: &StrokePoint) -> f64 {
         let dist = self.position.distance_to(&other.position);
         let dt = (other.timestamp - self.timestamp).abs();
@@ -107,6 +148,15 @@ impl Color {
         let b = (hex & 0xFF) as f32 / 255.0;
         Self::new(r, g, b, 1.0)
     }
+
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        Color::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -233,4 +283,15 @@ mod tests {
         let b = StrokePoint::new(3.0, 4.0, 1.0, 1.0);
         assert!((a.speed_to(&b) - 5.0).abs() < 1e-9);
     }
+
+    #[test]
+    fn test_stroke_point_without_stylus_fields() {
+        let p: StrokePoint = serde_json::from_str(
+            r#"{"position":{"x":1.0,"y":2.0},"pressure":0.5,"timestamp":0.0}"#,
+        )
+        .unwrap();
+        assert_eq!(p, StrokePoint::new(1.0, 2.0, 0.5, 0.0));
+        let json = serde_json::to_string(&p).unwrap();
+        assert!(!json.contains("altitude") && !json.contains("pointer_type"));
+    }
 }
@@ -0,0 +1,120 @@
+use crate::geometry::BezierSegment;
+use crate::point::{Point, StrokePoint};
+
+/// Longest extrapolation allowed, in seconds. Beyond this predictions overshoot visibly.
+pub const MAX_PREDICTION_LOOKAHEAD: f64 = 0.05;
+
+/// Points predicted between the last sample and `lookahead` seconds ahead.
+const PREDICTED_POINT_COUNT: usize = 3;
+
+/// Extrapolate `lookahead` seconds past the last of `recent` using velocity and
+/// acceleration from the latest samples. Returns nothing when there is too little
+/// history or the timestamps don't advance.
+pub fn predict_points(recent: &[StrokePoint], lookahead: f64) -> Vec<StrokePoint> {
+    let n = recent.len();
+    if n < 2 || lookahead.is_nan() || lookahead <= 0.0 {
+        return vec![];
+    }
+    let lookahead = lookahead.min(MAX_PREDICTION_LOOKAHEAD);
+    let last = recent[n - 1];
+    let prev = recent[n - 2];
+    let dt = last.timestamp - prev.timestamp;
+    if dt.is_nan() || dt <= 1e-6 {
+        return vec![];
+    }
+    let velocity = (last.position - prev.position) * (1.0 / dt);
+
+    // Acceleration from the previous interval, if there is one with a sane timestamp.
+    let prev_dt = if n >= 3 {
+        prev.timestamp - recent[n - 3].timestamp
+    } else {
+        0.0
+    };
+    let acceleration = if prev_dt > 1e-6 {
+        let prev_velocity = (prev.position - recent[n - 3].position) * (1.0 / prev_dt);
+        (velocity - prev_velocity) * (2.0 / (dt + prev_dt))
+    } else {
+        Point::new(0.0, 0.0)
+    };
+
+    (1..=PREDICTED_POINT_COUNT)
+        .map(|i| {
+            let t = lookahead * i as f64 / PREDICTED_POINT_COUNT as f64;
+            let mut offset = velocity * t + acceleration * (0.5 * t * t);
+            // Never predict further than constant velocity would, so a burst of
+            // acceleration can't fling the prediction far ahead of the pen.
+            let max_distance = velocity.distance_to(&Point::new(0.0, 0.0)) * t;
+            let distance = offset.distance_to(&Point::new(0.0, 0.0));
+            if distance > max_distance && distance > 0.0 {
+                offset = offset * (max_distance / distance);
+            }
+            StrokePoint {
+                position: last.position + offset,
+                timestamp: last.timestamp + t,
+                ..last
+            }
+        })
+        .collect()
+}
+
+/// Straight segments from `start` through `predicted`, all at `width`.
+pub fn prediction_segments(
+    start: Point,
+    predicted: &[StrokePoint],
+    width: f64,
+) -> Vec<BezierSegment> {
+    let mut from = start;
+    predicted
+        .iter()
+        .map(|p| {
+            let to = p.position;
+            let seg = BezierSegment {
+                p0: from,
+                p1: from.lerp(&to, 1.0 / 3.0),
+                p2: from.lerp(&to, 2.0 / 3.0),
+                p3: to,
+                start_width: width,
+                end_width: width,
+                colors: None,
+            };
+            from = to;
+            seg
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_velocity_prediction() {
+        let recent = [
+            StrokePoint::new(0.0, 0.0, 0.5, 0.0),
+            StrokePoint::new(10.0, 0.0, 0.5, 0.01),
+            StrokePoint::new(20.0, 0.0, 0.5, 0.02),
+        ];
+        let predicted = predict_points(&recent, 0.03);
+        assert_eq!(predicted.len(), 3);
+        let end = predicted.last().unwrap().position;
+        assert!((end.x - 50.0).abs() < 1e-9);
+        assert!(end.y.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_prediction_needs_history_and_is_capped() {
+        assert!(predict_points(&[StrokePoint::new(0.0, 0.0, 0.5, 0.0)], 0.02).is_empty());
+        let same_time = [
+            StrokePoint::new(0.0, 0.0, 0.5, 0.0),
+            StrokePoint::new(10.0, 0.0, 0.5, 0.0),
+        ];
+        assert!(predict_points(&same_time, 0.02).is_empty());
+
+        let recent = [
+            StrokePoint::new(0.0, 0.0, 0.5, 0.0),
+            StrokePoint::new(10.0, 0.0, 0.5, 0.01),
+        ];
+        let end = predict_points(&recent, 10.0).last().unwrap().position;
+        assert!((end.x - (10.0 + 1000.0 * MAX_PREDICTION_LOOKAHEAD)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,333 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::brush::BrushConfig;
+use crate::error::EngineError;
+use crate::nib::NibSettings;
+use crate::point::{Color, Point};
+use crate::pressure::PressureCurve;
+use crate::stamp::{StampSettings, StampTip};
+use crate::validation::brush_is_valid;
+
+/// Latest preset pack format written by `PresetLibrary::export_pack`.
+pub const PRESET_FORMAT_VERSION: u32 = 1;
+
+/// A named brush the user can pick from a palette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrushPreset {
+    pub id: Uuid,
+    pub name: String,
+    /// Revision of this preset; importing keeps whichever copy has the higher one.
+    pub version: u32,
+    pub brush: BrushConfig,
+}
+
+impl BrushPreset {
+    pub fn new(name: impl Into<String>, brush: BrushConfig) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            version: 1,
+            brush,
+        }
+    }
+
+    fn builtin(id: u128, name: &str, brush: BrushConfig) -> Self {
+        Self {
+            id: Uuid::from_u128(id),
+            ..Self::new(name, brush)
+        }
+    }
+}
+
+/// A shareable set of presets, as exported to JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PresetPack {
+    pub version: u32,
+    pub presets: Vec<BrushPreset>,
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u32,
+}
+
+/// Ordered collection of brush presets.
+#[derive(Debug, Clone, Default)]
+pub struct PresetLibrary {
+    presets: Vec<BrushPreset>,
+}
+
+impl PresetLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Library pre-filled with the presets that ship with the engine.
+    pub fn with_builtins() -> Self {
+        Self {
+            presets: builtin_presets(),
+        }
+    }
+
+    pub fn presets(&self) -> &[BrushPreset] {
+        &self.presets
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&BrushPreset> {
+        self.presets.iter().find(|p| p.id == id)
+    }
+
+    /// Add `preset`, replacing any preset with the same id in place.
+    pub fn add(&mut self, preset: BrushPreset) {
+        match self.presets.iter_mut().find(|p| p.id == preset.id) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn remove(&mut self, id: Uuid) -> Result<BrushPreset, EngineError> {
+        let index = self
+            .presets
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or(EngineError::PresetNotFound { preset_id: id })?;
+        Ok(self.presets.remove(index))
+    }
+
+    /// JSON pack of the presets in `ids`, or of the whole library when `ids` is empty.
+    pub fn export_pack(&self, ids: &[Uuid]) -> Result<String, EngineError> {
+        let presets = if ids.is_empty() {
+            self.presets.clone()
+        } else {
+            ids.iter()
+                .map(|&id| {
+                    self.get(id)
+                        .cloned()
+                        .ok_or(EngineError::PresetNotFound { preset_id: id })
+                })
+                .collect::<Result<_, _>>()?
+        };
+        let pack = PresetPack {
+            version: PRESET_FORMAT_VERSION,
+            presets,
+        };
+        serde_json::to_string(&pack).map_err(|e| EngineError::Serialization {
+            message: e.to_string(),
+        })
+    }
+
+    /// Merge a pack into the library. Presets already present are only replaced by a
+    /// higher `version`. Nothing is imported if any preset is unusable.
+    /// Returns how many presets were added or updated.
+    pub fn import_pack(&mut self, json: &str) -> Result<usize, EngineError> {
+        let VersionProbe { version } = serde_json::from_str(json)?;
+        if version > PRESET_FORMAT_VERSION {
+            return Err(EngineError::UnsupportedVersion {
+                found: version,
+                supported: PRESET_FORMAT_VERSION,
+            });
+        }
+        let pack: PresetPack = serde_json::from_str(json)?;
+        if let Some(bad) = pack.presets.iter().find(|p| !brush_is_valid(&p.brush)) {
+            return Err(EngineError::InvalidGeometry {
+                message: format!("brush preset '{}' has invalid parameters", bad.name),
+            });
+        }
+        let mut changed = 0;
+        for preset in pack.presets {
+            if self.get(preset.id).is_some_and(|p| p.version >= preset.version) {
+                continue;
+            }
+            self.add(preset);
+            changed += 1;
+        }
+        Ok(changed)
+    }
+}
+
+fn builtin_presets() -> Vec<BrushPreset> {
+    let ink = Color::black();
+
+    let mut fountain = BrushConfig::pen(ink, 2.5);
+    fountain.pressure_sensitivity = 1.2;
+    fountain.taper_in = 6.0;
+    fountain.taper_out = 12.0;
+
+    let mut ballpoint = BrushConfig::pen(Color::from_hex(0x1F3A93), 1.5);
+    ballpoint.pressure_sensitivity = 0.3;
+    ballpoint.velocity_sensitivity = 0.1;
+
+    let mut brush_pen = BrushConfig::pen(ink, 5.0);
+    brush_pen.min_width_factor = 0.1;
+    brush_pen.max_width_factor = 2.0;
+    brush_pen.pressure_sensitivity = 1.8;
+    brush_pen.taper_in = 10.0;
+    brush_pen.taper_out = 25.0;
+
+    let mut pencil = BrushConfig::pen(Color::from_hex(0x3C3C3C), 2.0);
+    pencil.tilt_sensitivity = 2.0;
+    let light = vec![Point::new(0.0, 0.2), Point::new(1.0, 1.0)];
+    pencil.opacity_curve = PressureCurve::new(light).ok();
+    pencil.color_jitter = 0.15;
+
+    let mut marker = BrushConfig::pen(ink, 6.0);
+    marker.min_width_factor = 0.95;
+    marker.max_width_factor = 1.05;
+    marker.pressure_sensitivity = 0.0;
+    marker.velocity_sensitivity = 0.0;
+
+    let highlighter = BrushConfig::highlighter(Color::from_hex(0xFFE600), 16.0);
+
+    let graphite = BrushConfig::stamp(
+        Color::from_hex(0x4A4A4A),
+        2.0,
+        StampSettings {
+            tip: StampTip::Grain { density: 0.6 },
+            spacing: 0.15,
+            size_jitter: 0.1,
+            rotation_jitter: PI,
+            flow: 0.7,
+            ..StampSettings::default()
+        },
+    );
+
+    let mut charcoal = BrushConfig::stamp(
+        Color::from_hex(0x202020),
+        8.0,
+        StampSettings {
+            tip: StampTip::Grain { density: 0.35 },
+            spacing: 0.1,
+            size_jitter: 0.3,
+            rotation_jitter: PI,
+            scatter: 0.15,
+            flow: 0.5,
+            ..StampSettings::default()
+        },
+    );
+    charcoal.tilt_sensitivity = 1.5;
+
+    let mut airbrush = BrushConfig::stamp(
+        ink,
+        30.0,
+        StampSettings {
+            tip: StampTip::Round { hardness: 0.0 },
+            spacing: 0.05,
+            flow: 0.08,
+            ..StampSettings::default()
+        },
+    );
+    airbrush.opacity_curve = Some(PressureCurve::linear());
+
+    let mut italic = BrushConfig::calligraphy(
+        ink,
+        8.0,
+        NibSettings {
+            angle: -PI / 6.0,
+            thickness: 0.08,
+        },
+    );
+    italic.pressure_sensitivity = 0.4;
+
+    vec![
+        BrushPreset::builtin(1, "Fountain Pen", fountain),
+        BrushPreset::builtin(2, "Ballpoint", ballpoint),
+        BrushPreset::builtin(3, "Brush Pen", brush_pen),
+        BrushPreset::builtin(4, "Pencil", pencil),
+        BrushPreset::builtin(5, "Marker", marker),
+        BrushPreset::builtin(6, "Highlighter", highlighter),
+        BrushPreset::builtin(7, "Eraser", BrushConfig::eraser(20.0)),
+        BrushPreset::builtin(8, "Graphite", graphite),
+        BrushPreset::builtin(9, "Charcoal", charcoal),
+        BrushPreset::builtin(10, "Airbrush", airbrush),
+        BrushPreset::builtin(11, "Italic Nib", italic),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtins_are_usable_and_stable() {
+        let library = PresetLibrary::with_builtins();
+        assert!(library.presets().len() > 3);
+        assert!(library.presets().iter().all(|p| brush_is_valid(&p.brush)));
+        let again = PresetLibrary::with_builtins();
+        assert_eq!(library.presets()[0].id, again.presets()[0].id);
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut source = PresetLibrary::new();
+        let preset = BrushPreset::new("Sketch", BrushConfig::pen(Color::black(), 3.0));
+        let id = preset.id;
+        source.add(preset);
+        let json = source.export_pack(&[id]).unwrap();
+
+        let mut target = PresetLibrary::with_builtins();
+        let before = target.presets().len();
+        assert_eq!(target.import_pack(&json).unwrap(), 1);
+        assert_eq!(target.presets().len(), before + 1);
+        assert_eq!(target.get(id).unwrap().name, "Sketch");
+        // Same version again is a no-op.
+        assert_eq!(target.import_pack(&json).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_import_keeps_newer_version() {
+        let mut library = PresetLibrary::new();
+        let mut preset = BrushPreset::new("Ink", BrushConfig::pen(Color::black(), 2.0));
+        preset.version = 3;
+        library.add(preset.clone());
+
+        let mut older = preset.clone();
+        older.version = 2;
+        older.name = "Old ink".into();
+        let pack = PresetPack {
+            version: PRESET_FORMAT_VERSION,
+            presets: vec![older],
+        };
+        let json = serde_json::to_string(&pack).unwrap();
+        assert_eq!(library.import_pack(&json).unwrap(), 0);
+        assert_eq!(library.get(preset.id).unwrap().name, "Ink");
+    }
+
+    #[test]
+    fn test_import_rejects_bad_packs() {
+        let mut library = PresetLibrary::new();
+        let future = format!(r#"{{"version":{},"presets":[]}}"#, PRESET_FORMAT_VERSION + 1);
+        assert!(matches!(
+            library.import_pack(&future),
+            Err(EngineError::UnsupportedVersion { .. })
+        ));
+
+        let mut bad = BrushPreset::new("Bad", BrushConfig::pen(Color::black(), 2.0));
+        bad.brush.base_width = -1.0;
+        let pack = PresetPack {
+            version: PRESET_FORMAT_VERSION,
+            presets: vec![bad],
+        };
+        let json = serde_json::to_string(&pack).unwrap();
+        assert!(matches!(
+            library.import_pack(&json),
+            Err(EngineError::InvalidGeometry { .. })
+        ));
+        assert!(library.presets().is_empty());
+
+        let mut inverted = BrushPreset::new("Inverted", BrushConfig::pen(Color::black(), 2.0));
+        (inverted.brush.min_width_factor, inverted.brush.max_width_factor) = (2.0, 0.5);
+        let json = serde_json::to_string(&PresetPack {
+            version: PRESET_FORMAT_VERSION,
+            presets: vec![inverted],
+        })
+        .unwrap();
+        assert!(library.import_pack(&json).is_err());
+        assert!(matches!(
+            library.remove(Uuid::new_v4()),
+            Err(EngineError::PresetNotFound { .. })
+        ));
+    }
+}
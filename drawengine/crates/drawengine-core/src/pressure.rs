@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::EngineError;
+use crate::point::Point;
+
+/// Quantiles sampled when calibrating a device curve.
+const CALIBRATION_STEPS: usize = 8;
+
+/// Fewest non-zero samples `PressureCurve::calibrate` will fit a curve to.
+pub const MIN_CALIBRATION_SAMPLES: usize = 32;
+
+/// Piecewise-linear mapping from input pressure to output pressure, both in `0.0..=1.0`.
+/// Inputs outside the first and last control points are clamped to them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<Point>", into = "Vec<Point>")]
+pub struct PressureCurve {
+    /// Control points with strictly increasing `x`, unless the curve was loaded from
+    /// a corrupt document; see `is_valid`.
+    points: Vec<Point>,
+}
+
+impl PressureCurve {
+    /// The identity curve.
+    pub fn linear() -> Self {
+        Self {
+            points: vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)],
+        }
+    }
+
+    /// Curve through `points`, which need at least two entries, strictly increasing `x`
+    /// and every coordinate in `0.0..=1.0`.
+    pub fn new(points: Vec<Point>) -> Result<Self, EngineError> {
+        check_points(&points)?;
+        Ok(Self { points })
+    }
+
+    /// Curve that spreads the pressures in `samples` (raw device readings from a few
+    /// ordinary strokes) evenly over `0.0..=1.0`, so a light-handed user or a stylus with
+    /// a narrow range still reaches the full width range. Zero readings (hover, lift-off)
+    /// are ignored. `None` when there are too few samples or they are all the same.
+    pub fn calibrate(samples: &[f64]) -> Option<Self> {
+        let mut sorted: Vec<f64> = samples
+            .iter()
+            .copied()
+            .filter(|p| p.is_finite() && *p > 0.0)
+            .map(|p| p.min(1.0))
+            .collect();
+        if sorted.len() < MIN_CALIBRATION_SAMPLES {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+
+        let mut points = vec![Point::new(0.0, 0.0)];
+        for step in 0..=CALIBRATION_STEPS {
+            let q = step as f64 / CALIBRATION_STEPS as f64;
+            let x = sorted[((sorted.len() - 1) as f64 * q).round() as usize];
+            // Repeated readings collapse onto one control point.
+            if x > points.last().map_or(0.0, |p| p.x) {
+                points.push(Point::new(x, q));
+            }
+        }
+        if points.len() < 3 {
+            return None;
+        }
+        // Harder than anything seen during calibration saturates.
+        if points.last().is_some_and(|p| p.x < 1.0) {
+            points.push(Point::new(1.0, 1.0));
+        }
+        Self::new(points).ok()
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// False only for curves deserialized from bad data, which validation reports and
+    /// repair replaces.
+    pub fn is_valid(&self) -> bool {
+        check_points(&self.points).is_ok()
+    }
+
+    pub fn is_linear(&self) -> bool {
+        *self == Self::linear()
+    }
+
+    /// Map `pressure` through the curve. Non-finite input maps to the curve at 0; an
+    /// invalid curve leaves `pressure` unchanged.
+    pub fn apply(&self, pressure: f64) -> f64 {
+        let pressure = if pressure.is_finite() { pressure } else { 0.0 };
+        if !self.is_valid() {
+            return pressure;
+        }
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if pressure <= first.x {
+            return first.y;
+        }
+        if pressure >= last.x {
+            return last.y;
+        }
+        let i = self.points.partition_point(|p| p.x <= pressure);
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        a.y + (b.y - a.y) * (pressure - a.x) / (b.x - a.x)
+    }
+}
+
+fn check_points(points: &[Point]) -> Result<(), EngineError> {
+    let invalid = |message: &str| EngineError::InvalidGeometry {
+        message: format!("pressure curve {message}"),
+    };
+    if points.len() < 2 {
+        return Err(invalid("needs at least two points"));
+    }
+    let in_range = |v: f64| (0.0..=1.0).contains(&v);
+    if !points.iter().all(|p| in_range(p.x) && in_range(p.y)) {
+        return Err(invalid("points must lie within 0..=1"));
+    }
+    if points.windows(2).any(|w| w[1].x <= w[0].x) {
+        return Err(invalid("x values must be strictly increasing"));
+    }
+    Ok(())
+}
+
+impl Default for PressureCurve {
+    fn default() -> Self {
+        Self::linear()
+    }
+}
+
+/// Unchecked, so one bad curve doesn't make a whole document unreadable.
+impl From<Vec<Point>> for PressureCurve {
+    fn from(points: Vec<Point>) -> Self {
+        Self { points }
+    }
+}
+
+impl From<PressureCurve> for Vec<Point> {
+    fn from(curve: PressureCurve) -> Self {
+        curve.points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_interpolates_and_clamps() {
+        let curve = PressureCurve::new(vec![
+            Point::new(0.2, 0.0),
+            Point::new(0.6, 0.8),
+            Point::new(1.0, 1.0),
+        ])
+        .unwrap();
+        assert_eq!(curve.apply(0.1), 0.0);
+        assert!((curve.apply(0.4) - 0.4).abs() < 1e-9);
+        assert!((curve.apply(0.8) - 0.9).abs() < 1e-9);
+        assert_eq!(curve.apply(2.0), 1.0);
+        assert_eq!(curve.apply(f64::NAN), 0.0);
+        assert_eq!(PressureCurve::linear().apply(0.37), 0.37);
+    }
+
+    #[test]
+    fn test_rejects_bad_points() {
+        assert!(PressureCurve::new(vec![Point::new(0.0, 0.0)]).is_err());
+        assert!(PressureCurve::new(vec![Point::new(0.5, 0.0), Point::new(0.5, 1.0)]).is_err());
+        assert!(PressureCurve::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 1.5)]).is_err());
+
+        // Bad curves still load, so the rest of a document can be repaired.
+        let loaded: PressureCurve = serde_json::from_str(r#"[{"x":1.0,"y":0.0}]"#).unwrap();
+        assert!(!loaded.is_valid());
+        assert_eq!(loaded.apply(0.4), 0.4);
+    }
+
+    #[test]
+    fn test_calibrate_spreads_narrow_device_range() {
+        // A stylus that only ever reports 0.1..0.3.
+        let samples: Vec<f64> = (0..100).map(|i| 0.1 + 0.2 * i as f64 / 99.0).collect();
+        let curve = PressureCurve::calibrate(&samples).unwrap();
+        assert!(curve.apply(0.1) < 0.05);
+        assert!((curve.apply(0.2) - 0.5).abs() < 0.05);
+        assert!(curve.apply(0.3) > 0.95);
+
+        let json = serde_json::to_string(&curve).unwrap();
+        let loaded: PressureCurve = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.points().len(), curve.points().len());
+        assert!((loaded.apply(0.22) - curve.apply(0.22)).abs() < 1e-9);
+
+        assert!(PressureCurve::calibrate(&[0.5; 100]).is_none());
+        assert!(PressureCurve::calibrate(&samples[..10]).is_none());
+    }
+}
@@ -0,0 +1,564 @@
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4};
+
+use crate::geometry::BezierSegment;
+use crate::point::{BoundingBox, Point, StrokePoint};
+use crate::shape::{distance_to_segment, rotate, ShapeGeometry};
+use crate::stroke::Stroke;
+
+/// How long the pen must rest at the end of a stroke to straighten it, in seconds.
+pub const HOLD_DURATION: f64 = 0.5;
+
+/// How far the pen may drift while resting, in canvas units.
+pub const HOLD_RADIUS: f64 = 3.0;
+
+/// Smallest bounding-box diagonal, in canvas units, worth recognizing.
+const MIN_SHAPE_SIZE: f64 = 10.0;
+
+/// Largest distance from the chord, as a fraction of its length, for a straight line.
+const LINE_TOLERANCE: f64 = 0.05;
+
+/// Corner detection tolerance as a fraction of the diagonal.
+const CORNER_TOLERANCE: f64 = 0.06;
+
+/// Turns gentler than this (radians) are not corners.
+const MIN_CORNER_ANGLE: f64 = 0.45;
+
+/// Mean distance from a polygon's edges, as a fraction of the diagonal.
+const POLYGON_TOLERANCE: f64 = 0.02;
+
+/// Mean relative radial error of an ellipse fit.
+const ELLIPSE_TOLERANCE: f64 = 0.1;
+
+/// Radii closer than this fraction of the larger one make a circle.
+const CIRCLE_TOLERANCE: f64 = 0.12;
+
+/// Gap between the ends, as a fraction of the path length, that still closes a shape.
+const CLOSED_GAP: f64 = 0.15;
+
+const MAX_POLYGON_SIDES: usize = 8;
+
+/// Angles this close (radians) to a horizontal, vertical or diagonal direction snap to it.
+const SNAP_ANGLE: f64 = 0.12;
+
+/// When the engine replaces a finished stroke with the shape it resembles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ShapeSnapping {
+    #[default]
+    Off,
+    /// Only strokes that end with the pen held still ("hold to straighten").
+    OnHold,
+    /// Every stroke that looks like a shape.
+    Always,
+}
+
+/// Clean geometry recognized from a hand-drawn stroke. Rotations are in radians,
+/// clockwise from the positive x axis.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecognizedShape {
+    Line {
+        start: Point,
+        end: Point,
+    },
+    /// A line with an open two-barbed head at `end`.
+    Arrow {
+        start: Point,
+        end: Point,
+        head_length: f64,
+    },
+    Ellipse {
+        center: Point,
+        radius_x: f64,
+        radius_y: f64,
+        rotation: f64,
+    },
+    Triangle {
+        vertices: [Point; 3],
+    },
+    Rectangle {
+        center: Point,
+        width: f64,
+        height: f64,
+        rotation: f64,
+    },
+    Polygon {
+        vertices: Vec<Point>,
+    },
+}
+
+impl RecognizedShape {
+    /// Outline as one path of constant `width`.
+    pub fn segments(&self, width: f64) -> Vec<BezierSegment> {
+        ShapeGeometry::from(self.clone()).segments(width)
+    }
+}
+
+/// Classify a drawn path, or `None` if it doesn't look like any supported shape.
+pub fn recognize_shape(points: &[Point]) -> Option<RecognizedShape> {
+    let mut path: Vec<Point> = Vec::with_capacity(points.len());
+    for &p in points {
+        if path.last() != Some(&p) {
+            path.push(p);
+        }
+    }
+    if path.len() < 2 {
+        return None;
+    }
+    let bounds = BoundingBox::from_points(&path);
+    let diagonal = bounds.width().hypot(bounds.height());
+    if diagonal.is_nan() || diagonal < MIN_SHAPE_SIZE {
+        return None;
+    }
+    let length: f64 = path.windows(2).map(|w| w[0].distance_to(&w[1])).sum();
+    if path[0].distance_to(&path[path.len() - 1]) <= CLOSED_GAP * length {
+        recognize_closed(&path, diagonal)
+    } else {
+        recognize_open(&path, diagonal)
+    }
+}
+
+/// Watches raw input timestamps for the pen resting at the end of a stroke. Fed
+/// unsmoothed samples, since smoothing keeps creeping towards a resting pen.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HoldDetector {
+    /// Where and when the pen last moved more than `HOLD_RADIUS`.
+    anchor: Option<(Point, f64)>,
+    latest: f64,
+}
+
+impl HoldDetector {
+    pub fn push(&mut self, position: Point, timestamp: f64) {
+        match self.anchor {
+            Some((anchor, _)) if anchor.distance_to(&position) <= HOLD_RADIUS => {}
+            _ => self.anchor = Some((position, timestamp)),
+        }
+        self.latest = timestamp;
+    }
+
+    /// Whether the pen has rested for at least `HOLD_DURATION`.
+    pub fn is_held(&self) -> bool {
+        self.anchor
+            .is_some_and(|(_, since)| self.latest - since >= HOLD_DURATION)
+    }
+}
+
+/// `stroke` redrawn as the shape it resembles, with a new id and a constant width,
+/// or `None` if it doesn't look like one.
+pub fn snap_stroke(stroke: &Stroke) -> Option<Stroke> {
+    let positions: Vec<Point> = stroke.points.iter().map(|p| p.position).collect();
+    let shape = recognize_shape(&positions)?;
+    if stroke.segments.is_empty() {
+        return None;
+    }
+    let width = stroke
+        .segments
+        .iter()
+        .map(|s| (s.start_width + s.end_width) * 0.5)
+        .sum::<f64>()
+        / stroke.segments.len() as f64;
+    let pressure =
+        stroke.points.iter().map(|p| p.pressure).sum::<f64>() / stroke.points.len() as f64;
+    let (first, last) = (stroke.points[0], stroke.points[stroke.points.len() - 1]);
+
+    let segments = shape.segments(width);
+    let n = segments.len();
+    let corners = segments.iter().map(|s| s.p0).chain([segments[n - 1].p3]);
+    let points = corners
+        .enumerate()
+        .map(|(i, position)| StrokePoint {
+            position,
+            pressure,
+            timestamp: first.timestamp + (last.timestamp - first.timestamp) * i as f64 / n as f64,
+            ..first
+        })
+        .collect();
+
+    let mut snapped = Stroke::new(stroke.brush.clone());
+    snapped.color = stroke.color;
+    snapped.points = points;
+    snapped.segments = segments.into_iter().map(Into::into).collect();
+    snapped.recompute_bounding_box();
+    Some(snapped)
+}
+
+fn recognize_open(path: &[Point], diagonal: f64) -> Option<RecognizedShape> {
+    let (start, end) = (path[0], path[path.len() - 1]);
+    let chord = start.distance_to(&end);
+    if path
+        .iter()
+        .all(|&p| distance_to_segment(p, start, end) <= LINE_TOLERANCE * chord)
+    {
+        let end = snap_direction(start, end);
+        return Some(RecognizedShape::Line { start, end });
+    }
+    recognize_arrow(&simplify(path, CORNER_TOLERANCE * diagonal))
+}
+
+/// A straight shaft followed by a head with barbs on both sides, drawn in one go.
+fn recognize_arrow(vertices: &[Point]) -> Option<RecognizedShape> {
+    if vertices.len() < 4 {
+        return None;
+    }
+    let (start, tip) = (vertices[0], vertices[1]);
+    let shaft = start.distance_to(&tip);
+    let head = &vertices[2..];
+    if shaft <= 0.0 || head.iter().any(|p| p.distance_to(&tip) > 0.5 * shaft) {
+        return None;
+    }
+    let back = start - tip;
+    let barb_side = |p: &Point| {
+        let barb = *p - tip;
+        let length = p.distance_to(&tip);
+        let backwards = dot(barb, back) >= length * shaft * FRAC_PI_3.cos();
+        if length >= 0.1 * shaft && backwards {
+            cross(back, barb).signum()
+        } else {
+            0.0
+        }
+    };
+    let sides: Vec<f64> = head.iter().map(barb_side).collect();
+    if !sides.contains(&1.0) || !sides.contains(&-1.0) {
+        return None;
+    }
+    let head_length = head.iter().map(|p| p.distance_to(&tip)).fold(0.0, f64::max);
+    Some(RecognizedShape::Arrow {
+        start,
+        end: snap_direction(start, tip),
+        head_length,
+    })
+}
+
+fn recognize_closed(path: &[Point], diagonal: f64) -> Option<RecognizedShape> {
+    // Start the outline where the pen finished, dropping any overshoot past the start.
+    let end = path[path.len() - 1];
+    let lead_in = (path.len() / 5).max(1);
+    let start = (0..lead_in)
+        .min_by(|&a, &b| path[a].distance_to(&end).total_cmp(&path[b].distance_to(&end)))
+        .unwrap_or(0);
+    let path = &path[start..];
+    let mut ring = path.to_vec();
+    ring.push(path[0]);
+
+    let mut corners = simplify(&ring, CORNER_TOLERANCE * diagonal);
+    corners.pop();
+    let corners = merge_corners(corners);
+    let polygon_error = (3..=MAX_POLYGON_SIDES)
+        .contains(&corners.len())
+        .then(|| polygon_error(path, &corners))
+        .filter(|error| *error <= POLYGON_TOLERANCE * diagonal);
+    // A many-sided polygon can hug a circle too, so keep whichever fits closer.
+    match (polygon_error, fit_ellipse(path)) {
+        (Some(polygon), Some((ellipse, error))) if error < polygon => Some(ellipse),
+        (Some(_), _) => Some(polygon_shape(corners)),
+        (None, ellipse) => ellipse.map(|(shape, _)| shape),
+    }
+}
+
+/// Drop vertices where the outline barely turns, such as where drawing began mid-edge.
+fn merge_corners(mut corners: Vec<Point>) -> Vec<Point> {
+    while corners.len() > 3 {
+        let n = corners.len();
+        let flattest = (0..n)
+            .map(|i| (i, turn(corners[(i + n - 1) % n], corners[i], corners[(i + 1) % n])))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match flattest {
+            Some((i, angle)) if angle < MIN_CORNER_ANGLE => {
+                corners.remove(i);
+            }
+            _ => break,
+        }
+    }
+    corners
+}
+
+fn polygon_shape(corners: Vec<Point>) -> RecognizedShape {
+    let n = corners.len();
+    let right_angled = (0..n).all(|i| {
+        let angle = turn(corners[(i + n - 1) % n], corners[i], corners[(i + 1) % n]);
+        (angle - FRAC_PI_2).abs() < MIN_CORNER_ANGLE
+    });
+    if let [a, b, c] = corners[..] {
+        RecognizedShape::Triangle {
+            vertices: [a, b, c],
+        }
+    } else if n == 4 && right_angled {
+        fit_rectangle(&corners)
+    } else {
+        RecognizedShape::Polygon { vertices: corners }
+    }
+}
+
+fn fit_rectangle(corners: &[Point]) -> RecognizedShape {
+    let center = centroid(corners);
+    let n = corners.len();
+    let (a, b) = (0..n)
+        .map(|i| (corners[i], corners[(i + 1) % n]))
+        .max_by(|(a, b), (c, d)| a.distance_to(b).total_cmp(&c.distance_to(d)))
+        .unwrap_or((corners[0], corners[1]));
+    let (rotation, _) = quarter_turn((b.y - a.y).atan2(b.x - a.x));
+    let rotation = snap_angle(rotation, FRAC_PI_2);
+
+    // Average opposite corners so a sloppy corner only moves its own side half as far.
+    let extent = |axis: Point| {
+        let mut along: Vec<f64> = corners.iter().map(|&p| dot(p - center, axis)).collect();
+        along.sort_by(f64::total_cmp);
+        (along[2] + along[3] - along[0] - along[1]) * 0.5
+    };
+    RecognizedShape::Rectangle {
+        center,
+        width: extent(rotate(Point::new(1.0, 0.0), rotation)),
+        height: extent(rotate(Point::new(0.0, 1.0), rotation)),
+        rotation,
+    }
+}
+
+/// Ellipse from the second moments of the outline, weighted by arc length so slowly
+/// drawn parts don't dominate, and its mean distance from the outline.
+fn fit_ellipse(path: &[Point]) -> Option<(RecognizedShape, f64)> {
+    let n = path.len();
+    let step = |i: usize| path[i].distance_to(&path[(i + 1) % n]);
+    let weights: Vec<f64> = (0..n).map(|i| 0.5 * (step((i + n - 1) % n) + step(i))).collect();
+    let total: f64 = weights.iter().sum();
+    if total.is_nan() || total <= 0.0 {
+        return None;
+    }
+    let center = path
+        .iter()
+        .zip(&weights)
+        .fold(Point::new(0.0, 0.0), |acc, (&p, &w)| acc + p * (w / total));
+    let moment = |f: &dyn Fn(Point) -> f64| {
+        path.iter()
+            .zip(&weights)
+            .map(|(&p, &w)| w * f(p - center))
+            .sum::<f64>()
+            / total
+    };
+    let (sxx, syy, sxy) = (moment(&|d| d.x * d.x), moment(&|d| d.y * d.y), moment(&|d| d.x * d.y));
+    let rotation = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (u, v) = (rotate(Point::new(1.0, 0.0), rotation), rotate(Point::new(0.0, 1.0), rotation));
+    let radius_x = (2.0 * moment(&|d| dot(d, u).powi(2))).sqrt();
+    let radius_y = (2.0 * moment(&|d| dot(d, v).powi(2))).sqrt();
+    if radius_x.is_nan() || radius_y.is_nan() || radius_x.min(radius_y) <= 0.0 {
+        return None;
+    }
+    let error = moment(&|d| ((dot(d, u) / radius_x).hypot(dot(d, v) / radius_y) - 1.0).abs());
+    if error > ELLIPSE_TOLERANCE {
+        return None;
+    }
+    let distance = error * (radius_x + radius_y) * 0.5;
+
+    let larger = radius_x.max(radius_y);
+    if (radius_x - radius_y).abs() <= CIRCLE_TOLERANCE * larger {
+        let radius = (radius_x + radius_y) * 0.5;
+        let circle = RecognizedShape::Ellipse {
+            center,
+            radius_x: radius,
+            radius_y: radius,
+            rotation: 0.0,
+        };
+        return Some((circle, distance));
+    }
+    let (rotation, swapped) = quarter_turn(rotation);
+    let (radius_x, radius_y) = if swapped {
+        (radius_y, radius_x)
+    } else {
+        (radius_x, radius_y)
+    };
+    let ellipse = RecognizedShape::Ellipse {
+        center,
+        radius_x,
+        radius_y,
+        rotation: snap_angle(rotation, FRAC_PI_2),
+    };
+    Some((ellipse, distance))
+}
+
+/// Ramer–Douglas–Peucker simplification of an open polyline.
+pub(crate) fn simplify(points: &[Point], epsilon: f64) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (index, distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (i + 1, distance_to_segment(p, first, last)))
+        .fold((0, 0.0), |best, d| if d.1 > best.1 { d } else { best });
+    if distance <= epsilon {
+        return vec![first, last];
+    }
+    let mut left = simplify(&points[..=index], epsilon);
+    left.pop();
+    left.extend(simplify(&points[index..], epsilon));
+    left
+}
+
+/// Mean distance from each point to the nearest polygon edge.
+fn polygon_error(path: &[Point], corners: &[Point]) -> f64 {
+    let n = corners.len();
+    let nearest = |p: Point| {
+        (0..n)
+            .map(|i| distance_to_segment(p, corners[i], corners[(i + 1) % n]))
+            .fold(f64::MAX, f64::min)
+    };
+    path.iter().map(|&p| nearest(p)).sum::<f64>() / path.len() as f64
+}
+
+/// `end` moved onto the nearest horizontal, vertical or diagonal through `start` when
+/// the line is nearly aligned with it.
+fn snap_direction(start: Point, end: Point) -> Point {
+    let angle = (end.y - start.y).atan2(end.x - start.x);
+    let snapped = snap_angle(angle, FRAC_PI_4);
+    start + Point::new(snapped.cos(), snapped.sin()) * start.distance_to(&end)
+}
+
+fn snap_angle(angle: f64, step: f64) -> f64 {
+    let nearest = (angle / step).round() * step;
+    if (angle - nearest).abs() < SNAP_ANGLE {
+        nearest
+    } else {
+        angle
+    }
+}
+
+/// `angle` reduced to within a quarter turn of 0, and whether an odd number of
+/// quarter turns was removed (so x and y extents swap).
+fn quarter_turn(angle: f64) -> (f64, bool) {
+    let turns = (angle / FRAC_PI_2).round();
+    (angle - turns * FRAC_PI_2, turns.rem_euclid(2.0) == 1.0)
+}
+
+/// How sharply the path turns at `b`, from 0 (straight on) to π (straight back).
+fn turn(a: Point, b: Point, c: Point) -> f64 {
+    let (ab, bc) = (b - a, c - b);
+    cross(ab, bc).abs().atan2(dot(ab, bc))
+}
+
+fn centroid(points: &[Point]) -> Point {
+    let sum = points.iter().fold(Point::new(0.0, 0.0), |acc, &p| acc + p);
+    sum * (1.0 / points.len() as f64)
+}
+
+fn dot(a: Point, b: Point) -> f64 {
+    a.x * b.x + a.y * b.y
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// Points along `corners` (closing back to the first when `closed`), with a small
+    /// deterministic wobble.
+    fn trace(corners: &[Point], closed: bool) -> Vec<Point> {
+        let mut stops = corners.to_vec();
+        if closed {
+            stops.push(corners[0]);
+        }
+        let mut points = Vec::new();
+        for w in stops.windows(2) {
+            for i in 0..20 {
+                let p = w[0].lerp(&w[1], i as f64 / 20.0);
+                let wobble = ((points.len() as f64) * 1.7).sin() * 0.4;
+                points.push(p + Point::new(wobble, -wobble));
+            }
+        }
+        points.push(*stops.last().unwrap());
+        points
+    }
+
+    #[test]
+    fn test_recognizes_lines_and_snaps_them() {
+        let points = trace(&[Point::new(0.0, 0.0), Point::new(100.0, 4.0)], false);
+        match recognize_shape(&points) {
+            Some(RecognizedShape::Line { start, end }) => {
+                assert!((end.y - start.y).abs() < 1e-9);
+                assert!((end.x - 100.0).abs() < 1.0);
+            }
+            other => panic!("Expected line, got {other:?}"),
+        }
+        assert!(recognize_shape(&points[..2]).is_none());
+    }
+
+    #[test]
+    fn test_recognizes_closed_shapes() {
+        let rect = [
+            Point::new(0.0, 0.0),
+            Point::new(120.0, 3.0),
+            Point::new(118.0, 80.0),
+            Point::new(-2.0, 78.0),
+        ];
+        match recognize_shape(&trace(&rect, true)) {
+            Some(RecognizedShape::Rectangle { width, height, rotation, .. }) => {
+                assert_eq!(rotation, 0.0);
+                assert!((width - 120.0).abs() < 5.0 && (height - 78.0).abs() < 5.0);
+            }
+            other => panic!("Expected rectangle, got {other:?}"),
+        }
+
+        let triangle = [Point::new(0.0, 0.0), Point::new(100.0, 0.0), Point::new(50.0, 80.0)];
+        assert!(matches!(
+            recognize_shape(&trace(&triangle, true)),
+            Some(RecognizedShape::Triangle { .. })
+        ));
+
+        let circle: Vec<Point> = (0..=90)
+            .map(|i| {
+                let a = i as f64 / 90.0 * 2.0 * PI;
+                Point::new(50.0 + 40.0 * a.cos(), 50.0 + 41.0 * a.sin())
+            })
+            .collect();
+        match recognize_shape(&circle) {
+            Some(RecognizedShape::Ellipse { radius_x, radius_y, .. }) => {
+                assert_eq!(radius_x, radius_y);
+                assert!((radius_x - 40.5).abs() < 1.0);
+            }
+            other => panic!("Expected circle, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_recognizes_arrow() {
+        let tip = Point::new(100.0, 0.0);
+        let path = [
+            Point::new(0.0, 0.0),
+            tip,
+            Point::new(85.0, -12.0),
+            tip,
+            Point::new(85.0, 12.0),
+        ];
+        match recognize_shape(&trace(&path, false)) {
+            Some(RecognizedShape::Arrow { end, head_length, .. }) => {
+                assert!(end.distance_to(&tip) < 2.0);
+                assert!(head_length > 15.0 && head_length < 25.0);
+            }
+            other => panic!("Expected arrow, got {other:?}"),
+        }
+        // A scribble is nothing.
+        let zigzag = [
+            Point::new(0.0, 0.0),
+            Point::new(40.0, 60.0),
+            Point::new(80.0, 0.0),
+            Point::new(120.0, 60.0),
+        ];
+        assert!(recognize_shape(&trace(&zigzag, false)).is_none());
+    }
+
+    #[test]
+    fn test_hold_detection_uses_timestamps() {
+        let mut hold = HoldDetector::default();
+        assert!(!hold.is_held());
+        for i in 0..10 {
+            hold.push(Point::new(i as f64 * 10.0, 0.0), i as f64 * 0.016);
+        }
+        assert!(!hold.is_held());
+        hold.push(Point::new(91.0, 1.0), 0.4);
+        hold.push(Point::new(90.5, 0.5), 0.8);
+        assert!(hold.is_held());
+        hold.push(Point::new(120.0, 0.0), 0.82);
+        assert!(!hold.is_held());
+    }
+}
@@ -1,6 +1,16 @@
+use uuid::Uuid;
+
+use crate::dash::{LineCap, LineJoin};
 use crate::geometry::BezierSegment;
+use crate::image::{AffineTransform, CropRect, Image};
+use crate::infinite::Chunk;
+use crate::layer::LayerManager;
+use crate::page::Page;
 use crate::point::{Color, Point};
+use crate::shape::Shape;
+use crate::stamp::{Stamp, StampTip};
 use crate::stroke::Stroke;
+use crate::text::{TextBox, TextMeasure, TextRun};
 
 /// Commands consumed by native renderers (Android Canvas / iOS CoreGraphics).
 #[derive(Debug, Clone)]
@@ -19,6 +29,80 @@ pub enum RenderCommand {
         segments: Vec<PathSegment>,
         color: Color,
         is_eraser: bool,
+        cap: LineCap,
+        join: LineJoin,
+    },
+    /// A dashed stroke: each inner list is one dash, drawn as its own open path with
+    /// `cap` at both ends. Zero-length dashes are dots drawn by the cap alone.
+    DrawDashedPath {
+        dashes: Vec<Vec<PathSegment>>,
+        color: Color,
+        cap: LineCap,
+        join: LineJoin,
+    },
+    /// Temporary extrapolated tail of the active stroke. Replaces any previously drawn
+    /// prediction; the next incremental update or full render discards it.
+    DrawPrediction {
+        segments: Vec<PathSegment>,
+        color: Color,
+    },
+    /// Brush tips from a stamp brush, drawn in order with source-over blending.
+    DrawStamps {
+        stamps: Vec<Stamp>,
+        tip: StampTip,
+    },
+    /// Fill every polygon with `color` as one path using the nonzero winding rule.
+    /// Used for calligraphy strokes, whose outline isn't a symmetric width profile, and
+    /// for filled regions.
+    FillPolygons {
+        polygons: Vec<Vec<Point>>,
+        color: Color,
+    },
+    /// A shape element: fill the path (if `fill` is set and the path is closed), then
+    /// stroke it with uniform `stroke_width` (if `stroke` is set). Ends are round.
+    DrawShape {
+        segments: Vec<PathSegment>,
+        closed: bool,
+        fill: Option<Color>,
+        stroke: Option<Color>,
+        stroke_width: f64,
+        join: LineJoin,
+    },
+    /// A text box laid out into runs; draw each run's text with its baseline starting
+    /// at the run's origin. Wrapping is already done.
+    DrawText {
+        runs: Vec<TextRun>,
+        font_family: String,
+        font_size: f64,
+        color: Color,
+    },
+    /// The `crop` rectangle of an imported image (see `DrawEngine::image_data`), drawn
+    /// with `transform` mapping its pixels, offset from the crop's top-left corner, into
+    /// the current coordinate space.
+    DrawImage {
+        image_id: Uuid,
+        crop: CropRect,
+        transform: AffineTransform,
+    },
+    /// Straight hairlines of uniform width, used for page templates.
+    DrawLines {
+        segments: Vec<(Point, Point)>,
+        color: Color,
+        width: f64,
+    },
+    /// Filled circles of uniform radius, used for dot-grid templates.
+    DrawDots {
+        centers: Vec<Point>,
+        radius: f64,
+        color: Color,
+    },
+    /// Fill an axis-aligned rectangle in canvas coordinates (e.g. a page background).
+    FillRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        color: Color,
     },
 }
 
@@ -31,6 +115,9 @@ pub struct PathSegment {
     pub p3: Point,
     pub start_width: f64,
     pub end_width: f64,
+    /// When set, overrides the command color with a gradient from the first color
+    /// to the second along the segment.
+    pub colors: Option<(Color, Color)>,
 }
 
 impl From<BezierSegment> for PathSegment {
@@ -42,6 +129,7 @@ impl From<BezierSegment> for PathSegment {
             p3: b.p3,
             start_width: b.start_width,
             end_width: b.end_width,
+            colors: b.colors,
         }
     }
 }
@@ -64,10 +152,197 @@ pub fn generate_full_render_commands(
         translate_y,
     });
 
+    push_stroke_commands(&mut commands, strokes.iter());
+
+    commands.push(RenderCommand::RestoreState);
+    commands
+}
+
+/// Generate render commands for a single page: its background color, template, then strokes.
+/// Text is wrapped with `text_measure`.
+pub fn generate_page_render_commands(
+    page: &Page,
+    scale: f64,
+    translate_x: f64,
+    translate_y: f64,
+    text_measure: &dyn TextMeasure,
+) -> Vec<RenderCommand> {
+    let mut commands = vec![
+        RenderCommand::Clear {
+            color: page.background_color,
+        },
+        RenderCommand::SaveState,
+        RenderCommand::SetTransform {
+            scale,
+            translate_x,
+            translate_y,
+        },
+    ];
+    commands.extend(page.template.render_commands(page.width, page.height));
+    push_layer_commands(&mut commands, &page.layer_manager, text_measure);
+    commands.push(RenderCommand::RestoreState);
+    commands
+}
+
+/// Generate render commands for several pages placed at canvas-space origins.
+/// Each page is drawn under its own transform so stroke coordinates stay page-local.
+pub fn generate_multi_page_render_commands(
+    pages: &[(&Page, Point)],
+    bg_color: Color,
+    scale: f64,
+    translate_x: f64,
+    translate_y: f64,
+    text_measure: &dyn TextMeasure,
+) -> Vec<RenderCommand> {
+    let mut commands = vec![RenderCommand::Clear { color: bg_color }];
+
+    for (page, origin) in pages {
+        commands.push(RenderCommand::SaveState);
+        commands.push(RenderCommand::SetTransform {
+            scale,
+            translate_x: translate_x + origin.x * scale,
+            translate_y: translate_y + origin.y * scale,
+        });
+        commands.push(RenderCommand::FillRect {
+            x: 0.0,
+            y: 0.0,
+            width: page.width,
+            height: page.height,
+            color: page.background_color,
+        });
+        commands.extend(page.template.render_commands(page.width, page.height));
+        push_layer_commands(&mut commands, &page.layer_manager, text_measure);
+        commands.push(RenderCommand::RestoreState);
+    }
+    commands
+}
+
+/// Generate render commands for infinite-canvas chunks placed at view-relative origins.
+pub fn generate_chunk_render_commands(
+    chunks: &[(&Chunk, Point)],
+    bg_color: Color,
+    scale: f64,
+    translate_x: f64,
+    translate_y: f64,
+) -> Vec<RenderCommand> {
+    let mut commands = vec![RenderCommand::Clear { color: bg_color }];
+
+    for (chunk, origin) in chunks {
+        commands.push(RenderCommand::SaveState);
+        commands.push(RenderCommand::SetTransform {
+            scale,
+            translate_x: translate_x + origin.x * scale,
+            translate_y: translate_y + origin.y * scale,
+        });
+        push_stroke_commands(&mut commands, chunk.strokes.iter());
+        commands.push(RenderCommand::RestoreState);
+    }
+    commands
+}
+
+/// Each visible layer's images, then its fills, shapes, strokes and text.
+fn push_layer_commands(
+    commands: &mut Vec<RenderCommand>,
+    layers: &LayerManager,
+    text_measure: &dyn TextMeasure,
+) {
+    for layer in layers.layers.iter().filter(|l| l.visible) {
+        push_image_commands(commands, &layer.images);
+        for fill in &layer.fills {
+            commands.push(RenderCommand::FillPolygons {
+                polygons: fill.polygons.clone(),
+                color: fill.color,
+            });
+        }
+        push_shape_commands(commands, &layer.shapes);
+        push_stroke_commands(commands, layer.strokes.iter());
+        push_text_commands(commands, &layer.texts, text_measure);
+    }
+}
+
+fn push_image_commands(commands: &mut Vec<RenderCommand>, images: &[Image]) {
+    for image in images {
+        commands.push(RenderCommand::DrawImage {
+            image_id: image.image_id,
+            crop: image.crop,
+            transform: image.transform(),
+        });
+    }
+}
+
+fn push_text_commands(
+    commands: &mut Vec<RenderCommand>,
+    texts: &[TextBox],
+    text_measure: &dyn TextMeasure,
+) {
+    for text in texts {
+        let runs = text.layout(text_measure);
+        if runs.is_empty() {
+            continue;
+        }
+        commands.push(RenderCommand::DrawText {
+            runs,
+            font_family: text.font_family.clone(),
+            font_size: text.font_size,
+            color: text.color,
+        });
+    }
+}
+
+fn push_shape_commands(commands: &mut Vec<RenderCommand>, shapes: &[Shape]) {
+    for shape in shapes {
+        let fill = shape.fill();
+        if fill.is_none() && shape.stroke_color.is_none() {
+            continue;
+        }
+        commands.push(RenderCommand::DrawShape {
+            segments: shape
+                .geometry
+                .segments(shape.stroke_width)
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            closed: shape.geometry.is_closed(),
+            fill,
+            stroke: shape.stroke_color,
+            stroke_width: shape.stroke_width,
+            join: shape.line_join(),
+        });
+    }
+}
+
+fn push_stroke_commands<'a>(
+    commands: &mut Vec<RenderCommand>,
+    strokes: impl Iterator<Item = &'a Stroke>,
+) {
     for stroke in strokes {
         if stroke.segments.is_empty() {
             continue;
         }
+        if let Some(settings) = stroke.brush.stamp_settings() {
+            commands.push(RenderCommand::DrawStamps {
+                stamps: stroke.stamps(),
+                tip: settings.tip,
+            });
+            continue;
+        }
+        if stroke.brush.nib_settings().is_some() {
+            commands.push(RenderCommand::FillPolygons {
+                polygons: stroke.outline(),
+                color: stroke.color,
+            });
+            continue;
+        }
+        let (cap, join) = (stroke.brush.line_cap, stroke.brush.line_join);
+        if stroke.brush.dash_pattern().is_some() {
+            commands.push(RenderCommand::DrawDashedPath {
+                dashes: path_dashes(stroke.dashes()),
+                color: stroke.color,
+                cap,
+                join,
+            });
+            continue;
+        }
         let segments: Vec<PathSegment> = stroke
             .segments
             .iter()
@@ -77,11 +352,10 @@ pub fn generate_full_render_commands(
             segments,
             color: stroke.color,
             is_eraser: stroke.is_eraser,
+            cap,
+            join,
         });
     }
-
-    commands.push(RenderCommand::RestoreState);
-    commands
 }
 
 /// Generate incremental render commands for newly added segments during drawing.
@@ -89,6 +363,8 @@ pub fn generate_incremental_commands(
     new_segments: &[BezierSegment],
     color: Color,
     is_eraser: bool,
+    cap: LineCap,
+    join: LineJoin,
 ) -> Vec<RenderCommand> {
     if new_segments.is_empty() {
         return vec![];
@@ -98,15 +374,66 @@ pub fn generate_incremental_commands(
         segments,
         color,
         is_eraser,
+        cap,
+        join,
+    }]
+}
+
+/// Generate incremental render commands for dashes laid out during drawing.
+pub fn generate_incremental_dash_commands(
+    dashes: Vec<Vec<BezierSegment>>,
+    color: Color,
+    cap: LineCap,
+    join: LineJoin,
+) -> Vec<RenderCommand> {
+    if dashes.is_empty() {
+        return vec![];
+    }
+    vec![RenderCommand::DrawDashedPath {
+        dashes: path_dashes(dashes),
+        color,
+        cap,
+        join,
     }]
 }
 
+fn path_dashes(dashes: Vec<Vec<BezierSegment>>) -> Vec<Vec<PathSegment>> {
+    dashes
+        .into_iter()
+        .map(|dash| dash.into_iter().map(Into::into).collect())
+        .collect()
+}
+
+/// Generate incremental render commands for stamps placed during drawing.
+pub fn generate_incremental_stamp_commands(
+    stamps: Vec<Stamp>,
+    tip: StampTip,
+) -> Vec<RenderCommand> {
+    if stamps.is_empty() {
+        return vec![];
+    }
+    vec![RenderCommand::DrawStamps { stamps, tip }]
+}
+
+/// Generate incremental render commands for a calligraphy outline drawn during drawing.
+pub fn generate_incremental_outline_commands(
+    polygons: Vec<Vec<Point>>,
+    color: Color,
+) -> Vec<RenderCommand> {
+    if polygons.is_empty() {
+        return vec![];
+    }
+    vec![RenderCommand::FillPolygons { polygons, color }]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::brush::BrushConfig;
     use crate::point::{Color, StrokePoint};
     use crate::stroke::StrokeBuilder;
+    use crate::template::PageTemplate;
+    use crate::text::EstimatedTextMeasure;
 
     #[test]
     fn test_full_render_commands_empty() {
@@ -128,6 +455,49 @@ mod tests {
         assert!(cmds.len() >= 4);
     }
 
+    #[test]
+    fn test_multi_page_commands() {
+        let a = Page::new(100.0, 100.0);
+        let b = Page::new(100.0, 50.0);
+        let placed = [(&a, Point::new(0.0, 0.0)), (&b, Point::new(0.0, 110.0))];
+        let cmds = generate_multi_page_render_commands(
+            &placed,
+            Color::black(),
+            2.0,
+            5.0,
+            0.0,
+            &EstimatedTextMeasure,
+        );
+        // Clear + 2 × (SaveState, SetTransform, FillRect, RestoreState)
+        assert_eq!(cmds.len(), 9);
+        match cmds[6] {
+            RenderCommand::SetTransform {
+                translate_x,
+                translate_y,
+                ..
+            } => {
+                assert!((translate_x - 5.0).abs() < 1e-9);
+                assert!((translate_y - 220.0).abs() < 1e-9);
+            }
+            ref other => panic!("Expected SetTransform, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_page_commands_draw_template_beneath_strokes() {
+        let mut page = Page::new(100.0, 100.0);
+        page.template = PageTemplate::grid(10.0);
+        let brush = BrushConfig::pen(Color::black(), 2.0);
+        let mut builder = StrokeBuilder::new(brush);
+        builder.add_point(StrokePoint::new(0.0, 0.0, 0.5, 0.0));
+        builder.add_point(StrokePoint::new(10.0, 10.0, 0.5, 0.016));
+        page.layer_manager.active_layer_mut().add_stroke(builder.finish());
+
+        let cmds = generate_page_render_commands(&page, 1.0, 0.0, 0.0, &EstimatedTextMeasure);
+        assert!(matches!(cmds[3], RenderCommand::DrawLines { .. }));
+        assert!(matches!(cmds[4], RenderCommand::DrawVariableWidthPath { .. }));
+    }
+
     #[test]
     fn test_incremental_commands() {
         let seg = BezierSegment {
@@ -137,8 +507,88 @@ mod tests {
             p3: Point::new(3.0, 0.0),
             start_width: 2.0,
             end_width: 3.0,
+            colors: None,
         };
-        let cmds = generate_incremental_commands(&[seg], Color::black(), false);
+        let cmds = generate_incremental_commands(
+            &[seg],
+            Color::black(),
+            false,
+            LineCap::Round,
+            LineJoin::Round,
+        );
         assert_eq!(cmds.len(), 1);
     }
+
+    #[test]
+    fn test_layer_draw_order() {
+        use crate::fill::FillRegion;
+        use crate::shape::{Shape, ShapeGeometry};
+
+        let mut page = Page::new(100.0, 100.0);
+        let mut builder = StrokeBuilder::new(BrushConfig::pen(Color::black(), 2.0));
+        builder.add_point(StrokePoint::new(0.0, 0.0, 0.5, 0.0));
+        builder.add_point(StrokePoint::new(10.0, 10.0, 0.5, 0.016));
+        let layer = page.layer_manager.active_layer_mut();
+        layer.add_stroke(builder.finish());
+        let ellipse = ShapeGeometry::Ellipse {
+            center: Point::new(50.0, 50.0),
+            radius_x: 20.0,
+            radius_y: 10.0,
+            rotation: 0.0,
+        };
+        layer.add_shape(Shape::new(ellipse, Color::black(), 1.0).with_fill(Color::white()));
+        layer.add_text(TextBox::new(Point::new(5.0, 5.0), Some(100.0), "a label"));
+        let photo = Image {
+            id: Uuid::new_v4(),
+            image_id: Uuid::new_v4(),
+            center: Point::new(50.0, 50.0),
+            scale: 0.5,
+            rotation: 0.0,
+            crop: CropRect {
+                x: 0.0,
+                y: 0.0,
+                width: 200.0,
+                height: 100.0,
+            },
+        };
+        layer.add_image(photo.clone());
+        let triangle = vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(0.0, 10.0),
+        ];
+        layer.add_fill(FillRegion::new(vec![triangle], Color::white()));
+
+        let cmds = generate_page_render_commands(&page, 1.0, 0.0, 0.0, &EstimatedTextMeasure);
+        match &cmds[3] {
+            RenderCommand::DrawImage {
+                image_id,
+                transform,
+                ..
+            } => {
+                assert_eq!(*image_id, photo.image_id);
+                assert_eq!((transform.a, transform.tx, transform.ty), (0.5, 0.0, 25.0));
+            }
+            other => panic!("Expected DrawImage, got {other:?}"),
+        }
+        assert!(matches!(cmds[4], RenderCommand::FillPolygons { .. }));
+        match &cmds[5] {
+            RenderCommand::DrawShape {
+                segments,
+                closed,
+                fill,
+                ..
+            } => {
+                assert_eq!(segments.len(), 4);
+                assert!(*closed);
+                assert_eq!(*fill, Some(Color::white()));
+            }
+            other => panic!("Expected DrawShape, got {other:?}"),
+        }
+        assert!(matches!(cmds[6], RenderCommand::DrawVariableWidthPath { .. }));
+        match &cmds[7] {
+            RenderCommand::DrawText { runs, .. } => assert_eq!(runs[0].text, "a label"),
+            other => panic!("Expected DrawText, got {other:?}"),
+        }
+    }
 }
@@ -1,24 +1,94 @@
 use serde::{Deserialize, Serialize};
 
-use crate::layer::Layer;
+use crate::error::EngineError;
+use crate::infinite::InfiniteCanvas;
+use crate::layer::{Layer, LayerManager};
+use crate::page::Page;
 use crate::point::Color;
 
+/// Latest document format version written by `save_to_json`.
+/// Version 1 documents (a single canvas) are still accepted and load as one page.
+/// Version 3 added the optional infinite canvas; older readers must not drop it silently.
+/// Version 4 added shapes to layers, version 5 text boxes, version 6 images and version 7
+/// filled regions, for the same reason.
+pub const CURRENT_VERSION: u32 = 7;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DocumentData {
     pub version: u32,
-    pub width: f64,
-    pub height: f64,
-    pub background_color: Color,
-    pub layers: Vec<Layer>,
+    pub pages: Vec<Page>,
+    #[serde(default)]
+    pub current_page_index: usize,
+    /// Chunked content for infinite-canvas documents. Pages are kept but unused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub infinite: Option<InfiniteCanvas>,
+}
+
+/// Version 1 layout: a single canvas with its layers at the top level.
+#[derive(Deserialize)]
+struct DocumentDataV1 {
+    width: f64,
+    height: f64,
+    background_color: Color,
+    layers: Vec<Layer>,
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u32,
 }
 
 impl DocumentData {
-    pub fn save_to_json(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string(self)
+    pub fn save_to_json(&self) -> Result<String, EngineError> {
+        serde_json::to_string(self).map_err(|e| EngineError::Serialization {
+            message: e.to_string(),
+        })
     }
 
-    pub fn load_from_json(json: &str) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(json)
+    pub fn load_from_json(json: &str) -> Result<Self, EngineError> {
+        let VersionProbe { version } = serde_json::from_str(json)?;
+        if version > CURRENT_VERSION {
+            return Err(EngineError::UnsupportedVersion {
+                found: version,
+                supported: CURRENT_VERSION,
+            });
+        }
+        let data = if version <= 1 {
+            let v1: DocumentDataV1 = serde_json::from_str(json)?;
+            let mut page = Page::new(v1.width, v1.height);
+            page.background_color = v1.background_color;
+            if !v1.layers.is_empty() {
+                page.layer_manager = LayerManager {
+                    layers: v1.layers,
+                    active_layer_index: 0,
+                };
+            }
+            DocumentData {
+                version: CURRENT_VERSION,
+                pages: vec![page],
+                current_page_index: 0,
+                infinite: None,
+            }
+        } else {
+            serde_json::from_str(json)?
+        };
+
+        if data.pages.is_empty() {
+            return Err(EngineError::InvalidGeometry {
+                message: "document has no pages".to_string(),
+            });
+        }
+        for page in &data.pages {
+            if !(page.width.is_finite() && page.height.is_finite())
+                || page.width <= 0.0
+                || page.height <= 0.0
+            {
+                return Err(EngineError::InvalidGeometry {
+                    message: format!("page size {}x{}", page.width, page.height),
+                });
+            }
+        }
+        Ok(data)
     }
 }
 
@@ -26,8 +96,7 @@ impl DocumentData {
 mod tests {
     use super::*;
     use crate::brush::BrushConfig;
-    use crate::layer::Layer;
-    use crate::point::{Color, StrokePoint};
+    use crate::point::StrokePoint;
     use crate::stroke::StrokeBuilder;
 
     #[test]
@@ -41,18 +110,63 @@ mod tests {
         }
         layer.add_stroke(builder.finish());
 
+        let mut page = Page::new(1920.0, 1080.0);
+        page.layer_manager.layers = vec![layer];
         let doc = DocumentData {
-            version: 1,
-            width: 1920.0,
-            height: 1080.0,
-            background_color: Color::white(),
-            layers: vec![layer],
+            version: CURRENT_VERSION,
+            pages: vec![page, Page::new(800.0, 600.0)],
+            current_page_index: 1,
+            infinite: None,
         };
 
         let json = doc.save_to_json().unwrap();
         let loaded = DocumentData::load_from_json(&json).unwrap();
-        assert_eq!(loaded.version, 1);
-        assert_eq!(loaded.layers.len(), 1);
-        assert_eq!(loaded.layers[0].strokes.len(), 1);
+        assert_eq!(loaded.version, CURRENT_VERSION);
+        assert_eq!(loaded.pages.len(), 2);
+        assert_eq!(loaded.current_page_index, 1);
+        assert_eq!(loaded.pages[0].layer_manager.layers[0].strokes.len(), 1);
+    }
+
+    #[test]
+    fn test_load_rejects_newer_version() {
+        let doc = DocumentData {
+            version: CURRENT_VERSION + 1,
+            pages: vec![Page::new(100.0, 100.0)],
+            current_page_index: 0,
+            infinite: None,
+        };
+        let json = doc.save_to_json().unwrap();
+        match DocumentData::load_from_json(&json) {
+            Err(EngineError::UnsupportedVersion { found, supported }) => {
+                assert_eq!(found, CURRENT_VERSION + 1);
+                assert_eq!(supported, CURRENT_VERSION);
+            }
+            other => panic!("Expected UnsupportedVersion, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_canvas_size() {
+        let json = r#"{"version":1,"width":-5.0,"height":100.0,
+            "background_color":{"r":1.0,"g":1.0,"b":1.0,"a":1.0},"layers":[]}"#;
+        assert!(matches!(
+            DocumentData::load_from_json(json),
+            Err(EngineError::InvalidGeometry { .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_version_1_as_single_page() {
+        let json = r#"{"version":1,"width":640.0,"height":480.0,
+            "background_color":{"r":0.0,"g":0.0,"b":0.0,"a":1.0},
+            "layers":[{"id":"7d444840-9dc0-11d1-b245-5ffdce74fad2","name":"Ink",
+                "visible":true,"opacity":1.0,"strokes":[]}]}"#;
+        let doc = DocumentData::load_from_json(json).unwrap();
+        assert_eq!(doc.version, CURRENT_VERSION);
+        assert_eq!(doc.pages.len(), 1);
+        let page = &doc.pages[0];
+        assert!((page.width - 640.0).abs() < 1e-9);
+        assert_eq!(page.background_color, Color::black());
+        assert_eq!(page.layer_manager.layers[0].name, "Ink");
     }
 }
@@ -0,0 +1,504 @@
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_6};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::dash::LineJoin;
+use crate::geometry::BezierSegment;
+use crate::point::{BoundingBox, Color, Point};
+use crate::recognition::RecognizedShape;
+
+/// Control point distance for a quarter-circle cubic.
+const KAPPA: f64 = 0.552_284_75;
+
+/// Straight pieces each outline segment is split into for hit-testing.
+const FLATTEN_STEPS: usize = 8;
+
+/// Parametric geometry of a shape, in page-local coordinates. Rotations are in radians,
+/// clockwise from the positive x axis.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShapeGeometry {
+    Line {
+        start: Point,
+        end: Point,
+    },
+    /// A line with an open two-barbed head at `end`.
+    Arrow {
+        start: Point,
+        end: Point,
+        head_length: f64,
+    },
+    Ellipse {
+        center: Point,
+        radius_x: f64,
+        radius_y: f64,
+        rotation: f64,
+    },
+    Rectangle {
+        center: Point,
+        width: f64,
+        height: f64,
+        rotation: f64,
+    },
+    Polygon {
+        vertices: Vec<Point>,
+    },
+}
+
+impl ShapeGeometry {
+    /// Whether the outline encloses an area that can be filled.
+    pub fn is_closed(&self) -> bool {
+        !matches!(self, ShapeGeometry::Line { .. } | ShapeGeometry::Arrow { .. })
+    }
+
+    /// Finite coordinates, non-negative sizes and at least three polygon vertices.
+    pub fn is_valid(&self) -> bool {
+        let finite = |p: &Point| p.x.is_finite() && p.y.is_finite();
+        let size = |v: f64| v.is_finite() && v >= 0.0;
+        match self {
+            ShapeGeometry::Line { start, end } => finite(start) && finite(end),
+            ShapeGeometry::Arrow {
+                start,
+                end,
+                head_length,
+            } => finite(start) && finite(end) && size(*head_length),
+            ShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => finite(center) && size(*radius_x) && size(*radius_y) && rotation.is_finite(),
+            ShapeGeometry::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            } => finite(center) && size(*width) && size(*height) && rotation.is_finite(),
+            ShapeGeometry::Polygon { vertices } => {
+                vertices.len() >= 3 && vertices.iter().all(finite)
+            }
+        }
+    }
+
+    /// Outline as one path of constant `width`.
+    pub fn segments(&self, width: f64) -> Vec<BezierSegment> {
+        let line = |a: Point, b: Point| BezierSegment {
+            p0: a,
+            p1: a.lerp(&b, 1.0 / 3.0),
+            p2: a.lerp(&b, 2.0 / 3.0),
+            p3: b,
+            start_width: width,
+            end_width: width,
+            colors: None,
+        };
+        let closed = |vertices: &[Point]| {
+            let n = vertices.len();
+            (0..n)
+                .map(|i| line(vertices[i], vertices[(i + 1) % n]))
+                .collect()
+        };
+        match self {
+            ShapeGeometry::Line { start, end } => vec![line(*start, *end)],
+            ShapeGeometry::Arrow {
+                start,
+                end,
+                head_length,
+            } => {
+                let back = (start.y - end.y).atan2(start.x - end.x);
+                let barb = |side: f64| {
+                    let angle = back + side * FRAC_PI_6;
+                    *end + Point::new(angle.cos(), angle.sin()) * *head_length
+                };
+                let (left, right) = (barb(-1.0), barb(1.0));
+                // One continuous path, retracing the first barb to reach the second.
+                vec![
+                    line(*start, *end),
+                    line(*end, left),
+                    line(left, *end),
+                    line(*end, right),
+                ]
+            }
+            ShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => {
+                let to_canvas = |p: Point| {
+                    *center + rotate(Point::new(p.x * radius_x, p.y * radius_y), *rotation)
+                };
+                (0..4)
+                    .map(|quarter| {
+                        let q = |x: f64, y: f64| {
+                            to_canvas(rotate(Point::new(x, y), quarter as f64 * FRAC_PI_2))
+                        };
+                        BezierSegment {
+                            p0: q(1.0, 0.0),
+                            p1: q(1.0, KAPPA),
+                            p2: q(KAPPA, 1.0),
+                            p3: q(0.0, 1.0),
+                            start_width: width,
+                            end_width: width,
+                            colors: None,
+                        }
+                    })
+                    .collect()
+            }
+            ShapeGeometry::Rectangle { .. } => closed(&self.handles()),
+            ShapeGeometry::Polygon { vertices } => closed(vertices),
+        }
+    }
+
+    /// Points the user drags to edit the shape: the two ends of a line or arrow, the
+    /// corners of a rectangle or of an ellipse's bounding box (clockwise from the
+    /// top-left before rotation), or a polygon's vertices.
+    pub fn handles(&self) -> Vec<Point> {
+        let corners = |center: Point, half_width: f64, half_height: f64, rotation: f64| {
+            let u = rotate(Point::new(half_width, 0.0), rotation);
+            let v = rotate(Point::new(0.0, half_height), rotation);
+            vec![
+                center - u - v,
+                center + u - v,
+                center + u + v,
+                center - u + v,
+            ]
+        };
+        match self {
+            ShapeGeometry::Line { start, end } | ShapeGeometry::Arrow { start, end, .. } => {
+                vec![*start, *end]
+            }
+            ShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => corners(*center, *radius_x, *radius_y, *rotation),
+            ShapeGeometry::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            } => corners(*center, width * 0.5, height * 0.5, *rotation),
+            ShapeGeometry::Polygon { vertices } => vertices.clone(),
+        }
+    }
+
+    /// Move handle `index` to `to`. Box corners resize around the opposite corner and
+    /// keep the rotation, so the shape never shears. Returns `false` for a bad index.
+    pub fn move_handle(&mut self, index: usize, to: Point) -> bool {
+        let handles = self.handles();
+        if index >= handles.len() {
+            return false;
+        }
+        match self {
+            ShapeGeometry::Line { start, end } | ShapeGeometry::Arrow { start, end, .. } => {
+                *if index == 0 { start } else { end } = to;
+            }
+            ShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => {
+                let opposite = handles[(index + 2) % 4];
+                let size = rotate(to - opposite, -*rotation);
+                *center = opposite.midpoint(&to);
+                *radius_x = size.x.abs() * 0.5;
+                *radius_y = size.y.abs() * 0.5;
+            }
+            ShapeGeometry::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            } => {
+                let opposite = handles[(index + 2) % 4];
+                let size = rotate(to - opposite, -*rotation);
+                *center = opposite.midpoint(&to);
+                *width = size.x.abs();
+                *height = size.y.abs();
+            }
+            ShapeGeometry::Polygon { vertices } => vertices[index] = to,
+        }
+        true
+    }
+
+    pub fn translate(&mut self, offset: Point) {
+        match self {
+            ShapeGeometry::Line { start, end } | ShapeGeometry::Arrow { start, end, .. } => {
+                *start = *start + offset;
+                *end = *end + offset;
+            }
+            ShapeGeometry::Ellipse { center, .. } | ShapeGeometry::Rectangle { center, .. } => {
+                *center = *center + offset;
+            }
+            ShapeGeometry::Polygon { vertices } => {
+                for v in vertices {
+                    *v = *v + offset;
+                }
+            }
+        }
+    }
+
+    /// Tight bounds of the outline's centerline.
+    pub fn bounding_box(&self) -> BoundingBox {
+        match self {
+            ShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => {
+                let (sin, cos) = rotation.sin_cos();
+                let half_width = (radius_x * cos).hypot(radius_y * sin);
+                let half_height = (radius_x * sin).hypot(radius_y * cos);
+                let offset = Point::new(half_width, half_height);
+                BoundingBox::from_points(&[*center - offset, *center + offset])
+            }
+            ShapeGeometry::Arrow { .. } => {
+                let ends: Vec<Point> = self.segments(0.0).iter().map(|s| s.p3).collect();
+                BoundingBox::from_points(&ends)
+            }
+            _ => BoundingBox::from_points(&self.handles()),
+        }
+    }
+
+    /// Outline flattened to a polyline; closed shapes repeat their first point at the end.
+    fn flatten(&self) -> Vec<Point> {
+        let segments = self.segments(0.0);
+        let mut points = Vec::with_capacity(segments.len() * FLATTEN_STEPS + 1);
+        if let Some(first) = segments.first() {
+            points.push(first.p0);
+        }
+        for seg in &segments {
+            let steps = (1..=FLATTEN_STEPS).map(|i| i as f64 / FLATTEN_STEPS as f64);
+            points.extend(steps.map(|t| seg.evaluate(t)));
+        }
+        points
+    }
+}
+
+impl From<RecognizedShape> for ShapeGeometry {
+    fn from(shape: RecognizedShape) -> Self {
+        match shape {
+            RecognizedShape::Line { start, end } => ShapeGeometry::Line { start, end },
+            RecognizedShape::Arrow {
+                start,
+                end,
+                head_length,
+            } => ShapeGeometry::Arrow {
+                start,
+                end,
+                head_length,
+            },
+            RecognizedShape::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => ShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            },
+            RecognizedShape::Triangle { vertices } => ShapeGeometry::Polygon {
+                vertices: vertices.to_vec(),
+            },
+            RecognizedShape::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            } => ShapeGeometry::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            },
+            RecognizedShape::Polygon { vertices } => ShapeGeometry::Polygon { vertices },
+        }
+    }
+}
+
+/// A shape element on a layer. Unlike a stroke it keeps its parametric geometry, so it
+/// can be resized or reshaped later without distorting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shape {
+    pub id: Uuid,
+    pub geometry: ShapeGeometry,
+    /// Outline color; `None` draws no outline.
+    pub stroke_color: Option<Color>,
+    pub stroke_width: f64,
+    /// Interior color. Ignored for lines and arrows, which enclose nothing.
+    pub fill_color: Option<Color>,
+}
+
+impl Shape {
+    /// An unfilled shape outlined in `stroke_color`.
+    pub fn new(geometry: ShapeGeometry, stroke_color: Color, stroke_width: f64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            geometry,
+            stroke_color: Some(stroke_color),
+            stroke_width,
+            fill_color: None,
+        }
+    }
+
+    pub fn with_fill(mut self, fill_color: Color) -> Self {
+        self.fill_color = Some(fill_color);
+        self
+    }
+
+    /// Fill color if the shape is closed and filled.
+    pub fn fill(&self) -> Option<Color> {
+        self.fill_color.filter(|_| self.geometry.is_closed())
+    }
+
+    /// Corners are sharp for rectangles and polygons and rounded for everything else.
+    pub fn line_join(&self) -> LineJoin {
+        match self.geometry {
+            ShapeGeometry::Rectangle { .. } | ShapeGeometry::Polygon { .. } => LineJoin::Miter,
+            _ => LineJoin::Round,
+        }
+    }
+
+    /// Bounds including the outline's width.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let mut bb = self.geometry.bounding_box();
+        if self.stroke_color.is_some() {
+            bb.expand_by(self.stroke_width * 0.5);
+        }
+        bb
+    }
+
+    /// Whether `point` is within `tolerance` of the visible outline, or inside a fill.
+    pub fn hit_test(&self, point: Point, tolerance: f64) -> bool {
+        let mut bb = self.bounding_box();
+        bb.expand_by(tolerance);
+        if !bb.contains_point(&point) {
+            return false;
+        }
+        let outline = self.geometry.flatten();
+        let reach = tolerance + self.stroke_color.map_or(0.0, |_| self.stroke_width * 0.5);
+        if outline
+            .windows(2)
+            .any(|w| distance_to_segment(point, w[0], w[1]) <= reach)
+        {
+            return true;
+        }
+        self.fill().is_some() && contains(&outline, point)
+    }
+}
+
+/// Even-odd point-in-polygon test on a closed polyline.
+fn contains(polygon: &[Point], p: Point) -> bool {
+    let mut inside = false;
+    for w in polygon.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+pub(crate) fn rotate(p: Point, angle: f64) -> Point {
+    let (sin, cos) = angle.sin_cos();
+    Point::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos)
+}
+
+pub(crate) fn distance_to_segment(p: Point, a: Point, b: Point) -> f64 {
+    let (ab, ap) = (b - a, p - a);
+    let length_sq = ab.x * ab.x + ab.y * ab.y;
+    if length_sq <= 0.0 {
+        return p.distance_to(&a);
+    }
+    let t = ((ap.x * ab.x + ap.y * ab.y) / length_sq).clamp(0.0, 1.0);
+    p.distance_to(&a.lerp(&b, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn rectangle() -> ShapeGeometry {
+        ShapeGeometry::Rectangle {
+            center: Point::new(50.0, 50.0),
+            width: 40.0,
+            height: 20.0,
+            rotation: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_corner_handle_resizes_without_distortion() {
+        let mut rect = rectangle();
+        assert!(rect.move_handle(2, Point::new(100.0, 80.0)));
+        match rect {
+            ShapeGeometry::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            } => {
+                // The top-left corner at (30, 40) stays put.
+                assert_eq!(center, Point::new(65.0, 60.0));
+                assert_eq!((width, height, rotation), (70.0, 40.0, 0.0));
+            }
+            other => panic!("Expected Rectangle, got {other:?}"),
+        }
+        assert!(!rect.move_handle(4, Point::new(0.0, 0.0)));
+
+        let mut ellipse = ShapeGeometry::Ellipse {
+            center: Point::new(0.0, 0.0),
+            radius_x: 10.0,
+            radius_y: 5.0,
+            rotation: FRAC_PI_2,
+        };
+        let fixed = ellipse.handles()[0];
+        ellipse.move_handle(2, Point::new(-10.0, 30.0));
+        let handles = ellipse.handles();
+        assert!(handles[0].distance_to(&fixed) < 1e-9);
+        let bb = ellipse.bounding_box();
+        assert!((bb.width() - 15.0).abs() < 1e-9 && (bb.height() - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hit_test_outline_and_fill() {
+        let outlined = Shape::new(rectangle(), Color::black(), 2.0);
+        assert!(outlined.hit_test(Point::new(30.5, 50.0), 0.0));
+        assert!(!outlined.hit_test(Point::new(50.0, 50.0), 2.0));
+
+        let filled = outlined.clone().with_fill(Color::white());
+        assert!(filled.hit_test(Point::new(50.0, 50.0), 0.0));
+        assert!(!filled.hit_test(Point::new(90.0, 50.0), 2.0));
+
+        let line = Shape::new(
+            ShapeGeometry::Line {
+                start: Point::new(0.0, 0.0),
+                end: Point::new(10.0, 10.0),
+            },
+            Color::black(),
+            2.0,
+        )
+        .with_fill(Color::white());
+        assert_eq!(line.fill(), None);
+        assert!(line.hit_test(Point::new(5.0, 6.0), 0.5));
+    }
+
+    #[test]
+    fn test_recognized_triangle_becomes_polygon() {
+        let vertices = [Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(5.0, 8.0)];
+        let geometry = ShapeGeometry::from(RecognizedShape::Triangle { vertices });
+        assert!(geometry.is_valid() && geometry.is_closed());
+        assert_eq!(geometry.handles(), vertices.to_vec());
+        assert!(!ShapeGeometry::Polygon {
+            vertices: vertices[..2].to_vec()
+        }
+        .is_valid());
+    }
+}
@@ -2,8 +2,11 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::brush::BrushConfig;
-use crate::geometry::{catmull_rom_to_bezier, BezierSegment};
+use crate::dash::dash_segments;
+use crate::geometry::{catmull_rom_to_bezier, BezierSegment, LazyRope, OneEuroFilter};
+use crate::nib::nib_outline;
 use crate::point::{BoundingBox, Color, Point, StrokePoint};
+use crate::stamp::{place_stamps, Stamp};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stroke {
@@ -25,6 +28,8 @@ pub struct SerializableBezierSegment {
     pub p3: Point,
     pub start_width: f64,
     pub end_width: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colors: Option<(Color, Color)>,
 }
 
 impl SerializableBezierSegment {
@@ -36,6 +41,7 @@ impl SerializableBezierSegment {
             p3: self.p3,
             start_width: self.start_width,
             end_width: self.end_width,
+            colors: self.colors,
         }
     }
 }
@@ -49,6 +55,7 @@ impl From<BezierSegment> for SerializableBezierSegment {
             p3: b.p3,
             start_width: b.start_width,
             end_width: b.end_width,
+            colors: b.colors,
         }
     }
 }
@@ -73,7 +80,27 @@ impl Stroke {
         self
     }
 
-    fn recompute_bounding_box(&mut self) {
+    /// Tips to draw for a stamp brush; empty for other brushes.
+    pub fn stamps(&self) -> Vec<Stamp> {
+        stamps_for(&self.brush, &self.segments, self.id, 0)
+    }
+
+    /// Filled outline for a calligraphy brush; empty for other brushes.
+    pub fn outline(&self) -> Vec<Vec<Point>> {
+        outline_for(&self.brush, &self.segments)
+    }
+
+    /// Dashes to draw for a dashed brush; empty for solid strokes.
+    pub fn dashes(&self) -> Vec<Vec<BezierSegment>> {
+        dashes_for(&self.brush, &self.segments, 0)
+    }
+
+    pub(crate) fn recompute_bounding_box(&mut self) {
+        let reach = match (self.brush.stamp_settings(), self.brush.nib_settings()) {
+            (Some(stamp), _) => stamp.reach(),
+            (None, Some(nib)) => nib.reach(),
+            (None, None) => 0.5,
+        };
         let mut bb = BoundingBox::empty();
         for seg in &self.segments {
             for t_step in 0..=10 {
@@ -82,7 +109,7 @@ impl Stroke {
                 let p = bezier.evaluate(t);
                 let w = bezier.width_at(t);
                 bb.expand_to_include(&p);
-                bb.expand_by(w * 0.5);
+                bb.expand_by(w * reach);
             }
         }
         if bb.is_valid() {
@@ -95,29 +122,103 @@ impl Stroke {
 pub struct StrokeBuilder {
     stroke: Stroke,
     widths: Vec<f64>,
+    /// Per-point colors, only recorded when the brush has color dynamics.
+    colors: Vec<Color>,
+    /// Length of the stroke so far, for gradients.
+    distance: f64,
     last_velocity: f64,
+    /// Input smoothing driven by `BrushConfig::smoothing`; `None` when it is 0.
+    filter: Option<OneEuroFilter>,
+    stabilizer: Option<LazyRope>,
 }
 
 impl StrokeBuilder {
     pub fn new(brush: BrushConfig) -> Self {
+        let filter = OneEuroFilter::from_smoothing(brush.smoothing);
         Self {
             stroke: Stroke::new(brush),
             widths: Vec::new(),
+            colors: Vec::new(),
+            distance: 0.0,
             last_velocity: 0.0,
+            filter,
+            stabilizer: None,
         }
     }
 
+    /// Make the drawn point trail the pen by `rope_length` canvas units (0 disables).
+    /// The remaining gap is closed by `finish`.
+    pub fn with_stabilizer(mut self, rope_length: f64) -> Self {
+        self.stabilizer = LazyRope::new(rope_length);
+        self
+    }
+
     /// Add a point and return new BezierSegments generated (if any).
-    pub fn add_point(&mut self, point: StrokePoint) -> Vec<BezierSegment> {
+    /// The position is smoothed first according to the brush's `smoothing`, then held
+    /// back by the stabilizer if one is set.
+    pub fn add_point(&mut self, mut point: StrokePoint) -> Vec<BezierSegment> {
+        if let Some(filter) = &mut self.filter {
+            point.position = filter.filter(point.position, point.timestamp);
+        }
+        if let Some(rope) = &mut self.stabilizer {
+            match rope.pull(point) {
+                Some(drawn) => point = drawn,
+                None => return vec![],
+            }
+        }
+        self.push_point(point)
+    }
+
+    /// Points accepted so far (after smoothing and stabilization).
+    pub fn points(&self) -> &[StrokePoint] {
+        &self.stroke.points
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.stroke.segments.len()
+    }
+
+    /// Current segments from `index` on. Earlier ones never change once a later point
+    /// has been added.
+    pub fn segments_from(&self, index: usize) -> Vec<BezierSegment> {
+        self.stroke.segments[index.min(self.stroke.segments.len())..]
+            .iter()
+            .map(SerializableBezierSegment::to_bezier)
+            .collect()
+    }
+
+    /// Dashes on segments from `index` on, for incremental drawing with a dashed brush.
+    pub fn dashes_from(&self, index: usize) -> Vec<Vec<BezierSegment>> {
+        dashes_for(&self.stroke.brush, &self.stroke.segments, index)
+    }
+
+    /// Stamps on segments from `index` on, for incremental drawing with a stamp brush.
+    pub fn stamps_from(&self, index: usize) -> Vec<Stamp> {
+        stamps_for(&self.stroke.brush, &self.stroke.segments, self.stroke.id, index)
+    }
+
+    pub fn last_width(&self) -> Option<f64> {
+        self.widths.last().copied()
+    }
+
+    fn push_point(&mut self, point: StrokePoint) -> Vec<BezierSegment> {
         // Calculate velocity-based width
         let velocity = if let Some(prev) = self.stroke.points.last() {
+            self.distance += prev.position.distance_to(&point.position);
             prev.speed_to(&point)
         } else {
             0.0
         };
         self.last_velocity = velocity;
-        let width = self.stroke.brush.compute_width(point.pressure, velocity);
+        let brush = &self.stroke.brush;
+        let width = brush.compute_width(point.pressure, velocity, point.altitude)
+            * brush.taper_in_factor(self.distance);
         self.widths.push(width);
+        if brush.has_color_dynamics() {
+            let noise = jitter_noise(self.stroke.id, self.colors.len());
+            let color = brush.compute_color(point.pressure, velocity, self.distance, noise);
+            self.colors.push(color);
+        }
         self.stroke.points.push(point);
 
         let n = self.stroke.points.len();
@@ -138,6 +239,7 @@ impl StrokeBuilder {
                 p3: p1,
                 start_width: self.widths[0],
                 end_width: self.widths[1],
+                colors: self.segment_colors(0, 1),
             };
             self.stroke.segments.push(seg.into());
             self.stroke.recompute_bounding_box();
@@ -148,7 +250,7 @@ impl StrokeBuilder {
         let idx = n - 1; // latest point index
         let i2 = idx;
         let i1 = idx - 1;
-        let i0 = if idx >= 2 { idx - 2 } else { 0 };
+        let i0 = idx.saturating_sub(2);
         let i3 = idx; // mirror: next point doesn't exist yet, use current
 
         let pts = &self.stroke.points;
@@ -181,6 +283,7 @@ impl StrokeBuilder {
                 p3: b3,
                 start_width: self.widths[ip1],
                 end_width: self.widths[ip2],
+                colors: self.segment_colors(ip1, ip2),
             };
             // Replace the second-to-last segment if it was a placeholder
             if self.stroke.segments.len() >= 2 {
@@ -197,6 +300,7 @@ impl StrokeBuilder {
             p3: pts[i2].position,
             start_width: self.widths[i1],
             end_width: self.widths[i2],
+            colors: self.segment_colors(i1, i2),
         };
         self.stroke.segments.push(last_seg.into());
         self.stroke.recompute_bounding_box();
@@ -214,9 +318,31 @@ impl StrokeBuilder {
         }
     }
 
+    fn segment_colors(&self, start: usize, end: usize) -> Option<(Color, Color)> {
+        if self.colors.is_empty() {
+            return None;
+        }
+        Some((self.colors[start], self.colors[end]))
+    }
+
     /// Finalize and return the completed Stroke.
     pub fn finish(mut self) -> Stroke {
-        // Refine the last segment with Catmull-Rom if possible
+        match self.stabilizer.take() {
+            Some(mut rope) => {
+                for point in rope.catch_up() {
+                    self.push_point(point);
+                }
+                self.refine_caught_up_end();
+            }
+            None => self.refine_end(),
+        }
+        self.apply_taper_out();
+        self.stroke.recompute_bounding_box();
+        self.stroke
+    }
+
+    /// Refine the last segment with Catmull-Rom over the last four points.
+    fn refine_end(&mut self) {
         let n = self.stroke.points.len();
         if n >= 4 {
             let pts = &self.stroke.points;
@@ -235,21 +361,124 @@ impl StrokeBuilder {
                 p3: b3,
                 start_width: self.widths[idx - 2],
                 end_width: self.widths[idx - 1],
+                colors: self.segment_colors(idx - 2, idx - 1),
             };
             if let Some(last) = self.stroke.segments.last_mut() {
                 *last = seg.into();
             }
         }
-        self.stroke.recompute_bounding_box();
-        self.stroke
+    }
+
+    /// After a stabilizer catch-up, refine the trailing linear segment so the stroke
+    /// still ends at the pen, mirroring the previous point past the end since there is
+    /// no next point.
+    fn refine_caught_up_end(&mut self) {
+        let n = self.stroke.points.len();
+        if n >= 3 {
+            let pts = &self.stroke.points;
+            let idx = n - 1;
+            let end = pts[idx].position;
+            let (b0, b1, b2, b3) = catmull_rom_to_bezier(
+                pts[idx - 2].position,
+                pts[idx - 1].position,
+                end,
+                end * 2.0 - pts[idx - 1].position,
+                0.5,
+            );
+            let seg = BezierSegment {
+                p0: b0,
+                p1: b1,
+                p2: b2,
+                p3: b3,
+                start_width: self.widths[idx - 1],
+                end_width: self.widths[idx],
+                colors: self.segment_colors(idx - 1, idx),
+            };
+            if let Some(last) = self.stroke.segments.last_mut() {
+                *last = seg.into();
+            }
+        }
+    }
+
+    /// Narrow the points within `taper_out` of the end. Segment `i` runs from point `i`
+    /// to point `i + 1`, so their widths are refreshed from the tapered points.
+    fn apply_taper_out(&mut self) {
+        let taper_out = self.stroke.brush.taper_out;
+        if taper_out.is_nan() || taper_out <= 0.0 {
+            return;
+        }
+        let mut to_end = 0.0;
+        for i in (0..self.widths.len()).rev() {
+            if i + 1 < self.stroke.points.len() {
+                let pts = &self.stroke.points;
+                to_end += pts[i].position.distance_to(&pts[i + 1].position);
+            }
+            if to_end >= taper_out {
+                break;
+            }
+            self.widths[i] *= self.stroke.brush.taper_out_factor(to_end);
+        }
+        for (i, seg) in self.stroke.segments.iter_mut().enumerate() {
+            seg.start_width = self.widths[i];
+            seg.end_width = self.widths[i + 1];
+        }
     }
 }
 
+fn stamps_for(
+    brush: &BrushConfig,
+    segments: &[SerializableBezierSegment],
+    stroke_id: Uuid,
+    from_segment: usize,
+) -> Vec<Stamp> {
+    let Some(settings) = brush.stamp_settings() else {
+        return vec![];
+    };
+    let segments: Vec<BezierSegment> = segments.iter().map(|s| s.to_bezier()).collect();
+    place_stamps(&segments, &settings, brush.color, stroke_id, from_segment)
+}
+
+fn dashes_for(
+    brush: &BrushConfig,
+    segments: &[SerializableBezierSegment],
+    from_segment: usize,
+) -> Vec<Vec<BezierSegment>> {
+    let Some(pattern) = brush.dash_pattern() else {
+        return vec![];
+    };
+    let segments: Vec<BezierSegment> = segments.iter().map(|s| s.to_bezier()).collect();
+    dash_segments(&segments, pattern, brush.base_width, from_segment)
+}
+
+fn outline_for(brush: &BrushConfig, segments: &[SerializableBezierSegment]) -> Vec<Vec<Point>> {
+    let Some(nib) = brush.nib_settings() else {
+        return vec![];
+    };
+    let segments: Vec<BezierSegment> = segments.iter().map(|s| s.to_bezier()).collect();
+    nib_outline(&segments, &nib)
+}
+
+/// Repeatable noise in `-1.0..=1.0` for sample `index` of a stroke, so jitter doesn't
+/// change between sessions.
+pub(crate) fn jitter_noise(stroke_id: Uuid, index: usize) -> f32 {
+    (stroke_hash(stroke_id, index) >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+/// SplitMix64 over the stroke id and `index`.
+pub(crate) fn stroke_hash(stroke_id: Uuid, index: usize) -> u64 {
+    let (hi, lo) = stroke_id.as_u64_pair();
+    let mut z = (hi ^ lo).wrapping_add((index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::brush::BrushConfig;
     use crate::point::Color;
+    use crate::pressure::PressureCurve;
 
     #[test]
     fn test_stroke_builder_single_point() {
@@ -286,4 +515,141 @@ mod tests {
         let stroke = Stroke::new(BrushConfig::default());
         assert!(!stroke.id.is_nil());
     }
+
+    #[test]
+    fn test_smoothing_damps_jitter() {
+        let jittery = |smoothing: f64| {
+            let mut brush = BrushConfig::pen(Color::black(), 2.0);
+            brush.smoothing = smoothing;
+            let mut builder = StrokeBuilder::new(brush);
+            for i in 0..30 {
+                let y = if i % 2 == 0 { 2.0 } else { -2.0 };
+                builder.add_point(StrokePoint::new(i as f64, y, 0.5, i as f64 * 0.008));
+            }
+            let stroke = builder.finish();
+            stroke.points[15..]
+                .iter()
+                .map(|p| p.position.y.abs())
+                .fold(0.0, f64::max)
+        };
+        assert!((jittery(0.0) - 2.0).abs() < 1e-9);
+        assert!(jittery(0.7) < 1.0);
+    }
+
+    #[test]
+    fn test_finish_without_stabilizer_is_unchanged() {
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        brush.smoothing = 0.0;
+        let input: Vec<StrokePoint> = (0..6)
+            .map(|i| {
+                let t = i as f64;
+                StrokePoint::new(t * 10.0, (t * 0.9).sin() * 8.0, 0.3 + t * 0.1, t * 0.01)
+            })
+            .collect();
+        for builder in [
+            StrokeBuilder::new(brush.clone()),
+            StrokeBuilder::new(brush.clone()).with_stabilizer(0.0),
+        ] {
+            let mut builder = builder;
+            for p in &input {
+                builder.add_point(*p);
+            }
+            let key = |s: &BezierSegment| (s.p0, s.p1, s.p2, s.p3, s.start_width, s.end_width);
+            let before: Vec<_> = builder.segments_from(0).iter().map(key).collect();
+            let widths = builder.widths.clone();
+            let stroke = builder.finish();
+            let after: Vec<_> = stroke.segments.iter().map(|s| s.to_bezier()).collect();
+
+            assert_eq!(stroke.points.len(), input.len());
+            assert_eq!(after.len(), before.len());
+            let unchanged: Vec<_> = after[..after.len() - 1].iter().map(key).collect();
+            assert_eq!(unchanged, before[..before.len() - 1]);
+            // The last segment is refined from the last four points, as it always was.
+            let n = input.len();
+            let (b0, b1, b2, b3) = catmull_rom_to_bezier(
+                input[n - 4].position,
+                input[n - 3].position,
+                input[n - 2].position,
+                input[n - 1].position,
+                0.5,
+            );
+            let last = after.last().unwrap();
+            assert_eq!((last.p0, last.p1, last.p2, last.p3), (b0, b1, b2, b3));
+            assert_eq!((last.start_width, last.end_width), (widths[n - 3], widths[n - 2]));
+        }
+    }
+
+    #[test]
+    fn test_stabilizer_ends_at_pen() {
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        brush.smoothing = 0.0;
+        let mut builder = StrokeBuilder::new(brush).with_stabilizer(20.0);
+        builder.add_point(StrokePoint::new(0.0, 0.0, 0.5, 0.0));
+        for i in 1..=10 {
+            let t = i as f64;
+            let segs = builder.add_point(StrokePoint::new(t * 3.0, 0.0, 0.5, t * 0.01));
+            if i <= 6 {
+                // Still within the rope length of the start.
+                assert!(segs.is_empty());
+            }
+        }
+        let stroke = builder.finish();
+        let last = stroke.points.last().unwrap().position;
+        assert!((last.x - 30.0).abs() < 1e-9);
+        let end = stroke.segments.last().unwrap().p3;
+        assert!((end.x - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_opacity_dynamics_color_segments() {
+        let mut brush = BrushConfig::pen(Color::black(), 2.0);
+        let plain = {
+            let mut builder = StrokeBuilder::new(brush.clone());
+            builder.add_point(StrokePoint::new(0.0, 0.0, 0.5, 0.0));
+            builder.add_point(StrokePoint::new(10.0, 0.0, 0.5, 0.016));
+            builder.finish()
+        };
+        assert!(plain.segments.iter().all(|s| s.colors.is_none()));
+
+        brush.opacity_curve = Some(PressureCurve::linear());
+        let mut builder = StrokeBuilder::new(brush);
+        for i in 0..6 {
+            let pressure = 0.2 + i as f64 * 0.15;
+            builder.add_point(StrokePoint::new(i as f64 * 10.0, 0.0, pressure, i as f64 * 0.016));
+        }
+        let stroke = builder.finish();
+        let (first, _) = stroke.segments[0].colors.unwrap();
+        let (_, last) = stroke.segments.last().unwrap().colors.unwrap();
+        assert!(first.a < last.a, "light pressure should draw lighter");
+    }
+
+    #[test]
+    fn test_jitter_noise_is_repeatable() {
+        let id = Uuid::new_v4();
+        let noise: Vec<f32> = (0..100).map(|i| jitter_noise(id, i)).collect();
+        assert!(noise.iter().all(|n| (-1.0..=1.0).contains(n)));
+        assert!(noise.iter().any(|n| *n < -0.5) && noise.iter().any(|n| *n > 0.5));
+        assert_eq!(jitter_noise(id, 7), noise[7]);
+    }
+
+    #[test]
+    fn test_taper_points_both_ends() {
+        let mut brush = BrushConfig::pen(Color::black(), 4.0);
+        brush.smoothing = 0.0;
+        brush.taper_in = 30.0;
+        brush.taper_out = 30.0;
+        let mut builder = StrokeBuilder::new(brush);
+        for i in 0..=20 {
+            builder.add_point(StrokePoint::new(i as f64 * 5.0, 0.0, 0.5, i as f64 * 0.016));
+        }
+        let stroke = builder.finish();
+        let first = stroke.segments[0];
+        let middle = stroke.segments[10];
+        let last = stroke.segments.last().unwrap();
+        assert!(first.start_width < first.end_width);
+        assert!(first.start_width < middle.start_width * 0.2);
+        assert!(last.end_width < last.start_width);
+        assert!(last.end_width < middle.end_width * 0.2);
+        assert_eq!(middle.start_width, middle.end_width);
+    }
 }
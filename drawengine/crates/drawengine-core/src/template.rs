@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+
+use crate::point::{Color, Point};
+use crate::render::RenderCommand;
+
+/// Spacing below this (in canvas units) is treated as invalid and renders nothing,
+/// so a corrupt file cannot make template generation emit millions of lines.
+pub const MIN_TEMPLATE_SPACING: f64 = 2.0;
+
+/// Background pattern drawn beneath the strokes of a page.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PageTemplate {
+    #[default]
+    Blank,
+    /// Horizontal lines every `spacing`, starting `top_margin` from the top, with an
+    /// optional vertical margin line at `margin_x`.
+    Ruled {
+        spacing: f64,
+        top_margin: f64,
+        margin_x: Option<f64>,
+        line_color: Color,
+        margin_color: Color,
+        line_width: f64,
+    },
+    Grid {
+        spacing: f64,
+        line_color: Color,
+        line_width: f64,
+    },
+    DotGrid {
+        spacing: f64,
+        dot_color: Color,
+        dot_radius: f64,
+    },
+    /// Cornell notes: header band, cue column on the left, summary band at the bottom,
+    /// and ruled lines in the note area.
+    Cornell {
+        spacing: f64,
+        header_height: f64,
+        cue_width: f64,
+        summary_height: f64,
+        line_color: Color,
+        divider_color: Color,
+        line_width: f64,
+    },
+    /// Five-line staves; `line_spacing` between staff lines, `staff_gap` between staves.
+    MusicStaff {
+        line_spacing: f64,
+        staff_gap: f64,
+        line_color: Color,
+        line_width: f64,
+    },
+}
+
+fn light_blue() -> Color {
+    Color::new(0.68, 0.78, 0.9, 1.0)
+}
+
+fn margin_red() -> Color {
+    Color::new(0.9, 0.45, 0.45, 1.0)
+}
+
+impl PageTemplate {
+    pub fn ruled(spacing: f64) -> Self {
+        PageTemplate::Ruled {
+            spacing,
+            top_margin: spacing * 3.0,
+            margin_x: Some(spacing * 3.0),
+            line_color: light_blue(),
+            margin_color: margin_red(),
+            line_width: 1.0,
+        }
+    }
+
+    pub fn grid(spacing: f64) -> Self {
+        PageTemplate::Grid {
+            spacing,
+            line_color: light_blue(),
+            line_width: 0.5,
+        }
+    }
+
+    pub fn dot_grid(spacing: f64) -> Self {
+        PageTemplate::DotGrid {
+            spacing,
+            dot_color: Color::new(0.6, 0.6, 0.6, 1.0),
+            dot_radius: 1.0,
+        }
+    }
+
+    pub fn cornell(spacing: f64) -> Self {
+        PageTemplate::Cornell {
+            spacing,
+            header_height: spacing * 3.0,
+            cue_width: spacing * 8.0,
+            summary_height: spacing * 8.0,
+            line_color: light_blue(),
+            divider_color: margin_red(),
+            line_width: 1.0,
+        }
+    }
+
+    pub fn music_staff(line_spacing: f64) -> Self {
+        PageTemplate::MusicStaff {
+            line_spacing,
+            staff_gap: line_spacing * 6.0,
+            line_color: Color::new(0.3, 0.3, 0.3, 1.0),
+            line_width: 1.0,
+        }
+    }
+
+    /// Whether the spacing parameters are usable. Invalid templates render nothing.
+    pub fn is_valid(&self) -> bool {
+        let ok = |v: f64| v.is_finite() && v >= MIN_TEMPLATE_SPACING;
+        match self {
+            PageTemplate::Blank => true,
+            PageTemplate::Ruled { spacing, .. }
+            | PageTemplate::Grid { spacing, .. }
+            | PageTemplate::DotGrid { spacing, .. }
+            | PageTemplate::Cornell { spacing, .. } => ok(*spacing),
+            PageTemplate::MusicStaff {
+                line_spacing,
+                staff_gap,
+                ..
+            } => ok(*line_spacing) && staff_gap.is_finite() && *staff_gap >= 0.0,
+        }
+    }
+
+    /// Commands that draw the template for a page of `width`×`height` in page coordinates.
+    pub fn render_commands(&self, width: f64, height: f64) -> Vec<RenderCommand> {
+        if !self.is_valid() {
+            return vec![];
+        }
+        match *self {
+            PageTemplate::Blank => vec![],
+            PageTemplate::Ruled {
+                spacing,
+                top_margin,
+                margin_x,
+                line_color,
+                margin_color,
+                line_width,
+            } => {
+                let mut cmds = vec![lines(
+                    horizontal_lines(top_margin, height, spacing, 0.0, width),
+                    line_color,
+                    line_width,
+                )];
+                if let Some(x) = margin_x {
+                    cmds.push(lines(
+                        vec![(Point::new(x, 0.0), Point::new(x, height))],
+                        margin_color,
+                        line_width,
+                    ));
+                }
+                cmds
+            }
+            PageTemplate::Grid {
+                spacing,
+                line_color,
+                line_width,
+            } => {
+                let mut segs = horizontal_lines(spacing, height, spacing, 0.0, width);
+                segs.extend(
+                    steps(spacing, width, spacing)
+                        .map(|x| (Point::new(x, 0.0), Point::new(x, height))),
+                );
+                vec![lines(segs, line_color, line_width)]
+            }
+            PageTemplate::DotGrid {
+                spacing,
+                dot_color,
+                dot_radius,
+            } => {
+                let centers = steps(spacing, height, spacing)
+                    .flat_map(|y| steps(spacing, width, spacing).map(move |x| Point::new(x, y)))
+                    .collect();
+                vec![RenderCommand::DrawDots {
+                    centers,
+                    radius: dot_radius,
+                    color: dot_color,
+                }]
+            }
+            PageTemplate::Cornell {
+                spacing,
+                header_height,
+                cue_width,
+                summary_height,
+                line_color,
+                divider_color,
+                line_width,
+            } => {
+                let summary_top = (height - summary_height).max(header_height);
+                let first_line = header_height + spacing;
+                let ruled = horizontal_lines(first_line, summary_top, spacing, 0.0, width);
+                let dividers = vec![
+                    (Point::new(0.0, header_height), Point::new(width, header_height)),
+                    (Point::new(cue_width, header_height), Point::new(cue_width, summary_top)),
+                    (Point::new(0.0, summary_top), Point::new(width, summary_top)),
+                ];
+                vec![
+                    lines(ruled, line_color, line_width),
+                    lines(dividers, divider_color, line_width * 2.0),
+                ]
+            }
+            PageTemplate::MusicStaff {
+                line_spacing,
+                staff_gap,
+                line_color,
+                line_width,
+            } => {
+                let staff_height = line_spacing * 4.0;
+                let mut segs = Vec::new();
+                let mut top = staff_gap.max(line_spacing);
+                while top + staff_height <= height {
+                    for i in 0..5 {
+                        let y = top + line_spacing * i as f64;
+                        segs.push((Point::new(0.0, y), Point::new(width, y)));
+                    }
+                    top += staff_height + staff_gap.max(line_spacing);
+                }
+                vec![lines(segs, line_color, line_width)]
+            }
+        }
+    }
+}
+
+/// Values `start, start + step, ...` strictly below `end`.
+fn steps(start: f64, end: f64, step: f64) -> impl Iterator<Item = f64> {
+    let count = if end > start {
+        ((end - start) / step).ceil() as usize
+    } else {
+        0
+    };
+    (0..count).map(move |i| start + step * i as f64)
+}
+
+fn horizontal_lines(start: f64, end: f64, spacing: f64, x0: f64, x1: f64) -> Vec<(Point, Point)> {
+    steps(start, end, spacing)
+        .map(|y| (Point::new(x0, y), Point::new(x1, y)))
+        .collect()
+}
+
+fn lines(segments: Vec<(Point, Point)>, color: Color, width: f64) -> RenderCommand {
+    RenderCommand::DrawLines {
+        segments,
+        color,
+        width,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_count(cmd: &RenderCommand) -> usize {
+        match cmd {
+            RenderCommand::DrawLines { segments, .. } => segments.len(),
+            other => panic!("Expected DrawLines, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_blank_renders_nothing() {
+        assert!(PageTemplate::Blank.render_commands(100.0, 100.0).is_empty());
+    }
+
+    #[test]
+    fn test_ruled_line_count() {
+        let template = PageTemplate::Ruled {
+            spacing: 10.0,
+            top_margin: 20.0,
+            margin_x: None,
+            line_color: Color::black(),
+            margin_color: Color::black(),
+            line_width: 1.0,
+        };
+        let cmds = template.render_commands(50.0, 100.0);
+        assert_eq!(cmds.len(), 1);
+        // y = 20, 30, ..., 90
+        assert_eq!(line_count(&cmds[0]), 8);
+    }
+
+    #[test]
+    fn test_dot_grid() {
+        let cmds = PageTemplate::dot_grid(10.0).render_commands(35.0, 25.0);
+        match &cmds[0] {
+            RenderCommand::DrawDots { centers, .. } => assert_eq!(centers.len(), 3 * 2),
+            other => panic!("Expected DrawDots, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_music_staff_has_five_line_groups() {
+        let cmds = PageTemplate::music_staff(8.0).render_commands(100.0, 300.0);
+        assert_eq!(line_count(&cmds[0]) % 5, 0);
+        assert!(line_count(&cmds[0]) >= 10);
+    }
+
+    #[test]
+    fn test_cornell_dividers() {
+        let cmds = PageTemplate::cornell(10.0).render_commands(200.0, 300.0);
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(line_count(&cmds[1]), 3);
+    }
+
+    #[test]
+    fn test_invalid_spacing_renders_nothing() {
+        let template = PageTemplate::grid(0.0);
+        assert!(!template.is_valid());
+        assert!(template.render_commands(1000.0, 1000.0).is_empty());
+        assert!(PageTemplate::dot_grid(f64::NAN).render_commands(10.0, 10.0).is_empty());
+    }
+}
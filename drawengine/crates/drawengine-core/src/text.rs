@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::point::{BoundingBox, Color, Point};
+
+pub const DEFAULT_FONT_FAMILY: &str = "sans-serif";
+
+pub const DEFAULT_FONT_SIZE: f64 = 16.0;
+
+/// Distance between baselines, in ems, for `EstimatedTextMeasure`.
+const LINE_HEIGHT: f64 = 1.25;
+
+/// Distance from the top of a line to its baseline, in ems, for `EstimatedTextMeasure`.
+const ASCENT: f64 = 0.8;
+
+/// Estimated advances, in ems, by kind of character.
+const AVERAGE_ADVANCE: f64 = 0.55;
+const NARROW_ADVANCE: f64 = 0.3;
+const CAPITAL_ADVANCE: f64 = 0.65;
+const BROAD_ADVANCE: f64 = 0.85;
+const SPACE_ADVANCE: f64 = 0.3;
+
+/// Advance of CJK and other full-width characters, in ems.
+const WIDE_ADVANCE: f64 = 1.0;
+
+/// Vertical metrics of a font at a given size, in page units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// Distance from the top of a line to its baseline.
+    pub ascent: f64,
+    /// Distance between baselines.
+    pub line_height: f64,
+}
+
+/// Measures text for line wrapping. Hosts that know the fonts they draw with should
+/// supply one, so lines break where the native renderer expects them to.
+pub trait TextMeasure: Send + Sync {
+    /// Advance of `text` set in `font_family` at `font_size`, in page units.
+    fn advance(&self, text: &str, font_family: &str, font_size: f64) -> f64;
+
+    fn font_metrics(&self, font_family: &str, font_size: f64) -> FontMetrics;
+}
+
+/// Per-character guesses used until the host supplies real metrics. Close enough for
+/// hit-testing and a first layout, but real fonts will wrap somewhat differently.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EstimatedTextMeasure;
+
+impl TextMeasure for EstimatedTextMeasure {
+    fn advance(&self, text: &str, _font_family: &str, font_size: f64) -> f64 {
+        text.chars().map(estimated_advance).sum::<f64>() * font_size
+    }
+
+    fn font_metrics(&self, _font_family: &str, font_size: f64) -> FontMetrics {
+        FontMetrics {
+            ascent: font_size * ASCENT,
+            line_height: font_size * LINE_HEIGHT,
+        }
+    }
+}
+
+/// A typed text label on a layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextBox {
+    pub id: Uuid,
+    /// Top-left corner in page-local coordinates.
+    pub position: Point,
+    /// Wrapping width. Lines break at spaces (or inside words too long to fit) to stay
+    /// within it; `None` only breaks at newlines.
+    pub width: Option<f64>,
+    pub font_family: String,
+    pub font_size: f64,
+    pub color: Color,
+    pub text: String,
+}
+
+/// One laid-out line of a text box, ready to draw as-is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun {
+    pub text: String,
+    /// Left end of the baseline in page-local coordinates.
+    pub origin: Point,
+    /// Measured advance of the whole run.
+    pub width: f64,
+}
+
+impl TextBox {
+    /// Black text in the default font.
+    pub fn new(position: Point, width: Option<f64>, text: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            position,
+            width,
+            font_family: DEFAULT_FONT_FAMILY.to_string(),
+            font_size: DEFAULT_FONT_SIZE,
+            color: Color::black(),
+            text: text.into(),
+        }
+    }
+
+    /// Finite position, a positive font size and a non-negative wrapping width.
+    pub fn is_valid(&self) -> bool {
+        self.position.x.is_finite()
+            && self.position.y.is_finite()
+            && self.font_size.is_finite()
+            && self.font_size > 0.0
+            && self.width.is_none_or(|w| w.is_finite() && w >= 0.0)
+    }
+
+    /// Lines of text wrapped to `width`, one run per non-empty line.
+    pub fn layout(&self, measure: &dyn TextMeasure) -> Vec<TextRun> {
+        let metrics = self.font_metrics(measure);
+        let top = self.position.y + metrics.ascent;
+        self.lines(measure)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (text, _))| !text.is_empty())
+            .map(|(i, (text, width))| TextRun {
+                text,
+                origin: Point::new(self.position.x, top + i as f64 * metrics.line_height),
+                width,
+            })
+            .collect()
+    }
+
+    /// The wrapping width, or the widest line when the box doesn't wrap, by the height
+    /// of all lines.
+    pub fn bounding_box(&self, measure: &dyn TextMeasure) -> BoundingBox {
+        let lines = self.lines(measure);
+        let width = self
+            .width
+            .unwrap_or_else(|| lines.iter().map(|(_, w)| *w).fold(0.0, f64::max));
+        let height = lines.len() as f64 * self.font_metrics(measure).line_height;
+        BoundingBox::from_points(&[
+            self.position,
+            self.position + Point::new(width, height),
+        ])
+    }
+
+    pub fn hit_test(&self, point: Point, tolerance: f64, measure: &dyn TextMeasure) -> bool {
+        let mut bb = self.bounding_box(measure);
+        bb.expand_by(tolerance);
+        bb.contains_point(&point)
+    }
+
+    /// The host's metrics, or estimates where it returns unusable values.
+    fn font_metrics(&self, measure: &dyn TextMeasure) -> FontMetrics {
+        let metrics = measure.font_metrics(&self.font_family, self.font_size);
+        let usable = |v: f64| v.is_finite() && v >= 0.0;
+        if usable(metrics.ascent) && usable(metrics.line_height) {
+            metrics
+        } else {
+            EstimatedTextMeasure.font_metrics(&self.font_family, self.font_size)
+        }
+    }
+
+    /// Wrapped lines with their measured widths, including empty ones.
+    fn lines(&self, measure: &dyn TextMeasure) -> Vec<(String, f64)> {
+        let max = self.width.unwrap_or(f64::INFINITY);
+        // `max` also drops NaN.
+        let advance = |text: &str| {
+            measure
+                .advance(text, &self.font_family, self.font_size)
+                .max(0.0)
+        };
+        let space = advance(" ");
+        let mut lines = Vec::new();
+        for paragraph in self.text.split('\n') {
+            let mut line = String::new();
+            let mut line_width = 0.0;
+            for word in paragraph.split(' ') {
+                // Whole words are measured so kerning within them counts.
+                let word_width = advance(word);
+                if !line.is_empty() && line_width + space + word_width > max {
+                    lines.push((std::mem::take(&mut line), line_width));
+                    line_width = 0.0;
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                    line_width += space;
+                }
+                // Only a word wider than a whole line is broken between characters.
+                if line_width + word_width <= max {
+                    line.push_str(word);
+                    line_width += word_width;
+                    continue;
+                }
+                for c in word.chars() {
+                    let a = advance(c.encode_utf8(&mut [0; 4]));
+                    if !line.is_empty() && line_width + a > max {
+                        lines.push((std::mem::take(&mut line), line_width));
+                        line_width = 0.0;
+                    }
+                    line.push(c);
+                    line_width += a;
+                }
+            }
+            lines.push((line, line_width));
+        }
+        lines
+    }
+}
+
+fn estimated_advance(c: char) -> f64 {
+    match c {
+        c if c.is_whitespace() => SPACE_ADVANCE,
+        'i' | 'j' | 'l' | 'f' | 't' | 'r' | 'I' | '.' | ',' | ':' | ';' | '!' | '\'' | '|' => {
+            NARROW_ADVANCE
+        }
+        'm' | 'w' | 'M' | 'W' | '@' => BROAD_ADVANCE,
+        c if c.is_uppercase() => CAPITAL_ADVANCE,
+        c if is_full_width(c) => WIDE_ADVANCE,
+        _ => AVERAGE_ADVANCE,
+    }
+}
+
+/// East Asian wide and full-width characters: Hangul, CJK, kana, full-width forms.
+fn is_full_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1100}'..='\u{115F}'
+            | '\u{2E80}'..='\u{A4CF}'
+            | '\u{AC00}'..='\u{D7A3}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FE30}'..='\u{FE4F}'
+            | '\u{FF00}'..='\u{FF60}'
+            | '\u{FFE0}'..='\u{FFE6}'
+            | '\u{1F300}'..='\u{1F64F}'
+            | '\u{20000}'..='\u{3FFFD}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(width: Option<f64>, text: &str) -> TextBox {
+        let mut text_box = TextBox::new(Point::new(10.0, 20.0), width, text);
+        text_box.font_size = 10.0;
+        text_box
+    }
+
+    fn texts(runs: Vec<TextRun>) -> Vec<String> {
+        runs.into_iter().map(|r| r.text).collect()
+    }
+
+    /// Made-up proportional font: "i" is 2 wide, "W" 12, anything else 6; lines are
+    /// 20 apart with the baseline 15 down.
+    struct TestFont;
+
+    impl TextMeasure for TestFont {
+        fn advance(&self, text: &str, _font_family: &str, _font_size: f64) -> f64 {
+            text.chars()
+                .map(|c| match c {
+                    'i' => 2.0,
+                    'W' => 12.0,
+                    _ => 6.0,
+                })
+                .sum()
+        }
+
+        fn font_metrics(&self, _font_family: &str, _font_size: f64) -> FontMetrics {
+            FontMetrics {
+                ascent: 15.0,
+                line_height: 20.0,
+            }
+        }
+    }
+
+    #[test]
+    fn test_wraps_at_spaces_and_newlines() {
+        // Each character is 5.5 wide and a space 3, so "aaaa bbbb" is 47.
+        let text_box = label(Some(40.0), "aaaa bbbb\n\ncc");
+        let layout = text_box.layout(&EstimatedTextMeasure);
+        assert_eq!(texts(layout.clone()), ["aaaa", "bbbb", "cc"]);
+        assert_eq!(layout[0].origin, Point::new(10.0, 28.0));
+        // The empty line still takes up space.
+        assert!((layout[2].origin.y - (28.0 + 3.0 * 12.5)).abs() < 1e-9);
+        let bb = text_box.bounding_box(&EstimatedTextMeasure);
+        assert!((bb.height() - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_long_word_breaks_and_unwrapped_box_grows() {
+        let runs = label(Some(20.0), "abcdegh").layout(&EstimatedTextMeasure);
+        assert_eq!(texts(runs), ["abc", "deg", "h"]);
+
+        let text_box = label(None, "abcd abcd");
+        assert_eq!(text_box.layout(&EstimatedTextMeasure).len(), 1);
+        let bb = text_box.bounding_box(&EstimatedTextMeasure);
+        assert!((bb.width() - 47.0).abs() < 1e-9);
+        assert!(text_box.hit_test(Point::new(50.0, 25.0), 0.0, &EstimatedTextMeasure));
+        assert!(!text_box.hit_test(Point::new(60.0, 25.0), 1.0, &EstimatedTextMeasure));
+    }
+
+    #[test]
+    fn test_wraps_by_measured_advances() {
+        // Same length, different widths: "iiiii iiiii" is 26 but "WWWWW WWWWW" is 126.
+        let narrow = label(Some(60.0), "iiiii iiiii");
+        assert_eq!(texts(narrow.layout(&TestFont)), ["iiiii iiiii"]);
+        let broad = label(Some(60.0), "WWWWW WWWWW");
+        let runs = broad.layout(&TestFont);
+        assert_eq!(texts(runs.clone()), ["WWWWW", "WWWWW"]);
+        assert_eq!(runs[0].width, 60.0);
+        assert_eq!(runs[1].origin, Point::new(10.0, 55.0));
+        assert_eq!(broad.bounding_box(&TestFont).height(), 40.0);
+
+        // The fallback estimates are proportional too.
+        let estimate = |text: &str| EstimatedTextMeasure.advance(text, DEFAULT_FONT_FAMILY, 10.0);
+        assert!(estimate("illi") < estimate("abcd"));
+        assert!(estimate("abcd") < estimate("MWMW"));
+        assert_eq!(estimate("漢字"), 20.0);
+    }
+}
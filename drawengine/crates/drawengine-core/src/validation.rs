@@ -0,0 +1,890 @@
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use crate::brush::BrushConfig;
+use crate::dash::DashPattern;
+use crate::fill::FillRegion;
+use crate::image::Image;
+use crate::nib::NibSettings;
+use crate::point::{BoundingBox, Color};
+use crate::pressure::PressureCurve;
+use crate::serialization::DocumentData;
+use crate::shape::Shape;
+use crate::stamp::{StampSettings, StampTip};
+use crate::stroke::Stroke;
+use crate::template::PageTemplate;
+use crate::text::{TextBox, DEFAULT_FONT_SIZE};
+
+/// Tolerance used when comparing a stored bounding box against a recomputed one.
+const BOUNDING_BOX_EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// A point, control point, width or pressure is NaN or infinite, or a shape's
+    /// geometry, an image's crop or a fill's outline is unusable.
+    NonFiniteGeometry,
+    /// A segment or outline width is negative, a text box has a negative
+    /// wrapping width or a font size that isn't positive, or an image's scale isn't
+    /// positive.
+    NegativeWidth,
+    /// A color component is outside `0.0..=1.0` or not finite.
+    ColorOutOfRange,
+    /// The stored bounding box does not match the stroke's segments.
+    StaleBoundingBox,
+    /// The stroke, shape, text box, image or fill id is already used by an earlier
+    /// element in the document.
+    DuplicateElementId,
+    /// The layer id is already used by an earlier layer in the document.
+    DuplicateLayerId,
+    /// The page id is already used by an earlier page in the document.
+    DuplicatePageId,
+    /// The page template has unusable spacing; it would render nothing.
+    InvalidTemplate,
+    /// Layer opacity is outside `0.0..=1.0` or not finite.
+    InvalidLayerOpacity,
+    /// A stroke's brush has a width, factor, sensitivity, color or tip setting that is
+    /// out of range, a minimum width factor above its maximum, or a malformed pressure
+    /// or opacity curve.
+    InvalidBrush,
+}
+
+/// A single problem found in a document. `page_id`/`layer_id`/`element_id` locate it
+/// when applicable; `element_id` is the stroke, shape, text box, image or fill.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    pub page_id: Option<Uuid>,
+    pub layer_id: Option<Uuid>,
+    pub element_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(
+        &mut self,
+        kind: IssueKind,
+        page_id: Option<Uuid>,
+        layer_id: Option<Uuid>,
+        element_id: Option<Uuid>,
+    ) {
+        self.issues.push(ValidationIssue {
+            kind,
+            page_id,
+            layer_id,
+            element_id,
+        });
+    }
+}
+
+/// Check a document for values that would break rendering or hit-testing.
+pub fn validate_document(doc: &DocumentData) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let mut page_ids = HashSet::new();
+    let mut layer_ids = HashSet::new();
+    let mut element_ids = HashSet::new();
+    for page in &doc.pages {
+        let pid = Some(page.id);
+        if !page_ids.insert(page.id) {
+            report.push(IssueKind::DuplicatePageId, pid, None, None);
+        }
+        if !color_in_range(&page.background_color) {
+            report.push(IssueKind::ColorOutOfRange, pid, None, None);
+        }
+        if !page.template.is_valid() {
+            report.push(IssueKind::InvalidTemplate, pid, None, None);
+        }
+        for layer in &page.layer_manager.layers {
+            let lid = Some(layer.id);
+            if !layer_ids.insert(layer.id) {
+                report.push(IssueKind::DuplicateLayerId, pid, lid, None);
+            }
+            if !(0.0..=1.0).contains(&layer.opacity) {
+                report.push(IssueKind::InvalidLayerOpacity, pid, lid, None);
+            }
+            for stroke in &layer.strokes {
+                check_stroke(&mut report, stroke, pid, lid, &mut element_ids);
+            }
+            for shape in &layer.shapes {
+                check_shape(&mut report, shape, pid, lid, &mut element_ids);
+            }
+            for text in &layer.texts {
+                check_text(&mut report, text, pid, lid, &mut element_ids);
+            }
+            for image in &layer.images {
+                check_image(&mut report, image, pid, lid, &mut element_ids);
+            }
+            for fill in &layer.fills {
+                check_fill(&mut report, fill, pid, lid, &mut element_ids);
+            }
+        }
+    }
+    // Infinite-canvas issues carry no page or layer id.
+    if let Some(canvas) = &doc.infinite {
+        if !color_in_range(&canvas.background_color) {
+            report.push(IssueKind::ColorOutOfRange, None, None, None);
+        }
+        for chunk in canvas.chunks() {
+            for stroke in &chunk.strokes {
+                check_stroke(&mut report, stroke, None, None, &mut element_ids);
+            }
+        }
+    }
+    report
+}
+
+fn check_stroke(
+    report: &mut ValidationReport,
+    stroke: &Stroke,
+    pid: Option<Uuid>,
+    lid: Option<Uuid>,
+    element_ids: &mut HashSet<Uuid>,
+) {
+    let eid = Some(stroke.id);
+    if !element_ids.insert(stroke.id) {
+        report.push(IssueKind::DuplicateElementId, pid, lid, eid);
+    }
+    if !stroke_is_finite(stroke) {
+        // Anything derived from non-finite geometry is meaningless; skip the rest.
+        report.push(IssueKind::NonFiniteGeometry, pid, lid, eid);
+        return;
+    }
+    if stroke_has_negative_width(stroke) {
+        report.push(IssueKind::NegativeWidth, pid, lid, eid);
+    }
+    if !stroke_colors(stroke).all(|c| color_in_range(&c)) {
+        report.push(IssueKind::ColorOutOfRange, pid, lid, eid);
+    }
+    if bounding_box_is_stale(stroke) {
+        report.push(IssueKind::StaleBoundingBox, pid, lid, eid);
+    }
+    if !brush_is_valid(&stroke.brush) {
+        report.push(IssueKind::InvalidBrush, pid, lid, eid);
+    }
+}
+
+fn check_shape(
+    report: &mut ValidationReport,
+    shape: &Shape,
+    pid: Option<Uuid>,
+    lid: Option<Uuid>,
+    element_ids: &mut HashSet<Uuid>,
+) {
+    let eid = Some(shape.id);
+    if !element_ids.insert(shape.id) {
+        report.push(IssueKind::DuplicateElementId, pid, lid, eid);
+    }
+    if !shape_is_finite(shape) {
+        report.push(IssueKind::NonFiniteGeometry, pid, lid, eid);
+        return;
+    }
+    if shape.stroke_width < 0.0 {
+        report.push(IssueKind::NegativeWidth, pid, lid, eid);
+    }
+    if !shape_colors(shape).all(|c| color_in_range(&c)) {
+        report.push(IssueKind::ColorOutOfRange, pid, lid, eid);
+    }
+}
+
+fn check_text(
+    report: &mut ValidationReport,
+    text: &TextBox,
+    pid: Option<Uuid>,
+    lid: Option<Uuid>,
+    element_ids: &mut HashSet<Uuid>,
+) {
+    let eid = Some(text.id);
+    if !element_ids.insert(text.id) {
+        report.push(IssueKind::DuplicateElementId, pid, lid, eid);
+    }
+    if !text_is_finite(text) {
+        report.push(IssueKind::NonFiniteGeometry, pid, lid, eid);
+        return;
+    }
+    if text.font_size <= 0.0 || text.width.is_some_and(|w| w < 0.0) {
+        report.push(IssueKind::NegativeWidth, pid, lid, eid);
+    }
+    if !color_in_range(&text.color) {
+        report.push(IssueKind::ColorOutOfRange, pid, lid, eid);
+    }
+}
+
+fn check_image(
+    report: &mut ValidationReport,
+    image: &Image,
+    pid: Option<Uuid>,
+    lid: Option<Uuid>,
+    element_ids: &mut HashSet<Uuid>,
+) {
+    let eid = Some(image.id);
+    if !element_ids.insert(image.id) {
+        report.push(IssueKind::DuplicateElementId, pid, lid, eid);
+    }
+    if !image_is_finite(image) {
+        report.push(IssueKind::NonFiniteGeometry, pid, lid, eid);
+        return;
+    }
+    if image.scale <= 0.0 {
+        report.push(IssueKind::NegativeWidth, pid, lid, eid);
+    }
+}
+
+fn check_fill(
+    report: &mut ValidationReport,
+    fill: &FillRegion,
+    pid: Option<Uuid>,
+    lid: Option<Uuid>,
+    element_ids: &mut HashSet<Uuid>,
+) {
+    let eid = Some(fill.id);
+    if !element_ids.insert(fill.id) {
+        report.push(IssueKind::DuplicateElementId, pid, lid, eid);
+    }
+    if !fill.is_valid() {
+        report.push(IssueKind::NonFiniteGeometry, pid, lid, eid);
+        return;
+    }
+    if !color_in_range(&fill.color) {
+        report.push(IssueKind::ColorOutOfRange, pid, lid, eid);
+    }
+}
+
+/// Fix what can be fixed in place and drop strokes that cannot be salvaged.
+/// Returns the issues that were found (and addressed).
+pub fn repair_document(doc: &mut DocumentData) -> ValidationReport {
+    let report = validate_document(doc);
+    if report.is_valid() {
+        return report;
+    }
+
+    let mut page_ids = HashSet::new();
+    let mut layer_ids = HashSet::new();
+    let mut element_ids = HashSet::new();
+    for page in &mut doc.pages {
+        if !page_ids.insert(page.id) {
+            page.id = Uuid::new_v4();
+            page_ids.insert(page.id);
+        }
+        page.background_color = sanitize_color(page.background_color);
+        if !page.template.is_valid() {
+            page.template = PageTemplate::Blank;
+        }
+        for layer in &mut page.layer_manager.layers {
+            if !layer_ids.insert(layer.id) {
+                layer.id = Uuid::new_v4();
+                layer_ids.insert(layer.id);
+            }
+            layer.opacity = if layer.opacity.is_finite() {
+                layer.opacity.clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            layer.strokes.retain(stroke_is_finite);
+            for stroke in &mut layer.strokes {
+                repair_stroke(stroke, &mut element_ids);
+            }
+            layer.shapes.retain(shape_is_finite);
+            for shape in &mut layer.shapes {
+                repair_shape(shape, &mut element_ids);
+            }
+            layer.texts.retain(text_is_finite);
+            for text in &mut layer.texts {
+                repair_text(text, &mut element_ids);
+            }
+            layer.images.retain(image_is_finite);
+            for image in &mut layer.images {
+                repair_image(image, &mut element_ids);
+            }
+            layer.fills.retain(FillRegion::is_valid);
+            for fill in &mut layer.fills {
+                repair_fill(fill, &mut element_ids);
+            }
+        }
+    }
+    if let Some(canvas) = &mut doc.infinite {
+        canvas.background_color = sanitize_color(canvas.background_color);
+        for chunk in canvas.chunks_mut() {
+            chunk.strokes.retain(stroke_is_finite);
+            for stroke in &mut chunk.strokes {
+                repair_stroke(stroke, &mut element_ids);
+            }
+        }
+        canvas.normalize();
+    }
+    report
+}
+
+fn repair_stroke(stroke: &mut Stroke, seen_ids: &mut HashSet<Uuid>) {
+    if !seen_ids.insert(stroke.id) {
+        stroke.id = Uuid::new_v4();
+        seen_ids.insert(stroke.id);
+    }
+    for seg in &mut stroke.segments {
+        seg.start_width = seg.start_width.max(0.0);
+        seg.end_width = seg.end_width.max(0.0);
+        if let Some((start, end)) = &mut seg.colors {
+            *start = sanitize_color(*start);
+            *end = sanitize_color(*end);
+        }
+    }
+    stroke.color = sanitize_color(stroke.color);
+    repair_brush(&mut stroke.brush);
+    if bounding_box_is_stale(stroke) {
+        stroke.bounding_box = BoundingBox::empty();
+        stroke.recompute_bounding_box();
+    }
+}
+
+/// Reset each unusable brush parameter to the default brush's.
+pub(crate) fn repair_brush(brush: &mut BrushConfig) {
+    let fallback = BrushConfig::default();
+    let non_negative = [
+        (&mut brush.base_width, fallback.base_width),
+        (&mut brush.min_width_factor, fallback.min_width_factor),
+        (&mut brush.max_width_factor, fallback.max_width_factor),
+        (&mut brush.pressure_sensitivity, fallback.pressure_sensitivity),
+        (&mut brush.velocity_sensitivity, fallback.velocity_sensitivity),
+        (&mut brush.tilt_sensitivity, fallback.tilt_sensitivity),
+        (&mut brush.velocity_opacity, fallback.velocity_opacity),
+        (&mut brush.color_jitter, fallback.color_jitter),
+        (&mut brush.gradient_length, fallback.gradient_length),
+        (&mut brush.taper_in, fallback.taper_in),
+        (&mut brush.taper_out, fallback.taper_out),
+    ];
+    for (value, default) in non_negative {
+        if !(value.is_finite() && *value >= 0.0) {
+            *value = default;
+        }
+    }
+    if brush.min_width_factor > brush.max_width_factor {
+        std::mem::swap(&mut brush.min_width_factor, &mut brush.max_width_factor);
+    }
+    brush.smoothing = if brush.smoothing.is_finite() {
+        brush.smoothing.clamp(0.0, 1.0)
+    } else {
+        fallback.smoothing
+    };
+    if !brush.pressure_curve.is_valid() {
+        brush.pressure_curve = PressureCurve::linear();
+    }
+    brush.opacity_curve = brush.opacity_curve.take().filter(PressureCurve::is_valid);
+    brush.color = sanitize_color(brush.color);
+    brush.gradient_color = brush.gradient_color.map(sanitize_color);
+    // `None` falls back to the default settings for the brush type.
+    brush.stamp = brush.stamp.take().filter(stamp_is_valid);
+    brush.nib = brush.nib.take().filter(nib_is_valid);
+    brush.dash = brush.dash.take().filter(dash_is_valid);
+}
+
+fn repair_shape(shape: &mut Shape, seen_ids: &mut HashSet<Uuid>) {
+    if !seen_ids.insert(shape.id) {
+        shape.id = Uuid::new_v4();
+        seen_ids.insert(shape.id);
+    }
+    shape.stroke_width = shape.stroke_width.max(0.0);
+    shape.stroke_color = shape.stroke_color.map(sanitize_color);
+    shape.fill_color = shape.fill_color.map(sanitize_color);
+}
+
+fn repair_text(text: &mut TextBox, seen_ids: &mut HashSet<Uuid>) {
+    if !seen_ids.insert(text.id) {
+        text.id = Uuid::new_v4();
+        seen_ids.insert(text.id);
+    }
+    if text.font_size <= 0.0 {
+        text.font_size = DEFAULT_FONT_SIZE;
+    }
+    text.width = text.width.filter(|w| *w >= 0.0);
+    text.color = sanitize_color(text.color);
+}
+
+fn repair_image(image: &mut Image, seen_ids: &mut HashSet<Uuid>) {
+    if !seen_ids.insert(image.id) {
+        image.id = Uuid::new_v4();
+        seen_ids.insert(image.id);
+    }
+    if image.scale <= 0.0 {
+        image.scale = 1.0;
+    }
+}
+
+fn repair_fill(fill: &mut FillRegion, seen_ids: &mut HashSet<Uuid>) {
+    if !seen_ids.insert(fill.id) {
+        fill.id = Uuid::new_v4();
+        seen_ids.insert(fill.id);
+    }
+    fill.color = sanitize_color(fill.color);
+}
+
+fn stroke_is_finite(stroke: &Stroke) -> bool {
+    let points_ok = stroke.points.iter().all(|p| {
+        p.position.x.is_finite()
+            && p.position.y.is_finite()
+            && p.pressure.is_finite()
+            && p.timestamp.is_finite()
+            && p.altitude.is_none_or(f64::is_finite)
+            && p.azimuth.is_none_or(f64::is_finite)
+    });
+    let segments_ok = stroke.segments.iter().all(|s| {
+        [s.p0, s.p1, s.p2, s.p3]
+            .iter()
+            .all(|p| p.x.is_finite() && p.y.is_finite())
+            && s.start_width.is_finite()
+            && s.end_width.is_finite()
+    });
+    points_ok && segments_ok
+}
+
+fn shape_is_finite(shape: &Shape) -> bool {
+    shape.geometry.is_valid() && shape.stroke_width.is_finite()
+}
+
+fn text_is_finite(text: &TextBox) -> bool {
+    text.position.x.is_finite()
+        && text.position.y.is_finite()
+        && text.font_size.is_finite()
+        && text.width.is_none_or(f64::is_finite)
+}
+
+/// Finite placement and a crop with some area; the scale may still be non-positive.
+fn image_is_finite(image: &Image) -> bool {
+    let crop = &image.crop;
+    [image.center.x, image.center.y, image.rotation, image.scale, crop.x, crop.y]
+        .iter()
+        .all(|v| v.is_finite())
+        && [crop.width, crop.height]
+            .iter()
+            .all(|v| v.is_finite() && *v > 0.0)
+}
+
+fn stroke_has_negative_width(stroke: &Stroke) -> bool {
+    stroke
+        .segments
+        .iter()
+        .any(|s| s.start_width < 0.0 || s.end_width < 0.0)
+}
+
+/// Whether a brush stored in a document or imported in a preset pack can be drawn
+/// with.
+pub(crate) fn brush_is_valid(brush: &BrushConfig) -> bool {
+    let non_negative = [
+        brush.base_width,
+        brush.min_width_factor,
+        brush.max_width_factor,
+        brush.pressure_sensitivity,
+        brush.velocity_sensitivity,
+        brush.tilt_sensitivity,
+        brush.velocity_opacity,
+        brush.color_jitter,
+        brush.gradient_length,
+        brush.taper_in,
+        brush.taper_out,
+    ];
+    non_negative.iter().all(|v| v.is_finite() && *v >= 0.0)
+        && brush.min_width_factor <= brush.max_width_factor
+        && (0.0..=1.0).contains(&brush.smoothing)
+        && brush.pressure_curve.is_valid()
+        && brush.opacity_curve.as_ref().is_none_or(PressureCurve::is_valid)
+        && color_in_range(&brush.color)
+        && brush.gradient_color.as_ref().is_none_or(color_in_range)
+        && brush.stamp.as_ref().is_none_or(stamp_is_valid)
+        && brush.nib.as_ref().is_none_or(nib_is_valid)
+        && brush.dash.as_ref().is_none_or(dash_is_valid)
+}
+
+fn stamp_is_valid(stamp: &StampSettings) -> bool {
+    let tip_ok = match &stamp.tip {
+        StampTip::Round { hardness } => (0.0..=1.0).contains(hardness),
+        StampTip::Grain { density } => (0.0..=1.0).contains(density),
+        StampTip::Square | StampTip::Mask { .. } => true,
+    };
+    tip_ok
+        && stamp.spacing.is_finite()
+        && stamp.spacing > 0.0
+        && [stamp.size_jitter, stamp.rotation_jitter, stamp.scatter]
+            .iter()
+            .all(|v| v.is_finite() && *v >= 0.0)
+        && (0.0..=1.0).contains(&stamp.flow)
+}
+
+fn nib_is_valid(nib: &NibSettings) -> bool {
+    nib.angle.is_finite() && (0.0..=1.0).contains(&nib.thickness)
+}
+
+fn dash_is_valid(dash: &DashPattern) -> bool {
+    dash.offset.is_finite() && DashPattern::new(dash.intervals.clone()).is_ok()
+}
+
+fn bounding_box_is_stale(stroke: &Stroke) -> bool {
+    let mut fresh = stroke.clone();
+    fresh.bounding_box = BoundingBox::empty();
+    fresh.recompute_bounding_box();
+    let (a, b) = (&stroke.bounding_box, &fresh.bounding_box);
+    if !b.is_valid() {
+        return a.is_valid();
+    }
+    [
+        a.min_x - b.min_x,
+        a.min_y - b.min_y,
+        a.max_x - b.max_x,
+        a.max_y - b.max_y,
+    ]
+    .iter()
+    .any(|d| d.is_nan() || d.abs() > BOUNDING_BOX_EPSILON)
+}
+
+/// Every color a stroke draws with: its own and per-segment ones. Brush colors are
+/// checked with the rest of the brush.
+fn stroke_colors(stroke: &Stroke) -> impl Iterator<Item = Color> + '_ {
+    let segment_colors = stroke
+        .segments
+        .iter()
+        .filter_map(|s| s.colors)
+        .flat_map(|(start, end)| [start, end]);
+    std::iter::once(stroke.color).chain(segment_colors)
+}
+
+fn shape_colors(shape: &Shape) -> impl Iterator<Item = Color> {
+    shape.stroke_color.into_iter().chain(shape.fill_color)
+}
+
+fn color_in_range(c: &Color) -> bool {
+    [c.r, c.g, c.b, c.a]
+        .iter()
+        .all(|v| (0.0..=1.0).contains(v))
+}
+
+/// Clamp each component into `0.0..=1.0`; non-finite ones fall back to opaque black.
+pub(crate) fn sanitize_color(c: Color) -> Color {
+    let fix = |v: f32, fallback: f32| {
+        if v.is_finite() {
+            v.clamp(0.0, 1.0)
+        } else {
+            fallback
+        }
+    };
+    Color::new(fix(c.r, 0.0), fix(c.g, 0.0), fix(c.b, 0.0), fix(c.a, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brush::BrushConfig;
+    use crate::infinite::{ChunkCoord, InfiniteCanvas};
+    use crate::layer::Layer;
+    use crate::page::Page;
+    use crate::point::StrokePoint;
+    use crate::serialization::CURRENT_VERSION;
+    use crate::stroke::StrokeBuilder;
+
+    fn make_stroke() -> Stroke {
+        let mut builder = StrokeBuilder::new(BrushConfig::pen(Color::black(), 2.0));
+        for i in 0..5 {
+            let t = i as f64;
+            builder.add_point(StrokePoint::new(t * 10.0, t * 5.0, 0.5, t * 0.016));
+        }
+        builder.finish()
+    }
+
+    fn make_doc(strokes: Vec<Stroke>) -> DocumentData {
+        let mut layer = Layer::new("Layer 1");
+        layer.strokes = strokes;
+        let mut page = Page::new(100.0, 100.0);
+        page.layer_manager.layers = vec![layer];
+        DocumentData {
+            version: CURRENT_VERSION,
+            pages: vec![page],
+            current_page_index: 0,
+            infinite: None,
+        }
+    }
+
+    fn kinds(report: &ValidationReport) -> Vec<IssueKind> {
+        report.issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_valid_document() {
+        let doc = make_doc(vec![make_stroke(), make_stroke()]);
+        assert!(validate_document(&doc).is_valid());
+    }
+
+    #[test]
+    fn test_detects_and_drops_non_finite_stroke() {
+        let mut bad = make_stroke();
+        bad.segments[0].p1.x = f64::NAN;
+        let mut doc = make_doc(vec![make_stroke(), bad]);
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::NonFiniteGeometry]);
+
+        repair_document(&mut doc);
+        assert_eq!(doc.pages[0].layer_manager.layers[0].strokes.len(), 1);
+        assert!(validate_document(&doc).is_valid());
+    }
+
+    #[test]
+    fn test_repairs_stale_bounding_box() {
+        let mut stroke = make_stroke();
+        let expected = stroke.bounding_box;
+        stroke.bounding_box.max_x += 500.0;
+        let mut doc = make_doc(vec![stroke]);
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::StaleBoundingBox]);
+
+        repair_document(&mut doc);
+        let bb = doc.pages[0].layer_manager.layers[0].strokes[0].bounding_box;
+        assert!((bb.max_x - expected.max_x).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dedupes_stroke_ids() {
+        let a = make_stroke();
+        let b = make_stroke().with_id(a.id);
+        let mut doc = make_doc(vec![a, b]);
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::DuplicateElementId]);
+
+        repair_document(&mut doc);
+        let strokes = &doc.pages[0].layer_manager.layers[0].strokes;
+        assert_ne!(strokes[0].id, strokes[1].id);
+        assert!(validate_document(&doc).is_valid());
+    }
+
+    #[test]
+    fn test_repairs_width_and_color() {
+        let mut stroke = make_stroke();
+        stroke.segments[0].start_width = -3.0;
+        stroke.color = Color::new(2.0, 0.0, -1.0, 1.0);
+        let mut doc = make_doc(vec![stroke]);
+        let found = kinds(&validate_document(&doc));
+        assert!(found.contains(&IssueKind::NegativeWidth));
+        assert!(found.contains(&IssueKind::ColorOutOfRange));
+
+        repair_document(&mut doc);
+        let stroke = &doc.pages[0].layer_manager.layers[0].strokes[0];
+        assert!(stroke.segments[0].start_width >= 0.0);
+        assert_eq!(stroke.color, Color::new(1.0, 0.0, 0.0, 1.0));
+        assert!(validate_document(&doc).is_valid());
+    }
+
+    #[test]
+    fn test_repairs_brush_parameters() {
+        use crate::stamp::StampSettings;
+
+        let mut stroke = make_stroke();
+        stroke.brush.base_width = f64::NAN;
+        stroke.brush.pressure_sensitivity = -1.0;
+        (stroke.brush.min_width_factor, stroke.brush.max_width_factor) = (2.0, 0.5);
+        stroke.brush.color = Color::new(0.0, 0.0, 3.0, 1.0);
+        stroke.brush.stamp = Some(StampSettings {
+            spacing: 0.0,
+            ..StampSettings::default()
+        });
+        let mut doc = make_doc(vec![stroke]);
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::InvalidBrush]);
+
+        repair_document(&mut doc);
+        assert!(validate_document(&doc).is_valid());
+        let brush = &doc.pages[0].layer_manager.layers[0].strokes[0].brush;
+        let fallback = BrushConfig::default();
+        assert_eq!(brush.base_width, fallback.base_width);
+        assert_eq!(brush.pressure_sensitivity, fallback.pressure_sensitivity);
+        assert_eq!((brush.min_width_factor, brush.max_width_factor), (0.5, 2.0));
+        assert_eq!(brush.color, Color::new(0.0, 0.0, 1.0, 1.0));
+        assert!(brush.stamp.is_none());
+    }
+
+    #[test]
+    fn test_loads_and_repairs_bad_pressure_curves() {
+        let mut stroke = make_stroke();
+        stroke.brush.pressure_curve =
+            serde_json::from_str(r#"[{"x":0.8,"y":0.0},{"x":0.2,"y":1.0}]"#).unwrap();
+        stroke.brush.opacity_curve = Some(serde_json::from_str("[]").unwrap());
+        let json = make_doc(vec![stroke]).save_to_json().unwrap();
+
+        let mut doc = DocumentData::load_from_json(&json).unwrap();
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::InvalidBrush]);
+        repair_document(&mut doc);
+        let brush = &doc.pages[0].layer_manager.layers[0].strokes[0].brush;
+        assert!(brush.pressure_curve.is_linear());
+        assert!(brush.opacity_curve.is_none());
+    }
+
+    #[test]
+    fn test_repairs_segment_color() {
+        let mut stroke = make_stroke();
+        stroke.segments[1].colors = Some((Color::black(), Color::new(0.0, 0.0, 0.0, f32::NAN)));
+        let mut doc = make_doc(vec![stroke]);
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::ColorOutOfRange]);
+
+        repair_document(&mut doc);
+        let stroke = &doc.pages[0].layer_manager.layers[0].strokes[0];
+        assert_eq!(stroke.segments[1].colors.unwrap().1, Color::black());
+    }
+
+    #[test]
+    fn test_resets_invalid_template() {
+        let mut doc = make_doc(vec![]);
+        doc.pages[0].template = PageTemplate::grid(-1.0);
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::InvalidTemplate]);
+        repair_document(&mut doc);
+        assert_eq!(doc.pages[0].template, PageTemplate::Blank);
+    }
+
+    #[test]
+    fn test_dedupes_stroke_ids_across_pages() {
+        let stroke = make_stroke();
+        let mut doc = make_doc(vec![stroke.clone()]);
+        let mut second = Page::new(100.0, 100.0);
+        second.layer_manager.active_layer_mut().add_stroke(stroke);
+        doc.pages.push(second);
+        let report = validate_document(&doc);
+        assert_eq!(kinds(&report), vec![IssueKind::DuplicateElementId]);
+        assert_eq!(report.issues[0].page_id, Some(doc.pages[1].id));
+
+        repair_document(&mut doc);
+        assert!(validate_document(&doc).is_valid());
+    }
+
+    #[test]
+    fn test_drops_non_finite_chunk_stroke() {
+        let mut bad = make_stroke();
+        bad.points[0].pressure = f64::INFINITY;
+        let mut canvas = InfiniteCanvas::new();
+        canvas.add_stroke(ChunkCoord::new(2, -1), make_stroke());
+        canvas.add_stroke(ChunkCoord::new(9, 9), bad);
+        let mut doc = make_doc(vec![]);
+        doc.infinite = Some(canvas);
+        let report = validate_document(&doc);
+        assert_eq!(kinds(&report), vec![IssueKind::NonFiniteGeometry]);
+        assert_eq!(report.issues[0].page_id, None);
+
+        repair_document(&mut doc);
+        let canvas = doc.infinite.as_ref().unwrap();
+        assert_eq!(canvas.stroke_count(), 1);
+        assert!(canvas.chunk(ChunkCoord::new(9, 9)).is_none());
+    }
+
+    #[test]
+    fn test_repairs_and_drops_shapes() {
+        use crate::point::Point;
+        use crate::shape::{Shape, ShapeGeometry};
+
+        let line = ShapeGeometry::Line {
+            start: Point::new(0.0, 0.0),
+            end: Point::new(10.0, 0.0),
+        };
+        let mut faded = Shape::new(line.clone(), Color::new(2.0, 0.0, 0.0, 1.0), -1.0);
+        faded.fill_color = Some(Color::white());
+        let broken = Shape::new(
+            ShapeGeometry::Line {
+                start: Point::new(f64::NAN, 0.0),
+                end: Point::new(10.0, 0.0),
+            },
+            Color::black(),
+            1.0,
+        );
+        let mut doc = make_doc(vec![]);
+        let layer = doc.pages[0].layer_manager.active_layer_mut();
+        layer.add_shape(faded);
+        layer.add_shape(broken);
+        assert_eq!(
+            kinds(&validate_document(&doc)),
+            vec![
+                IssueKind::NegativeWidth,
+                IssueKind::ColorOutOfRange,
+                IssueKind::NonFiniteGeometry,
+            ]
+        );
+
+        repair_document(&mut doc);
+        assert!(validate_document(&doc).is_valid());
+        let shapes = &doc.pages[0].layer_manager.layers[0].shapes;
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].stroke_width, 0.0);
+        assert_eq!(shapes[0].stroke_color, Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_repairs_text_sizes() {
+        use crate::point::Point;
+
+        let mut text = TextBox::new(Point::new(0.0, 0.0), Some(-5.0), "label");
+        text.font_size = 0.0;
+        let mut doc = make_doc(vec![]);
+        doc.pages[0].layer_manager.active_layer_mut().add_text(text);
+        assert_eq!(kinds(&validate_document(&doc)), vec![IssueKind::NegativeWidth]);
+
+        repair_document(&mut doc);
+        let text = &doc.pages[0].layer_manager.layers[0].texts[0];
+        assert_eq!((text.font_size, text.width), (DEFAULT_FONT_SIZE, None));
+    }
+
+    #[test]
+    fn test_repairs_image_scale_and_drops_empty_crop() {
+        use crate::image::CropRect;
+        use crate::point::Point;
+
+        let image = Image {
+            id: Uuid::new_v4(),
+            image_id: Uuid::new_v4(),
+            center: Point::new(50.0, 50.0),
+            scale: -2.0,
+            rotation: 0.0,
+            crop: CropRect {
+                x: 0.0,
+                y: 0.0,
+                width: 20.0,
+                height: 10.0,
+            },
+        };
+        let mut empty = image.clone();
+        empty.id = Uuid::new_v4();
+        empty.crop.height = 0.0;
+        let mut doc = make_doc(vec![]);
+        let layer = doc.pages[0].layer_manager.active_layer_mut();
+        layer.add_image(image);
+        layer.add_image(empty);
+        assert_eq!(
+            kinds(&validate_document(&doc)),
+            vec![IssueKind::NegativeWidth, IssueKind::NonFiniteGeometry]
+        );
+
+        repair_document(&mut doc);
+        assert!(validate_document(&doc).is_valid());
+        let images = &doc.pages[0].layer_manager.layers[0].images;
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].scale, 1.0);
+    }
+
+    #[test]
+    fn test_repairs_fill_colors_and_drops_degenerate_fills() {
+        use crate::point::Point;
+
+        let triangle = vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(0.0, 10.0),
+        ];
+        let bright = FillRegion::new(vec![triangle.clone()], Color::new(0.5, 1.5, 0.0, 1.0));
+        let sliver = FillRegion::new(vec![triangle[..2].to_vec()], Color::black());
+        let mut doc = make_doc(vec![]);
+        let layer = doc.pages[0].layer_manager.active_layer_mut();
+        layer.add_fill(bright);
+        layer.add_fill(sliver);
+        assert_eq!(
+            kinds(&validate_document(&doc)),
+            vec![IssueKind::ColorOutOfRange, IssueKind::NonFiniteGeometry]
+        );
+
+        repair_document(&mut doc);
+        let fills = &doc.pages[0].layer_manager.layers[0].fills;
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].color, Color::new(0.5, 1.0, 0.0, 1.0));
+    }
+}
@@ -10,3 +10,34 @@
 //     fn save_state(&self);
 //     fn restore_state(&self);
 // }
+
+use drawengine_core::text::{FontMetrics, TextMeasure};
+
+use crate::types::FfiFontMetrics;
+
+/// Measures text with the fonts the host draws with (e.g. Core Text), so the engine
+/// wraps text boxes where the native renderer will. Called while the engine is locked,
+/// so implementations must not call back into the engine.
+#[uniffi::export(callback_interface)]
+pub trait TextMeasurer: Send + Sync {
+    /// Advance of `text` set in `font_family` at `font_size`, in page units.
+    fn advance(&self, text: String, font_family: String, font_size: f64) -> f64;
+
+    fn font_metrics(&self, font_family: String, font_size: f64) -> FfiFontMetrics;
+}
+
+/// Adapts a host `TextMeasurer` to the engine's `TextMeasure`.
+pub(crate) struct HostTextMeasure(pub(crate) Box<dyn TextMeasurer>);
+
+impl TextMeasure for HostTextMeasure {
+    fn advance(&self, text: &str, font_family: &str, font_size: f64) -> f64 {
+        self.0
+            .advance(text.to_string(), font_family.to_string(), font_size)
+    }
+
+    fn font_metrics(&self, font_family: &str, font_size: f64) -> FontMetrics {
+        self.0
+            .font_metrics(font_family.to_string(), font_size)
+            .into()
+    }
+}
@@ -1,9 +1,17 @@
-use std::sync::RwLock;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use drawengine_core::canvas::DrawEngine;
+use drawengine_core::package::PackageReader;
+use drawengine_core::preset::BrushPreset;
+use uuid::Uuid;
 
+use crate::callbacks::{HostTextMeasure, TextMeasurer};
 use crate::types::{
-    convert_render_command, DrawEngineError, FfiBrushConfig, FfiEngineState, FfiRenderCommand,
+    convert_render_command, image_from_ffi, pressure_curve, shape_from_ffi, text_from_ffi,
+    DrawEngineError, FfiBrushConfig, FfiBrushPreset, FfiColor, FfiDocumentMetadata,
+    FfiEngineState, FfiFillRegion, FfiImage, FfiImageInfo, FfiPageInfo, FfiPageLayout,
+    FfiPageTemplate, FfiPoint, FfiRecoveryReport, FfiRect, FfiRenderCommand, FfiShape,
+    FfiShapeSnapping, FfiStrokeInput, FfiTextBox, FfiValidationIssue,
 };
 
 /// Thread-safe FFI facade over DrawEngine.
@@ -22,11 +30,125 @@ impl DrawEngineFFI {
         }
     }
 
+    /// Engine for an unbounded whiteboard; `view_width`/`view_height` are the screen size.
+    #[uniffi::constructor]
+    pub fn new_infinite(view_width: f64, view_height: f64) -> Self {
+        Self {
+            inner: RwLock::new(DrawEngine::new_infinite(view_width, view_height)),
+        }
+    }
+
     // --- Brush ---
 
-    pub fn set_brush(&self, config: FfiBrushConfig) {
-        let mut engine = self.inner.write().unwrap();
-        engine.set_brush(config.into());
+    /// Fails only if a curve in `config` is malformed.
+    pub fn set_brush(&self, config: FfiBrushConfig) -> Result<(), DrawEngineError> {
+        let brush = config.try_into()?;
+        let mut engine = self.write()?;
+        engine.set_brush(brush);
+        Ok(())
+    }
+
+    /// The current brush with every parameter filled in, for settings UIs.
+    pub fn get_brush(&self) -> FfiBrushConfig {
+        let engine = self.read_or_recover();
+        engine.current_brush().into()
+    }
+
+    /// All presets in library order.
+    pub fn list_brush_presets(&self) -> Vec<FfiBrushPreset> {
+        let engine = self.read_or_recover();
+        engine.brush_presets().presets().iter().map(Into::into).collect()
+    }
+
+    /// Save `brush` as a new preset. Returns the preset id.
+    pub fn add_brush_preset(
+        &self,
+        name: String,
+        brush: FfiBrushConfig,
+    ) -> Result<String, DrawEngineError> {
+        let preset = BrushPreset::new(name, brush.try_into()?);
+        let id = preset.id.to_string();
+        let mut engine = self.write()?;
+        engine.brush_presets_mut().add(preset);
+        Ok(id)
+    }
+
+    pub fn remove_brush_preset(&self, preset_id: String) -> Result<(), DrawEngineError> {
+        let id = parse_preset_id(preset_id)?;
+        let mut engine = self.write()?;
+        engine.brush_presets_mut().remove(id)?;
+        Ok(())
+    }
+
+    pub fn apply_brush_preset(&self, preset_id: String) -> Result<(), DrawEngineError> {
+        let id = parse_preset_id(preset_id)?;
+        let mut engine = self.write()?;
+        engine.apply_brush_preset(id).map_err(DrawEngineError::from)
+    }
+
+    /// Export presets as a JSON pack; an empty list exports the whole library.
+    pub fn export_brush_presets(&self, preset_ids: Vec<String>) -> Result<String, DrawEngineError> {
+        let ids = preset_ids
+            .into_iter()
+            .map(parse_preset_id)
+            .collect::<Result<Vec<_>, _>>()?;
+        let engine = self.read()?;
+        Ok(engine.brush_presets().export_pack(&ids)?)
+    }
+
+    /// Merge a JSON preset pack. Returns how many presets were added or updated.
+    pub fn import_brush_presets(&self, json: String) -> Result<u32, DrawEngineError> {
+        let mut engine = self.write()?;
+        Ok(engine.brush_presets_mut().import_pack(&json)? as u32)
+    }
+
+    /// Lazy-rope stabilizer length in canvas units; 0 turns it off.
+    pub fn set_stabilizer(&self, rope_length: f64) {
+        let mut engine = self.write_or_recover();
+        engine.set_stabilizer(rope_length);
+    }
+
+    /// Replace finished strokes that look like shapes with clean geometry; one undo
+    /// restores the stroke as drawn.
+    pub fn set_shape_snapping(&self, snapping: FfiShapeSnapping) {
+        let mut engine = self.write_or_recover();
+        engine.set_shape_snapping(snapping.into());
+    }
+
+    pub fn get_shape_snapping(&self) -> FfiShapeSnapping {
+        let engine = self.read_or_recover();
+        engine.shape_snapping().into()
+    }
+
+    /// Pen prediction in milliseconds (capped at 50); 0 turns it off.
+    pub fn set_prediction_lookahead(&self, milliseconds: f64) {
+        let mut engine = self.write_or_recover();
+        engine.set_prediction_lookahead(milliseconds / 1000.0);
+    }
+
+    /// Control points of the device pressure curve (x = raw pressure, y = normalized).
+    pub fn set_device_pressure_curve(&self, points: Vec<FfiPoint>) -> Result<(), DrawEngineError> {
+        let curve = pressure_curve(points)?;
+        let mut engine = self.write()?;
+        engine.set_device_pressure_curve(curve);
+        Ok(())
+    }
+
+    pub fn get_device_pressure_curve(&self) -> Vec<FfiPoint> {
+        let engine = self.read_or_recover();
+        engine
+            .device_pressure_curve()
+            .points()
+            .iter()
+            .map(|&p| p.into())
+            .collect()
+    }
+
+    /// Fit the device pressure curve to raw pressures sampled from a few strokes.
+    /// Returns false if there were too few distinct samples.
+    pub fn calibrate_device_pressure(&self, samples: Vec<f64>) -> bool {
+        let mut engine = self.write_or_recover();
+        engine.calibrate_device_pressure(&samples)
     }
 
     // --- Drawing ---
@@ -38,7 +160,7 @@ impl DrawEngineFFI {
         pressure: f64,
         timestamp: f64,
     ) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine
             .begin_stroke(x, y, pressure, timestamp)
             .into_iter()
@@ -53,7 +175,7 @@ impl DrawEngineFFI {
         pressure: f64,
         timestamp: f64,
     ) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine
             .add_point(x, y, pressure, timestamp)
             .into_iter()
@@ -61,8 +183,29 @@ impl DrawEngineFFI {
             .collect()
     }
 
+    /// Begin a stroke from a full input sample, keeping stylus tilt and pointer type.
+    pub fn begin_stroke_input(&self, input: FfiStrokeInput) -> Vec<FfiRenderCommand> {
+        let mut engine = self.write_or_recover();
+        engine
+            .begin_stroke_input(input.into())
+            .into_iter()
+            .map(convert_render_command)
+            .collect()
+    }
+
+    /// Add coalesced samples in one call; returns a single merged render delta.
+    pub fn add_points(&self, inputs: Vec<FfiStrokeInput>) -> Vec<FfiRenderCommand> {
+        let points: Vec<_> = inputs.into_iter().map(Into::into).collect();
+        let mut engine = self.write_or_recover();
+        engine
+            .add_points(&points)
+            .into_iter()
+            .map(convert_render_command)
+            .collect()
+    }
+
     pub fn end_stroke(&self) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine
             .end_stroke()
             .into_iter()
@@ -73,19 +216,19 @@ impl DrawEngineFFI {
     // --- Undo/Redo ---
 
     pub fn undo(&self) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine.undo().into_iter().map(convert_render_command).collect()
     }
 
     pub fn redo(&self) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine.redo().into_iter().map(convert_render_command).collect()
     }
 
     // --- Viewport ---
 
     pub fn zoom(&self, factor: f64, focal_x: f64, focal_y: f64) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine
             .zoom(factor, focal_x, focal_y)
             .into_iter()
@@ -94,7 +237,7 @@ impl DrawEngineFFI {
     }
 
     pub fn pan(&self, dx: f64, dy: f64) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine
             .pan(dx, dy)
             .into_iter()
@@ -103,7 +246,7 @@ impl DrawEngineFFI {
     }
 
     pub fn reset_viewport(&self) -> Vec<FfiRenderCommand> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write_or_recover();
         engine
             .reset_viewport()
             .into_iter()
@@ -111,10 +254,19 @@ impl DrawEngineFFI {
             .collect()
     }
 
+    pub fn set_view_size(&self, width: f64, height: f64) -> Vec<FfiRenderCommand> {
+        let mut engine = self.write_or_recover();
+        engine
+            .set_view_size(width, height)
+            .into_iter()
+            .map(convert_render_command)
+            .collect()
+    }
+
     // --- Render ---
 
     pub fn full_render(&self) -> Vec<FfiRenderCommand> {
-        let engine = self.inner.read().unwrap();
+        let engine = self.read_or_recover();
         engine
             .full_render()
             .into_iter()
@@ -122,10 +274,19 @@ impl DrawEngineFFI {
             .collect()
     }
 
+    pub fn thumbnail_render(&self, max_width: f64, max_height: f64) -> Vec<FfiRenderCommand> {
+        let engine = self.read_or_recover();
+        engine
+            .thumbnail_render(max_width, max_height)
+            .into_iter()
+            .map(convert_render_command)
+            .collect()
+    }
+
     // --- State ---
 
     pub fn get_state(&self) -> FfiEngineState {
-        let engine = self.inner.read().unwrap();
+        let engine = self.read_or_recover();
         let (offset_x, offset_y) = engine.get_offset();
         FfiEngineState {
             stroke_count: engine.stroke_count() as u32,
@@ -135,18 +296,476 @@ impl DrawEngineFFI {
             offset_x,
             offset_y,
             active_layer_id: engine.active_layer_id().to_string(),
+            current_page_id: engine.current_page().id.to_string(),
+            page_count: engine.page_count() as u32,
+            canvas_anchor: engine.canvas_anchor().map(Into::into),
         }
     }
 
+    // --- Pages ---
+
+    pub fn get_pages(&self) -> Vec<FfiPageInfo> {
+        let engine = self.read_or_recover();
+        engine.pages.iter().map(Into::into).collect()
+    }
+
+    pub fn set_current_page(&self, page_id: String) -> Result<(), DrawEngineError> {
+        let id = parse_page_id(page_id)?;
+        let mut engine = self.write()?;
+        engine.set_current_page(id).map_err(DrawEngineError::from)
+    }
+
+    /// Append a blank page. Returns the new page id.
+    pub fn add_page(&self, width: f64, height: f64) -> Result<String, DrawEngineError> {
+        let mut engine = self.write()?;
+        Ok(engine.add_page(width, height)?.to_string())
+    }
+
+    /// Insert a copy after the given page. Returns the copy's id.
+    pub fn duplicate_page(&self, page_id: String) -> Result<String, DrawEngineError> {
+        let id = parse_page_id(page_id)?;
+        let mut engine = self.write()?;
+        Ok(engine.duplicate_page(id)?.to_string())
+    }
+
+    pub fn remove_page(&self, page_id: String) -> Result<(), DrawEngineError> {
+        let id = parse_page_id(page_id)?;
+        let mut engine = self.write()?;
+        engine.remove_page(id).map_err(DrawEngineError::from)
+    }
+
+    pub fn move_page(&self, page_id: String, to_index: u32) -> Result<(), DrawEngineError> {
+        let id = parse_page_id(page_id)?;
+        let mut engine = self.write()?;
+        engine
+            .move_page(id, to_index as usize)
+            .map_err(DrawEngineError::from)
+    }
+
+    pub fn set_page_background(
+        &self,
+        page_id: String,
+        color: FfiColor,
+        template: FfiPageTemplate,
+    ) -> Result<Vec<FfiRenderCommand>, DrawEngineError> {
+        let id = parse_page_id(page_id)?;
+        let mut engine = self.write()?;
+        Ok(engine
+            .set_page_background(id, color.into(), template.into())?
+            .into_iter()
+            .map(convert_render_command)
+            .collect())
+    }
+
+    pub fn set_page_layout(&self, layout: FfiPageLayout) -> Vec<FfiRenderCommand> {
+        let mut engine = self.write_or_recover();
+        engine
+            .set_page_layout(layout.into())
+            .into_iter()
+            .map(convert_render_command)
+            .collect()
+    }
+
+    // --- Shapes ---
+
+    /// Page-local coordinates on the current page of a screen point; shapes use these.
+    pub fn screen_to_page(&self, screen_x: f64, screen_y: f64) -> FfiPoint {
+        let engine = self.read_or_recover();
+        engine.screen_to_page(screen_x, screen_y).into()
+    }
+
+    /// Add a shape to the active layer of the current page. Returns its new id.
+    pub fn add_shape(&self, shape: FfiShape) -> Result<String, DrawEngineError> {
+        let mut engine = self.write()?;
+        let id = engine.add_shape(shape_from_ffi(shape, Uuid::new_v4()))?;
+        Ok(id.to_string())
+    }
+
+    /// Replace the shape with the same id, e.g. to change its colors.
+    pub fn update_shape(&self, shape: FfiShape) -> Result<(), DrawEngineError> {
+        let id = parse_shape_id(shape.id.clone())?;
+        let mut engine = self.write()?;
+        engine
+            .update_shape(shape_from_ffi(shape, id))
+            .map_err(DrawEngineError::from)
+    }
+
+    pub fn remove_shape(&self, shape_id: String) -> Result<(), DrawEngineError> {
+        let id = parse_shape_id(shape_id)?;
+        let mut engine = self.write()?;
+        engine.remove_shape(id).map_err(DrawEngineError::from)
+    }
+
+    pub fn get_shape(&self, shape_id: String) -> Option<FfiShape> {
+        let id = Uuid::parse_str(&shape_id).ok()?;
+        let engine = self.read_or_recover();
+        engine.shape(id).map(Into::into)
+    }
+
+    /// Topmost shape within `tolerance` of a page-local point, for selection.
+    pub fn shape_at(&self, x: f64, y: f64, tolerance: f64) -> Option<String> {
+        let engine = self.read_or_recover();
+        engine
+            .shape_at(FfiPoint { x, y }.into(), tolerance)
+            .map(|id| id.to_string())
+    }
+
+    /// Edit handles in page-local coordinates, indexed as `move_shape_handle` expects.
+    pub fn get_shape_handles(&self, shape_id: String) -> Result<Vec<FfiPoint>, DrawEngineError> {
+        let id = parse_shape_id(shape_id.clone())?;
+        let engine = self.read()?;
+        let shape = engine
+            .shape(id)
+            .ok_or(DrawEngineError::ShapeNotFound { shape_id })?;
+        Ok(shape.geometry.handles().into_iter().map(Into::into).collect())
+    }
+
+    /// Drag a handle to a page-local point; call `finish_shape_edit` when the drag ends
+    /// so it becomes one undo step.
+    pub fn move_shape_handle(
+        &self,
+        shape_id: String,
+        handle: u32,
+        x: f64,
+        y: f64,
+    ) -> Result<Vec<FfiRenderCommand>, DrawEngineError> {
+        let id = parse_shape_id(shape_id)?;
+        let mut engine = self.write()?;
+        Ok(engine
+            .move_shape_handle(id, handle as usize, FfiPoint { x, y }.into())?
+            .into_iter()
+            .map(convert_render_command)
+            .collect())
+    }
+
+    pub fn finish_shape_edit(&self) {
+        let mut engine = self.write_or_recover();
+        engine.finish_shape_edit();
+    }
+
+    // --- Text ---
+
+    /// Add a text box to the active layer of the current page. Returns its new id.
+    pub fn add_text(&self, text: FfiTextBox) -> Result<String, DrawEngineError> {
+        let mut engine = self.write()?;
+        let id = engine.add_text(text_from_ffi(text, Uuid::new_v4()))?;
+        Ok(id.to_string())
+    }
+
+    /// Replace the text box with the same id, e.g. after typing or moving it.
+    pub fn update_text(&self, text: FfiTextBox) -> Result<(), DrawEngineError> {
+        let id = parse_text_id(text.id.clone())?;
+        let mut engine = self.write()?;
+        engine
+            .update_text(text_from_ffi(text, id))
+            .map_err(DrawEngineError::from)
+    }
+
+    pub fn remove_text(&self, text_id: String) -> Result<(), DrawEngineError> {
+        let id = parse_text_id(text_id)?;
+        let mut engine = self.write()?;
+        engine.remove_text(id).map_err(DrawEngineError::from)
+    }
+
+    pub fn get_text(&self, text_id: String) -> Option<FfiTextBox> {
+        let id = Uuid::parse_str(&text_id).ok()?;
+        let engine = self.read_or_recover();
+        engine.text(id).map(Into::into)
+    }
+
+    /// Topmost text box within `tolerance` of a page-local point, for selection.
+    pub fn text_at(&self, x: f64, y: f64, tolerance: f64) -> Option<String> {
+        let engine = self.read_or_recover();
+        engine
+            .text_at(FfiPoint { x, y }.into(), tolerance)
+            .map(|id| id.to_string())
+    }
+
+    /// Page-local frame of a laid-out text box, for drawing its selection.
+    pub fn get_text_bounds(&self, text_id: String) -> Result<FfiRect, DrawEngineError> {
+        let id = parse_text_id(text_id)?;
+        let engine = self.read()?;
+        Ok(engine.text_bounds(id)?.into())
+    }
+
+    /// Wrap text with the host's font measurements instead of the engine's estimates.
+    /// Returns a full render with the new layout.
+    pub fn set_text_measurer(&self, measurer: Box<dyn TextMeasurer>) -> Vec<FfiRenderCommand> {
+        let mut engine = self.write_or_recover();
+        engine
+            .set_text_measure(Arc::new(HostTextMeasure(measurer)))
+            .into_iter()
+            .map(convert_render_command)
+            .collect()
+    }
+
+    // --- Images ---
+
+    /// Store PNG or JPEG bytes for placing with `add_image`. `save_package` and the
+    /// journal keep them; `save` doesn't.
+    pub fn import_image(&self, bytes: Vec<u8>) -> Result<FfiImageInfo, DrawEngineError> {
+        let mut engine = self.write()?;
+        Ok(engine.import_image(bytes)?.into())
+    }
+
+    pub fn get_image_info(&self, image_id: String) -> Option<FfiImageInfo> {
+        let id = Uuid::parse_str(&image_id).ok()?;
+        let engine = self.read_or_recover();
+        engine.image_data(id).map(Into::into)
+    }
+
+    /// Encoded bytes for a `DrawImage` command's `image_id`; decode once and cache by id.
+    pub fn get_image_bytes(&self, image_id: String) -> Option<Vec<u8>> {
+        let id = Uuid::parse_str(&image_id).ok()?;
+        let engine = self.read_or_recover();
+        engine.image_data(id).map(|d| d.bytes.clone())
+    }
+
+    /// Place an imported image on the active layer of the current page. Returns the
+    /// placement's new id.
+    pub fn add_image(&self, image: FfiImage) -> Result<String, DrawEngineError> {
+        let image_id = parse_image_id(image.image_id.clone())?;
+        let mut engine = self.write()?;
+        let id = engine.add_image(image_from_ffi(image, Uuid::new_v4(), image_id))?;
+        Ok(id.to_string())
+    }
+
+    /// Replace the placement with the same id, e.g. after moving, scaling, rotating or
+    /// cropping it.
+    pub fn update_image(&self, image: FfiImage) -> Result<(), DrawEngineError> {
+        let id = parse_image_id(image.id.clone())?;
+        let image_id = parse_image_id(image.image_id.clone())?;
+        let mut engine = self.write()?;
+        engine
+            .update_image(image_from_ffi(image, id, image_id))
+            .map_err(DrawEngineError::from)
+    }
+
+    pub fn remove_image(&self, id: String) -> Result<(), DrawEngineError> {
+        let id = parse_image_id(id)?;
+        let mut engine = self.write()?;
+        engine.remove_image(id).map_err(DrawEngineError::from)
+    }
+
+    pub fn get_image(&self, id: String) -> Option<FfiImage> {
+        let id = Uuid::parse_str(&id).ok()?;
+        let engine = self.read_or_recover();
+        engine.image(id).map(Into::into)
+    }
+
+    /// Topmost placed image within `tolerance` of a page-local point, for selection.
+    pub fn image_at(&self, x: f64, y: f64, tolerance: f64) -> Option<String> {
+        let engine = self.read_or_recover();
+        engine
+            .image_at(FfiPoint { x, y }.into(), tolerance)
+            .map(|id| id.to_string())
+    }
+
+    /// Page-local axis-aligned bounds of a placed image, rotation included.
+    pub fn get_image_bounds(&self, id: String) -> Result<FfiRect, DrawEngineError> {
+        let uuid = parse_image_id(id.clone())?;
+        let engine = self.read()?;
+        let image = engine
+            .image(uuid)
+            .ok_or(DrawEngineError::ImageNotFound { image_id: id })?;
+        Ok(image.bounding_box().into())
+    }
+
+    // --- Fills ---
+
+    /// Fill the area enclosed by strokes and shape outlines around a page-local point in
+    /// the current brush color. Returns the new fill's id, or `None` if the area isn't
+    /// closed.
+    pub fn fill_enclosed_area(&self, x: f64, y: f64) -> Result<Option<String>, DrawEngineError> {
+        let mut engine = self.write()?;
+        let id = engine.fill_enclosed_area(FfiPoint { x, y }.into())?;
+        Ok(id.map(|id| id.to_string()))
+    }
+
+    /// Fill the inside of a lasso through page-local points in the current brush color.
+    pub fn fill_lasso(&self, points: Vec<FfiPoint>) -> Result<String, DrawEngineError> {
+        let points: Vec<_> = points.into_iter().map(Into::into).collect();
+        let mut engine = self.write()?;
+        Ok(engine.fill_lasso(&points)?.to_string())
+    }
+
+    pub fn remove_fill(&self, fill_id: String) -> Result<(), DrawEngineError> {
+        let id = Uuid::parse_str(&fill_id)
+            .map_err(|_| DrawEngineError::FillNotFound { fill_id })?;
+        let mut engine = self.write()?;
+        engine.remove_fill(id).map_err(DrawEngineError::from)
+    }
+
+    pub fn get_fill(&self, fill_id: String) -> Option<FfiFillRegion> {
+        let id = Uuid::parse_str(&fill_id).ok()?;
+        let engine = self.read_or_recover();
+        engine.fill(id).map(Into::into)
+    }
+
+    /// Topmost fill covering a page-local point, for selection.
+    pub fn fill_at(&self, x: f64, y: f64) -> Option<String> {
+        let engine = self.read_or_recover();
+        engine
+            .fill_at(FfiPoint { x, y }.into())
+            .map(|id| id.to_string())
+    }
+
+    // --- Layers ---
+
+    pub fn set_active_layer(&self, layer_id: String) -> Result<(), DrawEngineError> {
+        let id = Uuid::parse_str(&layer_id)
+            .map_err(|_| DrawEngineError::LayerNotFound { layer_id })?;
+        let mut engine = self.write()?;
+        engine.set_active_layer(id).map_err(DrawEngineError::from)
+    }
+
     // --- Serialization ---
 
     pub fn save(&self) -> Result<String, DrawEngineError> {
-        let engine = self.inner.read().unwrap();
+        let engine = self.read()?;
         engine.save().map_err(DrawEngineError::from)
     }
 
     pub fn load(&self, json: String) -> Result<(), DrawEngineError> {
-        let mut engine = self.inner.write().unwrap();
+        let mut engine = self.write()?;
         engine.load(&json).map_err(DrawEngineError::from)
     }
+
+    /// Load a document, repairing corrupt content. Returns the issues that were fixed.
+    pub fn load_repaired(
+        &self,
+        json: String,
+    ) -> Result<Vec<FfiValidationIssue>, DrawEngineError> {
+        let mut engine = self.write()?;
+        let report = engine.load_repaired(&json)?;
+        Ok(report.issues.into_iter().map(Into::into).collect())
+    }
+
+    pub fn save_package(
+        &self,
+        metadata: FfiDocumentMetadata,
+        thumbnail: Vec<u8>,
+    ) -> Result<Vec<u8>, DrawEngineError> {
+        let engine = self.read()?;
+        engine
+            .save_package(&metadata.into(), &thumbnail)
+            .map_err(DrawEngineError::from)
+    }
+
+    pub fn load_package(&self, bytes: Vec<u8>) -> Result<FfiDocumentMetadata, DrawEngineError> {
+        let mut engine = self.write()?;
+        Ok(engine.load_package(&bytes)?.into())
+    }
+
+    // --- Journal ---
+
+    pub fn set_journaling(&self, enabled: bool) {
+        let mut engine = self.write_or_recover();
+        engine.set_journaling(enabled);
+    }
+
+    /// Returns JSON lines to append to the journal file; empty when nothing changed.
+    pub fn drain_journal(&self) -> Result<String, DrawEngineError> {
+        let mut engine = self.write()?;
+        engine.drain_journal().map_err(DrawEngineError::from)
+    }
+
+    pub fn recover(
+        &self,
+        snapshot_json: Option<String>,
+        journal: String,
+    ) -> Result<FfiRecoveryReport, DrawEngineError> {
+        let mut engine = self.write()?;
+        let report = engine.recover(snapshot_json.as_deref(), &journal)?;
+        Ok(report.into())
+    }
+}
+
+impl DrawEngineFFI {
+    /// Fails with `LockPoisoned` if a panic happened while the engine was locked.
+    fn read(&self) -> Result<RwLockReadGuard<'_, DrawEngine>, DrawEngineError> {
+        Ok(self.inner.read()?)
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, DrawEngine>, DrawEngineError> {
+        Ok(self.inner.write()?)
+    }
+
+    /// For methods with no error to return: takes over a poisoned lock instead of
+    /// panicking across the FFI boundary.
+    fn read_or_recover(&self) -> RwLockReadGuard<'_, DrawEngine> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_or_recover(&self) -> RwLockWriteGuard<'_, DrawEngine> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn parse_page_id(page_id: String) -> Result<Uuid, DrawEngineError> {
+    Uuid::parse_str(&page_id).map_err(|_| DrawEngineError::PageNotFound { page_id })
+}
+
+fn parse_shape_id(shape_id: String) -> Result<Uuid, DrawEngineError> {
+    Uuid::parse_str(&shape_id).map_err(|_| DrawEngineError::ShapeNotFound { shape_id })
+}
+
+fn parse_image_id(image_id: String) -> Result<Uuid, DrawEngineError> {
+    Uuid::parse_str(&image_id).map_err(|_| DrawEngineError::ImageNotFound { image_id })
+}
+
+fn parse_text_id(text_id: String) -> Result<Uuid, DrawEngineError> {
+    Uuid::parse_str(&text_id).map_err(|_| DrawEngineError::TextNotFound { text_id })
+}
+
+fn parse_preset_id(preset_id: String) -> Result<Uuid, DrawEngineError> {
+    Uuid::parse_str(&preset_id).map_err(|_| DrawEngineError::PresetNotFound { preset_id })
+}
+
+/// Read only the metadata of a package, without parsing the document body.
+#[uniffi::export]
+pub fn read_package_metadata(bytes: Vec<u8>) -> Result<FfiDocumentMetadata, DrawEngineError> {
+    let reader = PackageReader::new(&bytes)?;
+    Ok(reader.metadata()?.into())
+}
+
+/// Read only the preview thumbnail of a package (empty if none was stored).
+#[uniffi::export]
+pub fn read_package_thumbnail(bytes: Vec<u8>) -> Result<Vec<u8>, DrawEngineError> {
+    let reader = PackageReader::new(&bytes)?;
+    Ok(reader.thumbnail().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_of_range_brush_saves_a_loadable_document() {
+        let engine = DrawEngineFFI::new(800.0, 600.0);
+        let mut brush = engine.get_brush();
+        brush.color = FfiColor {
+            r: 1.05,
+            g: -0.2,
+            b: f32::NAN,
+            a: 1.0,
+        };
+        brush.base_width = -4.0;
+        brush.gradient_color = Some(FfiColor {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 7.0,
+        });
+        engine.set_brush(brush).unwrap();
+        let color = engine.get_brush().color;
+        assert_eq!((color.r, color.g, color.b), (1.0, 0.0, 0.0));
+
+        engine.begin_stroke(10.0, 10.0, 0.5, 0.0);
+        engine.add_point(40.0, 40.0, 0.5, 0.016);
+        engine.end_stroke();
+        let json = engine.save().unwrap();
+        let reloaded = DrawEngineFFI::new(800.0, 600.0);
+        reloaded.load(json).unwrap();
+    }
 }
@@ -5,11 +5,100 @@
 pub enum DrawEngineError {
     #[error("{message}")]
     SerializationError { message: String },
+    #[error("parse error at line {line}, column {column}: {message}")]
+    ParseError {
+        message: String,
+        line: u32,
+        column: u32,
+    },
+    #[error("unsupported document version {found} (supported up to {supported})")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("invalid geometry: {message}")]
+    InvalidGeometry { message: String },
+    #[error("invalid document: {} issue(s)", issues.len())]
+    InvalidDocument { issues: Vec<FfiValidationIssue> },
+    #[error("invalid package: {message}")]
+    InvalidPackage { message: String },
+    #[error("layer not found: {layer_id}")]
+    LayerNotFound { layer_id: String },
+    #[error("page not found: {page_id}")]
+    PageNotFound { page_id: String },
+    #[error("cannot remove the last page")]
+    CannotRemoveLastPage,
+    #[error("brush preset not found: {preset_id}")]
+    PresetNotFound { preset_id: String },
+    #[error("shape not found: {shape_id}")]
+    ShapeNotFound { shape_id: String },
+    #[error("text box not found: {text_id}")]
+    TextNotFound { text_id: String },
+    #[error("fill not found: {fill_id}")]
+    FillNotFound { fill_id: String },
+    #[error("image not found: {image_id}")]
+    ImageNotFound { image_id: String },
+    #[error("invalid image: {message}")]
+    InvalidImage { message: String },
+    #[error("unsupported: {message}")]
+    Unsupported { message: String },
+    #[error("engine lock poisoned")]
+    LockPoisoned,
 }
 
-impl From<String> for DrawEngineError {
-    fn from(s: String) -> Self {
-        DrawEngineError::SerializationError { message: s }
+impl From<EngineError> for DrawEngineError {
+    fn from(e: EngineError) -> Self {
+        match e {
+            EngineError::Parse {
+                message,
+                line,
+                column,
+            } => DrawEngineError::ParseError {
+                message,
+                line: line as u32,
+                column: column as u32,
+            },
+            EngineError::UnsupportedVersion { found, supported } => {
+                DrawEngineError::UnsupportedVersion { found, supported }
+            }
+            EngineError::InvalidGeometry { message } => {
+                DrawEngineError::InvalidGeometry { message }
+            }
+            EngineError::InvalidDocument { issues } => DrawEngineError::InvalidDocument {
+                issues: issues.into_iter().map(Into::into).collect(),
+            },
+            EngineError::InvalidPackage { message } => DrawEngineError::InvalidPackage { message },
+            EngineError::LayerNotFound { layer_id } => DrawEngineError::LayerNotFound {
+                layer_id: layer_id.to_string(),
+            },
+            EngineError::PageNotFound { page_id } => DrawEngineError::PageNotFound {
+                page_id: page_id.to_string(),
+            },
+            EngineError::CannotRemoveLastPage => DrawEngineError::CannotRemoveLastPage,
+            EngineError::Serialization { message } => {
+                DrawEngineError::SerializationError { message }
+            }
+            EngineError::PresetNotFound { preset_id } => DrawEngineError::PresetNotFound {
+                preset_id: preset_id.to_string(),
+            },
+            EngineError::ShapeNotFound { shape_id } => DrawEngineError::ShapeNotFound {
+                shape_id: shape_id.to_string(),
+            },
+            EngineError::TextNotFound { text_id } => DrawEngineError::TextNotFound {
+                text_id: text_id.to_string(),
+            },
+            EngineError::FillNotFound { fill_id } => DrawEngineError::FillNotFound {
+                fill_id: fill_id.to_string(),
+            },
+            EngineError::ImageNotFound { image_id } => DrawEngineError::ImageNotFound {
+                image_id: image_id.to_string(),
+            },
+            EngineError::InvalidImage { message } => DrawEngineError::InvalidImage { message },
+            EngineError::Unsupported { message } => DrawEngineError::Unsupported { message },
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for DrawEngineError {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        DrawEngineError::LockPoisoned
     }
 }
 
@@ -27,19 +116,101 @@ pub struct FfiColor {
     pub a: f32,
 }
 
+/// One touch sample in screen coordinates, for batched input.
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiStrokeInput {
     pub x: f64,
     pub y: f64,
     pub pressure: f64,
     pub timestamp: f64,
+    /// Stylus altitude in radians (π/2 = upright), if the device reports it.
+    #[uniffi(default = None)]
+    pub altitude: Option<f64>,
+    /// Stylus azimuth in radians, if the device reports it.
+    #[uniffi(default = None)]
+    pub azimuth: Option<f64>,
+    #[uniffi(default = None)]
+    pub pointer_type: Option<FfiPointerType>,
 }
 
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiPointerType {
+    Finger,
+    Pen,
+    Mouse,
+    PenEraser,
+}
+
+/// When finished strokes are replaced by the shape they resemble.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiShapeSnapping {
+    Off,
+    /// Only strokes that end with the pen held still.
+    OnHold,
+    Always,
+}
+
+/// A brush. Every optional field left `None` keeps the brush type's preset value, so
+/// `get_brush` returns them all set.
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiBrushConfig {
     pub brush_type: FfiBrushType,
     pub color: FfiColor,
     pub base_width: f64,
+    /// Input smoothing in `0.0..=1.0`.
+    #[uniffi(default = None)]
+    pub smoothing: Option<f64>,
+    #[uniffi(default = None)]
+    pub min_width_factor: Option<f64>,
+    #[uniffi(default = None)]
+    pub max_width_factor: Option<f64>,
+    #[uniffi(default = None)]
+    pub pressure_sensitivity: Option<f64>,
+    #[uniffi(default = None)]
+    pub velocity_sensitivity: Option<f64>,
+    #[uniffi(default = None)]
+    pub tilt_sensitivity: Option<f64>,
+    /// Pressure curve control points; see `set_device_pressure_curve`.
+    #[uniffi(default = None)]
+    pub pressure_curve: Option<Vec<FfiPoint>>,
+    /// Opacity-by-pressure curve; empty turns opacity dynamics off.
+    #[uniffi(default = None)]
+    pub opacity_curve: Option<Vec<FfiPoint>>,
+    #[uniffi(default = None)]
+    pub velocity_opacity: Option<f64>,
+    /// Lightness jitter in `0.0..=1.0`.
+    #[uniffi(default = None)]
+    pub color_jitter: Option<f64>,
+    /// Color the stroke blends into over `gradient_length`.
+    #[uniffi(default = None)]
+    pub gradient_color: Option<FfiColor>,
+    #[uniffi(default = None)]
+    pub gradient_length: Option<f64>,
+    #[uniffi(default = None)]
+    pub taper_in: Option<f64>,
+    #[uniffi(default = None)]
+    pub taper_out: Option<f64>,
+    /// Tip and spacing for `FfiBrushType::Stamp`.
+    #[uniffi(default = None)]
+    pub stamp: Option<FfiStampSettings>,
+    /// Nib for `FfiBrushType::Calligraphy`.
+    #[uniffi(default = None)]
+    pub nib: Option<FfiNibSettings>,
+    /// Dash pattern; empty intervals draw the stroke solid.
+    #[uniffi(default = None)]
+    pub dash: Option<FfiDashPattern>,
+    #[uniffi(default = None)]
+    pub line_cap: Option<FfiLineCap>,
+    #[uniffi(default = None)]
+    pub line_join: Option<FfiLineJoin>,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiBrushPreset {
+    pub id: String,
+    pub name: String,
+    pub version: u32,
+    pub brush: FfiBrushConfig,
 }
 
 #[derive(Debug, Clone, uniffi::Enum)]
@@ -47,6 +218,8 @@ pub enum FfiBrushType {
     Pen,
     Highlighter,
     Eraser,
+    Stamp,
+    Calligraphy,
 }
 
 #[derive(Debug, Clone, uniffi::Record)]
@@ -61,6 +234,80 @@ pub struct FfiPathSegment {
     pub p3_y: f64,
     pub start_width: f64,
     pub end_width: f64,
+    /// Gradient endpoints overriding the command color; both set or both `None`.
+    pub start_color: Option<FfiColor>,
+    pub end_color: Option<FfiColor>,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiStamp {
+    pub x: f64,
+    pub y: f64,
+    /// Diameter in canvas units.
+    pub size: f64,
+    /// Radians, clockwise from the positive x axis.
+    pub rotation: f64,
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+    /// Seed for procedural tips such as grain.
+    pub seed: u32,
+}
+
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiStampTip {
+    /// Disc; `hardness` 1 is a crisp edge, 0 fades out from the center.
+    Round { hardness: f64 },
+    Square,
+    /// Disc of random specks covering `density` of its area.
+    Grain { density: f64 },
+    /// Grayscale texture the host registered under `name`.
+    Mask { name: String },
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiStampSettings {
+    pub tip: FfiStampTip,
+    /// Distance between stamps as a fraction of the stamp size.
+    pub spacing: f64,
+    pub size_jitter: f64,
+    /// Largest random rotation in radians.
+    pub rotation_jitter: f64,
+    /// Largest sideways offset as a fraction of the stamp size.
+    pub scatter: f64,
+    pub follow_direction: bool,
+    /// Opacity of a single stamp.
+    pub flow: f64,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiDashPattern {
+    /// Dash, gap, dash, gap… as multiples of the base width; a zero dash is a dot.
+    pub intervals: Vec<f64>,
+    pub offset: f64,
+}
+
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiLineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiLineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiNibSettings {
+    /// Radians, clockwise from the positive x axis.
+    pub angle: f64,
+    /// Nib thickness as a fraction of its length.
+    pub thickness: f64,
 }
 
 #[derive(Debug, Clone, uniffi::Enum)]
@@ -85,9 +332,341 @@ pub enum FfiRenderCommand {
         b: f32,
         a: f32,
         is_eraser: bool,
+        cap: FfiLineCap,
+        join: FfiLineJoin,
+    },
+    /// Each inner list is one dash, drawn as its own open path with `cap` at both ends.
+    DrawDashedPath {
+        dashes: Vec<Vec<FfiPathSegment>>,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+        cap: FfiLineCap,
+        join: FfiLineJoin,
+    },
+    /// Temporary predicted tail of the active stroke; replaces the previous prediction.
+    DrawPrediction {
+        segments: Vec<FfiPathSegment>,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    },
+    /// Brush tips from a stamp brush, drawn in order.
+    DrawStamps {
+        stamps: Vec<FfiStamp>,
+        tip: FfiStampTip,
+    },
+    /// Fill all polygons as one path with the nonzero winding rule.
+    FillPolygons {
+        polygons: Vec<Vec<FfiPoint>>,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    },
+    DrawLines {
+        lines: Vec<FfiLine>,
+        width: f64,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    },
+    DrawDots {
+        centers: Vec<FfiPoint>,
+        radius: f64,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    },
+    FillRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    },
+    /// Fill the path if `fill` is set and it is closed, then stroke it with uniform
+    /// `stroke_width` if `stroke` is set. Ends are round.
+    DrawShape {
+        segments: Vec<FfiPathSegment>,
+        closed: bool,
+        fill: Option<FfiColor>,
+        stroke: Option<FfiColor>,
+        stroke_width: f64,
+        join: FfiLineJoin,
+    },
+    /// A wrapped text box; draw each run with its baseline starting at (`x`, `y`).
+    DrawText {
+        runs: Vec<FfiTextRun>,
+        font_family: String,
+        font_size: f64,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+    },
+    /// The `crop` pixels of the image from `get_image_bytes`, mapped by `transform`
+    /// from offsets relative to the crop's top-left corner.
+    DrawImage {
+        image_id: String,
+        crop: FfiRect,
+        transform: FfiAffineTransform,
+    },
+}
+
+/// Maps `(x, y)` to `(a·x + c·y + tx, b·x + d·y + ty)`, like `CGAffineTransform`.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiAffineTransform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub tx: f64,
+    pub ty: f64,
+}
+
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiImageFormat {
+    Png,
+    Jpeg,
+}
+
+/// An imported image's header, without its bytes.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiImageInfo {
+    pub id: String,
+    pub format: FfiImageFormat,
+    /// Size in pixels.
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An imported image placed on a layer.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiImage {
+    /// Ignored by `add_image`, which assigns a new id.
+    pub id: String,
+    /// Id returned by `import_image`.
+    pub image_id: String,
+    /// Center of the visible part in page-local coordinates.
+    pub center: FfiPoint,
+    /// Page units per image pixel.
+    pub scale: f64,
+    /// Radians, clockwise from the positive x axis.
+    pub rotation: f64,
+    /// Visible part of the image, in pixels.
+    pub crop: FfiRect,
+}
+
+/// A filled area, e.g. from the fill tool.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiFillRegion {
+    pub id: String,
+    /// Page-local outlines, filled together with the nonzero rule.
+    pub polygons: Vec<Vec<FfiPoint>>,
+    pub color: FfiColor,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiTextRun {
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    /// The run's advance as measured by the `TextMeasurer`, or estimated without one.
+    pub width: f64,
+}
+
+/// Vertical font metrics in page units, returned by a `TextMeasurer`.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiFontMetrics {
+    /// Distance from the top of a line to its baseline.
+    pub ascent: f64,
+    /// Distance between baselines.
+    pub line_height: f64,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiTextBox {
+    /// Ignored by `add_text`, which assigns a new id.
+    pub id: String,
+    /// Top-left corner in page-local coordinates.
+    pub x: f64,
+    pub y: f64,
+    /// Wrapping width; `None` only breaks at newlines.
+    pub width: Option<f64>,
+    pub font_family: String,
+    pub font_size: f64,
+    pub color: FfiColor,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Parametric shape geometry in page-local coordinates. Rotations are in radians,
+/// clockwise from the positive x axis.
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiShapeGeometry {
+    Line {
+        start: FfiPoint,
+        end: FfiPoint,
+    },
+    Arrow {
+        start: FfiPoint,
+        end: FfiPoint,
+        head_length: f64,
+    },
+    Ellipse {
+        center: FfiPoint,
+        radius_x: f64,
+        radius_y: f64,
+        rotation: f64,
+    },
+    Rectangle {
+        center: FfiPoint,
+        width: f64,
+        height: f64,
+        rotation: f64,
+    },
+    Polygon {
+        vertices: Vec<FfiPoint>,
     },
 }
 
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiShape {
+    /// Ignored by `add_shape`, which assigns a new id.
+    pub id: String,
+    pub geometry: FfiShapeGeometry,
+    /// `None` draws no outline.
+    pub stroke_color: Option<FfiColor>,
+    pub stroke_width: f64,
+    /// Ignored for lines and arrows.
+    pub fill_color: Option<FfiColor>,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiLine {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+}
+
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiPageTemplate {
+    Blank,
+    Ruled {
+        spacing: f64,
+        top_margin: f64,
+        margin_x: Option<f64>,
+        line_color: FfiColor,
+        margin_color: FfiColor,
+        line_width: f64,
+    },
+    Grid {
+        spacing: f64,
+        line_color: FfiColor,
+        line_width: f64,
+    },
+    DotGrid {
+        spacing: f64,
+        dot_color: FfiColor,
+        dot_radius: f64,
+    },
+    Cornell {
+        spacing: f64,
+        header_height: f64,
+        cue_width: f64,
+        summary_height: f64,
+        line_color: FfiColor,
+        divider_color: FfiColor,
+        line_width: f64,
+    },
+    MusicStaff {
+        line_spacing: f64,
+        staff_gap: f64,
+        line_color: FfiColor,
+        line_width: f64,
+    },
+}
+
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiPageLayout {
+    Single,
+    ContinuousVertical { gap: f64, background: FfiColor },
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiPageInfo {
+    pub id: String,
+    pub width: f64,
+    pub height: f64,
+    pub stroke_count: u32,
+    pub background: FfiColor,
+    pub template: FfiPageTemplate,
+}
+
+#[derive(Debug, Clone, uniffi::Enum)]
+pub enum FfiIssueKind {
+    NonFiniteGeometry,
+    NegativeWidth,
+    ColorOutOfRange,
+    StaleBoundingBox,
+    DuplicateElementId,
+    DuplicateLayerId,
+    DuplicatePageId,
+    InvalidTemplate,
+    InvalidLayerOpacity,
+    InvalidBrush,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiValidationIssue {
+    pub kind: FfiIssueKind,
+    pub page_id: Option<String>,
+    pub layer_id: Option<String>,
+    pub element_id: Option<String>,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiRecoveryReport {
+    pub applied: u32,
+    pub skipped: u32,
+    pub corrupt_lines: u32,
+    pub repaired_issues: Vec<FfiValidationIssue>,
+    /// Placed images whose bytes must be imported again.
+    pub missing_image_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiDocumentMetadata {
+    pub title: String,
+    pub created_at: f64,
+    pub modified_at: f64,
+    pub tags: Vec<String>,
+    pub app_version: String,
+}
+
+/// Infinite-canvas chunk position.
+#[derive(Debug, Clone, Copy, uniffi::Record)]
+pub struct FfiChunkCoord {
+    pub x: i64,
+    pub y: i64,
+}
+
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiEngineState {
     pub stroke_count: u32,
@@ -97,17 +676,357 @@ pub struct FfiEngineState {
     pub offset_x: f64,
     pub offset_y: f64,
     pub active_layer_id: String,
+    pub current_page_id: String,
+    pub page_count: u32,
+    /// Set in infinite-canvas mode; `offset_x`/`offset_y` are then relative to this chunk.
+    pub canvas_anchor: Option<FfiChunkCoord>,
 }
 
 // --- Conversion helpers ---
 
 use drawengine_core::brush::{BrushConfig, BrushType};
+use drawengine_core::dash::{DashPattern, LineCap, LineJoin};
+use drawengine_core::error::EngineError;
+use drawengine_core::fill::FillRegion;
+use drawengine_core::image::{AffineTransform, CropRect, Image, ImageData, ImageFormat};
+use drawengine_core::infinite::ChunkCoord;
+use drawengine_core::journal::RecoveryReport;
+use drawengine_core::nib::NibSettings;
+use drawengine_core::package::DocumentMetadata;
+use drawengine_core::page::{Page, PageLayout};
 use drawengine_core::point::Color;
+use drawengine_core::point::{BoundingBox, Point, PointerType, StrokePoint};
+use drawengine_core::preset::BrushPreset;
+use drawengine_core::pressure::PressureCurve;
+use drawengine_core::recognition::ShapeSnapping;
 use drawengine_core::render::{PathSegment, RenderCommand};
+use drawengine_core::shape::{Shape, ShapeGeometry};
+use drawengine_core::stamp::{Stamp, StampSettings, StampTip};
+use drawengine_core::template::PageTemplate;
+use drawengine_core::text::{FontMetrics, TextBox, TextRun};
+use drawengine_core::validation::{IssueKind, ValidationIssue};
+use uuid::Uuid;
 
+impl From<FfiStrokeInput> for StrokePoint {
+    fn from(input: FfiStrokeInput) -> Self {
+        StrokePoint {
+            altitude: input.altitude,
+            azimuth: input.azimuth,
+            pointer_type: input.pointer_type.map_or(PointerType::Unknown, Into::into),
+            ..StrokePoint::new(input.x, input.y, input.pressure, input.timestamp)
+        }
+    }
+}
+
+impl From<FfiPointerType> for PointerType {
+    fn from(t: FfiPointerType) -> Self {
+        match t {
+            FfiPointerType::Finger => PointerType::Finger,
+            FfiPointerType::Pen => PointerType::Pen,
+            FfiPointerType::Mouse => PointerType::Mouse,
+            FfiPointerType::PenEraser => PointerType::PenEraser,
+        }
+    }
+}
+
+impl From<FfiShapeSnapping> for ShapeSnapping {
+    fn from(s: FfiShapeSnapping) -> Self {
+        match s {
+            FfiShapeSnapping::Off => ShapeSnapping::Off,
+            FfiShapeSnapping::OnHold => ShapeSnapping::OnHold,
+            FfiShapeSnapping::Always => ShapeSnapping::Always,
+        }
+    }
+}
+
+impl From<ShapeSnapping> for FfiShapeSnapping {
+    fn from(s: ShapeSnapping) -> Self {
+        match s {
+            ShapeSnapping::Off => FfiShapeSnapping::Off,
+            ShapeSnapping::OnHold => FfiShapeSnapping::OnHold,
+            ShapeSnapping::Always => FfiShapeSnapping::Always,
+        }
+    }
+}
+
+impl From<ChunkCoord> for FfiChunkCoord {
+    fn from(c: ChunkCoord) -> Self {
+        Self { x: c.x, y: c.y }
+    }
+}
+
+/// Components are clamped into `0.0..=1.0`; non-finite ones fall back to opaque black.
 impl From<FfiColor> for Color {
     fn from(c: FfiColor) -> Self {
-        Color::new(c.r, c.g, c.b, c.a)
+        let fix = |v: f32, fallback: f32| {
+            if v.is_finite() {
+                v.clamp(0.0, 1.0)
+            } else {
+                fallback
+            }
+        };
+        Color::new(fix(c.r, 0.0), fix(c.g, 0.0), fix(c.b, 0.0), fix(c.a, 1.0))
+    }
+}
+
+impl From<FfiShapeGeometry> for ShapeGeometry {
+    fn from(g: FfiShapeGeometry) -> Self {
+        match g {
+            FfiShapeGeometry::Line { start, end } => ShapeGeometry::Line {
+                start: start.into(),
+                end: end.into(),
+            },
+            FfiShapeGeometry::Arrow {
+                start,
+                end,
+                head_length,
+            } => ShapeGeometry::Arrow {
+                start: start.into(),
+                end: end.into(),
+                head_length,
+            },
+            FfiShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => ShapeGeometry::Ellipse {
+                center: center.into(),
+                radius_x,
+                radius_y,
+                rotation,
+            },
+            FfiShapeGeometry::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            } => ShapeGeometry::Rectangle {
+                center: center.into(),
+                width,
+                height,
+                rotation,
+            },
+            FfiShapeGeometry::Polygon { vertices } => ShapeGeometry::Polygon {
+                vertices: vertices.into_iter().map(Into::into).collect(),
+            },
+        }
+    }
+}
+
+impl From<ShapeGeometry> for FfiShapeGeometry {
+    fn from(g: ShapeGeometry) -> Self {
+        match g {
+            ShapeGeometry::Line { start, end } => FfiShapeGeometry::Line {
+                start: start.into(),
+                end: end.into(),
+            },
+            ShapeGeometry::Arrow {
+                start,
+                end,
+                head_length,
+            } => FfiShapeGeometry::Arrow {
+                start: start.into(),
+                end: end.into(),
+                head_length,
+            },
+            ShapeGeometry::Ellipse {
+                center,
+                radius_x,
+                radius_y,
+                rotation,
+            } => FfiShapeGeometry::Ellipse {
+                center: center.into(),
+                radius_x,
+                radius_y,
+                rotation,
+            },
+            ShapeGeometry::Rectangle {
+                center,
+                width,
+                height,
+                rotation,
+            } => FfiShapeGeometry::Rectangle {
+                center: center.into(),
+                width,
+                height,
+                rotation,
+            },
+            ShapeGeometry::Polygon { vertices } => FfiShapeGeometry::Polygon {
+                vertices: vertices.into_iter().map(Into::into).collect(),
+            },
+        }
+    }
+}
+
+impl From<&Shape> for FfiShape {
+    fn from(s: &Shape) -> Self {
+        FfiShape {
+            id: s.id.to_string(),
+            geometry: s.geometry.clone().into(),
+            stroke_color: s.stroke_color.map(Into::into),
+            stroke_width: s.stroke_width,
+            fill_color: s.fill_color.map(Into::into),
+        }
+    }
+}
+
+/// `shape` with id `id`; the id in the record itself is not parsed.
+pub fn shape_from_ffi(shape: FfiShape, id: Uuid) -> Shape {
+    Shape {
+        id,
+        geometry: shape.geometry.into(),
+        stroke_color: shape.stroke_color.map(Into::into),
+        stroke_width: shape.stroke_width,
+        fill_color: shape.fill_color.map(Into::into),
+    }
+}
+
+impl From<&TextBox> for FfiTextBox {
+    fn from(t: &TextBox) -> Self {
+        FfiTextBox {
+            id: t.id.to_string(),
+            x: t.position.x,
+            y: t.position.y,
+            width: t.width,
+            font_family: t.font_family.clone(),
+            font_size: t.font_size,
+            color: t.color.into(),
+            text: t.text.clone(),
+        }
+    }
+}
+
+/// `text` with id `id`; the id in the record itself is not parsed.
+pub fn text_from_ffi(text: FfiTextBox, id: Uuid) -> TextBox {
+    TextBox {
+        id,
+        position: Point::new(text.x, text.y),
+        width: text.width,
+        font_family: text.font_family,
+        font_size: text.font_size,
+        color: text.color.into(),
+        text: text.text,
+    }
+}
+
+impl From<&FillRegion> for FfiFillRegion {
+    fn from(f: &FillRegion) -> Self {
+        FfiFillRegion {
+            id: f.id.to_string(),
+            polygons: f
+                .polygons
+                .iter()
+                .map(|polygon| polygon.iter().map(|&p| p.into()).collect())
+                .collect(),
+            color: f.color.into(),
+        }
+    }
+}
+
+impl From<&ImageData> for FfiImageInfo {
+    fn from(d: &ImageData) -> Self {
+        FfiImageInfo {
+            id: d.id.to_string(),
+            format: match d.format {
+                ImageFormat::Png => FfiImageFormat::Png,
+                ImageFormat::Jpeg => FfiImageFormat::Jpeg,
+            },
+            width: d.width,
+            height: d.height,
+        }
+    }
+}
+
+impl From<&Image> for FfiImage {
+    fn from(i: &Image) -> Self {
+        FfiImage {
+            id: i.id.to_string(),
+            image_id: i.image_id.to_string(),
+            center: i.center.into(),
+            scale: i.scale,
+            rotation: i.rotation,
+            crop: i.crop.into(),
+        }
+    }
+}
+
+/// `image` with placement id `id` showing imported image `image_id`; the ids in the
+/// record itself are not parsed.
+pub fn image_from_ffi(image: FfiImage, id: Uuid, image_id: Uuid) -> Image {
+    Image {
+        id,
+        image_id,
+        center: image.center.into(),
+        scale: image.scale,
+        rotation: image.rotation,
+        crop: image.crop.into(),
+    }
+}
+
+impl From<CropRect> for FfiRect {
+    fn from(c: CropRect) -> Self {
+        FfiRect {
+            x: c.x,
+            y: c.y,
+            width: c.width,
+            height: c.height,
+        }
+    }
+}
+
+impl From<FfiRect> for CropRect {
+    fn from(r: FfiRect) -> Self {
+        CropRect {
+            x: r.x,
+            y: r.y,
+            width: r.width,
+            height: r.height,
+        }
+    }
+}
+
+impl From<AffineTransform> for FfiAffineTransform {
+    fn from(t: AffineTransform) -> Self {
+        FfiAffineTransform {
+            a: t.a,
+            b: t.b,
+            c: t.c,
+            d: t.d,
+            tx: t.tx,
+            ty: t.ty,
+        }
+    }
+}
+
+impl From<FfiFontMetrics> for FontMetrics {
+    fn from(m: FfiFontMetrics) -> Self {
+        FontMetrics {
+            ascent: m.ascent,
+            line_height: m.line_height,
+        }
+    }
+}
+
+impl From<TextRun> for FfiTextRun {
+    fn from(r: TextRun) -> Self {
+        FfiTextRun {
+            text: r.text,
+            x: r.origin.x,
+            y: r.origin.y,
+            width: r.width,
+        }
+    }
+}
+
+impl From<BoundingBox> for FfiRect {
+    fn from(bb: BoundingBox) -> Self {
+        FfiRect {
+            x: bb.min_x,
+            y: bb.min_y,
+            width: bb.width(),
+            height: bb.height(),
+        }
     }
 }
 
@@ -128,17 +1047,522 @@ impl From<FfiBrushType> for BrushType {
             FfiBrushType::Pen => BrushType::Pen,
             FfiBrushType::Highlighter => BrushType::Highlighter,
             FfiBrushType::Eraser => BrushType::Eraser,
+            FfiBrushType::Stamp => BrushType::Stamp,
+            FfiBrushType::Calligraphy => BrushType::Calligraphy,
+        }
+    }
+}
+
+impl From<BrushType> for FfiBrushType {
+    fn from(bt: BrushType) -> Self {
+        match bt {
+            BrushType::Pen => FfiBrushType::Pen,
+            BrushType::Highlighter => FfiBrushType::Highlighter,
+            BrushType::Eraser => FfiBrushType::Eraser,
+            BrushType::Stamp => FfiBrushType::Stamp,
+            BrushType::Calligraphy => FfiBrushType::Calligraphy,
         }
     }
 }
 
-impl From<FfiBrushConfig> for BrushConfig {
-    fn from(cfg: FfiBrushConfig) -> Self {
+/// Out-of-range numbers and colors are clamped; only malformed curves are rejected.
+impl TryFrom<FfiBrushConfig> for BrushConfig {
+    type Error = DrawEngineError;
+
+    fn try_from(mut cfg: FfiBrushConfig) -> Result<Self, Self::Error> {
         let color: Color = cfg.color.into();
-        match cfg.brush_type {
+        cfg.base_width = finite_or(cfg.base_width, BrushConfig::default().base_width).max(0.0);
+        let mut brush = match cfg.brush_type {
             FfiBrushType::Pen => BrushConfig::pen(color, cfg.base_width),
             FfiBrushType::Highlighter => BrushConfig::highlighter(color, cfg.base_width),
             FfiBrushType::Eraser => BrushConfig::eraser(cfg.base_width),
+            FfiBrushType::Stamp => BrushConfig::stamp(color, cfg.base_width, Default::default()),
+            FfiBrushType::Calligraphy => {
+                BrushConfig::calligraphy(color, cfg.base_width, Default::default())
+            }
+        };
+        let unit = |v: f64| v.clamp(0.0, 1.0);
+        let non_negative = |v: f64| v.max(0.0);
+        let set = |field: &mut f64, value: Option<f64>, fix: &dyn Fn(f64) -> f64| {
+            if let Some(v) = value.filter(|v| v.is_finite()) {
+                *field = fix(v);
+            }
+        };
+        set(&mut brush.smoothing, cfg.smoothing, &unit);
+        set(&mut brush.min_width_factor, cfg.min_width_factor, &non_negative);
+        set(&mut brush.max_width_factor, cfg.max_width_factor, &non_negative);
+        brush.max_width_factor = brush.max_width_factor.max(brush.min_width_factor);
+        set(&mut brush.pressure_sensitivity, cfg.pressure_sensitivity, &non_negative);
+        set(&mut brush.velocity_sensitivity, cfg.velocity_sensitivity, &non_negative);
+        set(&mut brush.tilt_sensitivity, cfg.tilt_sensitivity, &non_negative);
+        set(&mut brush.velocity_opacity, cfg.velocity_opacity, &unit);
+        set(&mut brush.color_jitter, cfg.color_jitter, &unit);
+        set(&mut brush.gradient_length, cfg.gradient_length, &non_negative);
+        set(&mut brush.taper_in, cfg.taper_in, &non_negative);
+        set(&mut brush.taper_out, cfg.taper_out, &non_negative);
+        if let Some(points) = cfg.pressure_curve {
+            brush.pressure_curve = pressure_curve(points)?;
+        }
+        if let Some(points) = cfg.opacity_curve {
+            brush.opacity_curve = if points.is_empty() {
+                None
+            } else {
+                Some(pressure_curve(points)?)
+            };
+        }
+        if let Some(end) = cfg.gradient_color {
+            brush.gradient_color = Some(end.into());
+        }
+        if let Some(stamp) = cfg.stamp {
+            brush.stamp = Some(stamp.into());
+        }
+        if let Some(nib) = cfg.nib {
+            brush.nib = Some(nib.into());
+        }
+        if let Some(dash) = cfg.dash {
+            brush.dash = if dash.intervals.is_empty() {
+                None
+            } else {
+                let mut pattern = DashPattern::new(dash.intervals)?;
+                pattern.offset = finite_or(dash.offset, 0.0);
+                Some(pattern)
+            };
+        }
+        if let Some(cap) = cfg.line_cap {
+            brush.line_cap = cap.into();
+        }
+        if let Some(join) = cfg.line_join {
+            brush.line_join = join.into();
+        }
+        Ok(brush)
+    }
+}
+
+impl From<&BrushConfig> for FfiBrushConfig {
+    fn from(brush: &BrushConfig) -> Self {
+        let curve_points = |c: &PressureCurve| c.points().iter().map(|&p| p.into()).collect();
+        FfiBrushConfig {
+            brush_type: brush.brush_type.into(),
+            color: brush.color.into(),
+            base_width: brush.base_width,
+            smoothing: Some(brush.smoothing),
+            min_width_factor: Some(brush.min_width_factor),
+            max_width_factor: Some(brush.max_width_factor),
+            pressure_sensitivity: Some(brush.pressure_sensitivity),
+            velocity_sensitivity: Some(brush.velocity_sensitivity),
+            tilt_sensitivity: Some(brush.tilt_sensitivity),
+            pressure_curve: Some(curve_points(&brush.pressure_curve)),
+            opacity_curve: Some(brush.opacity_curve.as_ref().map_or(vec![], curve_points)),
+            velocity_opacity: Some(brush.velocity_opacity),
+            color_jitter: Some(brush.color_jitter),
+            gradient_color: brush.gradient_color.map(Into::into),
+            gradient_length: Some(brush.gradient_length),
+            taper_in: Some(brush.taper_in),
+            taper_out: Some(brush.taper_out),
+            stamp: brush.stamp.clone().map(Into::into),
+            nib: brush.nib.clone().map(Into::into),
+            dash: Some(brush.dash.as_ref().map_or(
+                FfiDashPattern {
+                    intervals: vec![],
+                    offset: 0.0,
+                },
+                |d| FfiDashPattern {
+                    intervals: d.intervals.clone(),
+                    offset: d.offset,
+                },
+            )),
+            line_cap: Some(brush.line_cap.into()),
+            line_join: Some(brush.line_join.into()),
+        }
+    }
+}
+
+impl From<&BrushPreset> for FfiBrushPreset {
+    fn from(preset: &BrushPreset) -> Self {
+        FfiBrushPreset {
+            id: preset.id.to_string(),
+            name: preset.name.clone(),
+            version: preset.version,
+            brush: (&preset.brush).into(),
+        }
+    }
+}
+
+impl From<FfiStampTip> for StampTip {
+    fn from(tip: FfiStampTip) -> Self {
+        match tip {
+            FfiStampTip::Round { hardness } => StampTip::Round {
+                hardness: finite_or(hardness, 1.0).clamp(0.0, 1.0),
+            },
+            FfiStampTip::Square => StampTip::Square,
+            FfiStampTip::Grain { density } => StampTip::Grain {
+                density: finite_or(density, 0.5).clamp(0.0, 1.0),
+            },
+            FfiStampTip::Mask { name } => StampTip::Mask { name },
+        }
+    }
+}
+
+impl From<StampTip> for FfiStampTip {
+    fn from(tip: StampTip) -> Self {
+        match tip {
+            StampTip::Round { hardness } => FfiStampTip::Round { hardness },
+            StampTip::Square => FfiStampTip::Square,
+            StampTip::Grain { density } => FfiStampTip::Grain { density },
+            StampTip::Mask { name } => FfiStampTip::Mask { name },
+        }
+    }
+}
+
+/// Out-of-range numbers are clamped like the other brush parameters.
+impl From<FfiStampSettings> for StampSettings {
+    fn from(s: FfiStampSettings) -> Self {
+        let defaults = StampSettings::default();
+        StampSettings {
+            tip: s.tip.into(),
+            spacing: finite_or(s.spacing, defaults.spacing).max(0.01),
+            size_jitter: finite_or(s.size_jitter, 0.0).clamp(0.0, 1.0),
+            rotation_jitter: finite_or(s.rotation_jitter, 0.0).max(0.0),
+            scatter: finite_or(s.scatter, 0.0).max(0.0),
+            follow_direction: s.follow_direction,
+            flow: finite_or(s.flow, 1.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl From<StampSettings> for FfiStampSettings {
+    fn from(s: StampSettings) -> Self {
+        FfiStampSettings {
+            tip: s.tip.into(),
+            spacing: s.spacing,
+            size_jitter: s.size_jitter,
+            rotation_jitter: s.rotation_jitter,
+            scatter: s.scatter,
+            follow_direction: s.follow_direction,
+            flow: s.flow,
+        }
+    }
+}
+
+impl From<FfiLineCap> for LineCap {
+    fn from(cap: FfiLineCap) -> Self {
+        match cap {
+            FfiLineCap::Butt => LineCap::Butt,
+            FfiLineCap::Round => LineCap::Round,
+            FfiLineCap::Square => LineCap::Square,
+        }
+    }
+}
+
+impl From<LineCap> for FfiLineCap {
+    fn from(cap: LineCap) -> Self {
+        match cap {
+            LineCap::Butt => FfiLineCap::Butt,
+            LineCap::Round => FfiLineCap::Round,
+            LineCap::Square => FfiLineCap::Square,
+        }
+    }
+}
+
+impl From<FfiLineJoin> for LineJoin {
+    fn from(join: FfiLineJoin) -> Self {
+        match join {
+            FfiLineJoin::Miter => LineJoin::Miter,
+            FfiLineJoin::Round => LineJoin::Round,
+            FfiLineJoin::Bevel => LineJoin::Bevel,
+        }
+    }
+}
+
+impl From<LineJoin> for FfiLineJoin {
+    fn from(join: LineJoin) -> Self {
+        match join {
+            LineJoin::Miter => FfiLineJoin::Miter,
+            LineJoin::Round => FfiLineJoin::Round,
+            LineJoin::Bevel => FfiLineJoin::Bevel,
+        }
+    }
+}
+
+impl From<FfiNibSettings> for NibSettings {
+    fn from(n: FfiNibSettings) -> Self {
+        let defaults = NibSettings::default();
+        NibSettings {
+            angle: finite_or(n.angle, defaults.angle),
+            thickness: finite_or(n.thickness, defaults.thickness).clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl From<NibSettings> for FfiNibSettings {
+    fn from(n: NibSettings) -> Self {
+        FfiNibSettings {
+            angle: n.angle,
+            thickness: n.thickness,
+        }
+    }
+}
+
+impl From<Stamp> for FfiStamp {
+    fn from(s: Stamp) -> Self {
+        FfiStamp {
+            x: s.position.x,
+            y: s.position.y,
+            size: s.size,
+            rotation: s.rotation,
+            r: s.color.r,
+            g: s.color.g,
+            b: s.color.b,
+            a: s.color.a,
+            seed: s.seed,
+        }
+    }
+}
+
+fn finite_or(value: f64, fallback: f64) -> f64 {
+    if value.is_finite() {
+        value
+    } else {
+        fallback
+    }
+}
+
+pub(crate) fn pressure_curve(points: Vec<FfiPoint>) -> Result<PressureCurve, DrawEngineError> {
+    Ok(PressureCurve::new(points.into_iter().map(Into::into).collect())?)
+}
+
+impl From<ValidationIssue> for FfiValidationIssue {
+    fn from(issue: ValidationIssue) -> Self {
+        let kind = match issue.kind {
+            IssueKind::NonFiniteGeometry => FfiIssueKind::NonFiniteGeometry,
+            IssueKind::NegativeWidth => FfiIssueKind::NegativeWidth,
+            IssueKind::ColorOutOfRange => FfiIssueKind::ColorOutOfRange,
+            IssueKind::StaleBoundingBox => FfiIssueKind::StaleBoundingBox,
+            IssueKind::DuplicateElementId => FfiIssueKind::DuplicateElementId,
+            IssueKind::DuplicateLayerId => FfiIssueKind::DuplicateLayerId,
+            IssueKind::DuplicatePageId => FfiIssueKind::DuplicatePageId,
+            IssueKind::InvalidTemplate => FfiIssueKind::InvalidTemplate,
+            IssueKind::InvalidLayerOpacity => FfiIssueKind::InvalidLayerOpacity,
+            IssueKind::InvalidBrush => FfiIssueKind::InvalidBrush,
+        };
+        FfiValidationIssue {
+            kind,
+            page_id: issue.page_id.map(|id| id.to_string()),
+            layer_id: issue.layer_id.map(|id| id.to_string()),
+            element_id: issue.element_id.map(|id| id.to_string()),
+        }
+    }
+}
+
+impl From<RecoveryReport> for FfiRecoveryReport {
+    fn from(r: RecoveryReport) -> Self {
+        FfiRecoveryReport {
+            applied: r.applied as u32,
+            skipped: r.skipped as u32,
+            corrupt_lines: r.corrupt_lines as u32,
+            repaired_issues: r.repair.issues.into_iter().map(Into::into).collect(),
+            missing_image_ids: r.missing_images.iter().map(Uuid::to_string).collect(),
+        }
+    }
+}
+
+impl From<FfiDocumentMetadata> for DocumentMetadata {
+    fn from(m: FfiDocumentMetadata) -> Self {
+        DocumentMetadata {
+            title: m.title,
+            created_at: m.created_at,
+            modified_at: m.modified_at,
+            tags: m.tags,
+            app_version: m.app_version,
+        }
+    }
+}
+
+impl From<DocumentMetadata> for FfiDocumentMetadata {
+    fn from(m: DocumentMetadata) -> Self {
+        FfiDocumentMetadata {
+            title: m.title,
+            created_at: m.created_at,
+            modified_at: m.modified_at,
+            tags: m.tags,
+            app_version: m.app_version,
+        }
+    }
+}
+
+impl From<FfiPageLayout> for PageLayout {
+    fn from(layout: FfiPageLayout) -> Self {
+        match layout {
+            FfiPageLayout::Single => PageLayout::Single,
+            FfiPageLayout::ContinuousVertical { gap, background } => {
+                PageLayout::ContinuousVertical {
+                    gap,
+                    background: background.into(),
+                }
+            }
+        }
+    }
+}
+
+impl From<FfiPageTemplate> for PageTemplate {
+    fn from(t: FfiPageTemplate) -> Self {
+        match t {
+            FfiPageTemplate::Blank => PageTemplate::Blank,
+            FfiPageTemplate::Ruled {
+                spacing,
+                top_margin,
+                margin_x,
+                line_color,
+                margin_color,
+                line_width,
+            } => PageTemplate::Ruled {
+                spacing,
+                top_margin,
+                margin_x,
+                line_color: line_color.into(),
+                margin_color: margin_color.into(),
+                line_width,
+            },
+            FfiPageTemplate::Grid {
+                spacing,
+                line_color,
+                line_width,
+            } => PageTemplate::Grid {
+                spacing,
+                line_color: line_color.into(),
+                line_width,
+            },
+            FfiPageTemplate::DotGrid {
+                spacing,
+                dot_color,
+                dot_radius,
+            } => PageTemplate::DotGrid {
+                spacing,
+                dot_color: dot_color.into(),
+                dot_radius,
+            },
+            FfiPageTemplate::Cornell {
+                spacing,
+                header_height,
+                cue_width,
+                summary_height,
+                line_color,
+                divider_color,
+                line_width,
+            } => PageTemplate::Cornell {
+                spacing,
+                header_height,
+                cue_width,
+                summary_height,
+                line_color: line_color.into(),
+                divider_color: divider_color.into(),
+                line_width,
+            },
+            FfiPageTemplate::MusicStaff {
+                line_spacing,
+                staff_gap,
+                line_color,
+                line_width,
+            } => PageTemplate::MusicStaff {
+                line_spacing,
+                staff_gap,
+                line_color: line_color.into(),
+                line_width,
+            },
+        }
+    }
+}
+
+impl From<PageTemplate> for FfiPageTemplate {
+    fn from(t: PageTemplate) -> Self {
+        match t {
+            PageTemplate::Blank => FfiPageTemplate::Blank,
+            PageTemplate::Ruled {
+                spacing,
+                top_margin,
+                margin_x,
+                line_color,
+                margin_color,
+                line_width,
+            } => FfiPageTemplate::Ruled {
+                spacing,
+                top_margin,
+                margin_x,
+                line_color: line_color.into(),
+                margin_color: margin_color.into(),
+                line_width,
+            },
+            PageTemplate::Grid {
+                spacing,
+                line_color,
+                line_width,
+            } => FfiPageTemplate::Grid {
+                spacing,
+                line_color: line_color.into(),
+                line_width,
+            },
+            PageTemplate::DotGrid {
+                spacing,
+                dot_color,
+                dot_radius,
+            } => FfiPageTemplate::DotGrid {
+                spacing,
+                dot_color: dot_color.into(),
+                dot_radius,
+            },
+            PageTemplate::Cornell {
+                spacing,
+                header_height,
+                cue_width,
+                summary_height,
+                line_color,
+                divider_color,
+                line_width,
+            } => FfiPageTemplate::Cornell {
+                spacing,
+                header_height,
+                cue_width,
+                summary_height,
+                line_color: line_color.into(),
+                divider_color: divider_color.into(),
+                line_width,
+            },
+            PageTemplate::MusicStaff {
+                line_spacing,
+                staff_gap,
+                line_color,
+                line_width,
+            } => FfiPageTemplate::MusicStaff {
+                line_spacing,
+                staff_gap,
+                line_color: line_color.into(),
+                line_width,
+            },
+        }
+    }
+}
+
+impl From<FfiPoint> for Point {
+    fn from(p: FfiPoint) -> Self {
+        Point::new(p.x, p.y)
+    }
+}
+
+impl From<Point> for FfiPoint {
+    fn from(p: Point) -> Self {
+        FfiPoint { x: p.x, y: p.y }
+    }
+}
+
+impl From<&Page> for FfiPageInfo {
+    fn from(page: &Page) -> Self {
+        FfiPageInfo {
+            id: page.id.to_string(),
+            width: page.width,
+            height: page.height,
+            stroke_count: page.stroke_count() as u32,
+            background: page.background_color.into(),
+            template: page.template.clone().into(),
         }
     }
 }
@@ -156,6 +1580,8 @@ impl From<PathSegment> for FfiPathSegment {
             p3_y: s.p3.y,
             start_width: s.start_width,
             end_width: s.end_width,
+            start_color: s.colors.map(|(c, _)| c.into()),
+            end_color: s.colors.map(|(_, c)| c.into()),
         }
     }
 }
@@ -183,6 +1609,8 @@ pub fn convert_render_command(cmd: RenderCommand) -> FfiRenderCommand {
             segments,
             color,
             is_eraser,
+            cap,
+            join,
         } => FfiRenderCommand::DrawVariableWidthPath {
             segments: segments.into_iter().map(Into::into).collect(),
             r: color.r,
@@ -190,6 +1618,132 @@ pub fn convert_render_command(cmd: RenderCommand) -> FfiRenderCommand {
             b: color.b,
             a: color.a,
             is_eraser,
+            cap: cap.into(),
+            join: join.into(),
+        },
+        RenderCommand::DrawDashedPath {
+            dashes,
+            color,
+            cap,
+            join,
+        } => FfiRenderCommand::DrawDashedPath {
+            dashes: dashes
+                .into_iter()
+                .map(|dash| dash.into_iter().map(Into::into).collect())
+                .collect(),
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+            cap: cap.into(),
+            join: join.into(),
+        },
+        RenderCommand::DrawPrediction { segments, color } => FfiRenderCommand::DrawPrediction {
+            segments: segments.into_iter().map(Into::into).collect(),
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        },
+        RenderCommand::DrawStamps { stamps, tip } => FfiRenderCommand::DrawStamps {
+            stamps: stamps.into_iter().map(Into::into).collect(),
+            tip: tip.into(),
+        },
+        RenderCommand::FillPolygons { polygons, color } => FfiRenderCommand::FillPolygons {
+            polygons: polygons
+                .into_iter()
+                .map(|polygon| polygon.into_iter().map(Into::into).collect())
+                .collect(),
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        },
+        RenderCommand::DrawLines {
+            segments,
+            color,
+            width,
+        } => FfiRenderCommand::DrawLines {
+            lines: segments
+                .into_iter()
+                .map(|(a, b)| FfiLine {
+                    x1: a.x,
+                    y1: a.y,
+                    x2: b.x,
+                    y2: b.y,
+                })
+                .collect(),
+            width,
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        },
+        RenderCommand::DrawDots {
+            centers,
+            radius,
+            color,
+        } => FfiRenderCommand::DrawDots {
+            centers: centers.into_iter().map(Into::into).collect(),
+            radius,
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        },
+        RenderCommand::FillRect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        } => FfiRenderCommand::FillRect {
+            x,
+            y,
+            width,
+            height,
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        },
+        RenderCommand::DrawShape {
+            segments,
+            closed,
+            fill,
+            stroke,
+            stroke_width,
+            join,
+        } => FfiRenderCommand::DrawShape {
+            segments: segments.into_iter().map(Into::into).collect(),
+            closed,
+            fill: fill.map(Into::into),
+            stroke: stroke.map(Into::into),
+            stroke_width,
+            join: join.into(),
+        },
+        RenderCommand::DrawText {
+            runs,
+            font_family,
+            font_size,
+            color,
+        } => FfiRenderCommand::DrawText {
+            runs: runs.into_iter().map(Into::into).collect(),
+            font_family,
+            font_size,
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        },
+        RenderCommand::DrawImage {
+            image_id,
+            crop,
+            transform,
+        } => FfiRenderCommand::DrawImage {
+            image_id: image_id.to_string(),
+            crop: crop.into(),
+            transform: transform.into(),
         },
     }
 }
@@ -216,7 +216,11 @@ extension CanvasViewController: ToolbarViewDelegate {
 
     func toolbarDidSelectBrush(_ type: FfiBrushType, color: FfiColor, width: Double) {
         guard engine != nil else { return }
-        engine.setBrush(config: FfiBrushConfig(brushType: type, color: color, baseWidth: width))
+        do {
+            try engine.setBrush(config: FfiBrushConfig(brushType: type, color: color, baseWidth: width))
+        } catch {
+            showAlert(title: "Brush Not Set", message: error.localizedDescription)
+        }
     }
 
     func toolbarDidTapUndo() {
@@ -47,18 +47,13 @@ final class RenderCommandProcessor {
                     screenScale: screenScale
                 )
 
-            case let .drawVariableWidthPath(segments, r, g, b, a, isEraser):
-                drawPath(
-                    context: context,
-                    segments: segments,
-                    r: r, g: g, b: b, a: a,
-                    isEraser: isEraser
-                )
+            default:
+                draw(command, in: context)
             }
         }
     }
 
-    /// Process incremental render commands (only DrawVariableWidthPath).
+    /// Process incremental render commands (drawing commands only).
     /// Wraps them with the current viewport transform.
     static func processIncremental(
         commands: [FfiRenderCommand],
@@ -80,17 +75,102 @@ final class RenderCommandProcessor {
         )
 
         for command in commands {
-            if case let .drawVariableWidthPath(segments, r, g, b, a, isEraser) = command {
+            switch command {
+            case .clear, .saveState, .restoreState, .setTransform:
+                continue
+            default:
+                draw(command, in: context)
+            }
+        }
+
+        context.restoreGState()
+    }
+
+    // MARK: - Drawing Commands
+
+    /// Draws one drawing command in the current transform. State commands are handled
+    /// by the callers.
+    private static func draw(_ command: FfiRenderCommand, in context: CGContext) {
+        switch command {
+        case .clear, .saveState, .restoreState, .setTransform:
+            break
+
+        case let .drawVariableWidthPath(segments, r, g, b, a, isEraser, cap, join):
+            drawPath(
+                context: context,
+                segments: segments,
+                r: r, g: g, b: b, a: a,
+                isEraser: isEraser,
+                cap: lineCap(cap),
+                join: lineJoin(join)
+            )
+
+        case let .drawDashedPath(dashes, r, g, b, a, cap, join):
+            for dash in dashes {
                 drawPath(
                     context: context,
-                    segments: segments,
+                    segments: dash,
                     r: r, g: g, b: b, a: a,
-                    isEraser: isEraser
+                    isEraser: false,
+                    cap: lineCap(cap),
+                    join: lineJoin(join)
                 )
             }
-        }
 
-        context.restoreGState()
+        case .drawPrediction:
+            // The sample draws into a persistent bitmap, where a temporary tail would
+            // never be erased; it waits for the committed stroke instead.
+            break
+
+        case .drawStamps, .drawText, .drawImage:
+            // Stamp tips, fonts and image bytes aren't loaded by the sample app.
+            break
+
+        case let .fillPolygons(polygons, r, g, b, a):
+            context.setBlendMode(.normal)
+            context.setFillColor(cgColor(r: r, g: g, b: b, a: a))
+            context.beginPath()
+            for polygon in polygons where !polygon.isEmpty {
+                context.addLines(between: polygon.map { CGPoint(x: $0.x, y: $0.y) })
+                context.closePath()
+            }
+            context.fillPath(using: .winding)
+
+        case let .drawLines(lines, width, r, g, b, a):
+            context.setBlendMode(.normal)
+            context.setStrokeColor(cgColor(r: r, g: g, b: b, a: a))
+            context.setLineWidth(CGFloat(width))
+            context.setLineCap(.butt)
+            context.strokeLineSegments(between: lines.flatMap {
+                [CGPoint(x: $0.x1, y: $0.y1), CGPoint(x: $0.x2, y: $0.y2)]
+            })
+
+        case let .drawDots(centers, radius, r, g, b, a):
+            context.setBlendMode(.normal)
+            context.setFillColor(cgColor(r: r, g: g, b: b, a: a))
+            for center in centers {
+                context.fillEllipse(in: CGRect(
+                    x: center.x - radius, y: center.y - radius,
+                    width: radius * 2, height: radius * 2
+                ))
+            }
+
+        case let .fillRect(x, y, width, height, r, g, b, a):
+            context.setBlendMode(.normal)
+            context.setFillColor(cgColor(r: r, g: g, b: b, a: a))
+            context.fill(CGRect(x: x, y: y, width: width, height: height))
+
+        case let .drawShape(segments, closed, fill, stroke, strokeWidth, join):
+            drawShape(
+                context: context,
+                segments: segments,
+                closed: closed,
+                fill: fill,
+                stroke: stroke,
+                strokeWidth: strokeWidth,
+                join: lineJoin(join)
+            )
+        }
     }
 
     // MARK: - Transform
@@ -120,24 +200,32 @@ final class RenderCommandProcessor {
         context: CGContext,
         segments: [FfiPathSegment],
         r: Float, g: Float, b: Float, a: Float,
-        isEraser: Bool
+        isEraser: Bool,
+        cap: CGLineCap,
+        join: CGLineJoin
     ) {
-        guard !segments.isEmpty else { return }
+        guard let first = segments.first, let last = segments.last else { return }
 
+        let color = cgColor(r: r, g: g, b: b, a: a)
         context.setBlendMode(isEraser ? .clear : .normal)
-        context.setFillColor(cgColor(r: r, g: g, b: b, a: a))
+        context.setFillColor(color)
+        context.setStrokeColor(color)
 
-        // Single degenerate segment → draw a dot
+        // Single degenerate segment → draw a dot in the cap's shape
         if segments.count == 1 {
-            let seg = segments[0]
-            let dx = seg.p3X - seg.p0X
-            let dy = seg.p3Y - seg.p0Y
+            let dx = first.p3X - first.p0X
+            let dy = first.p3Y - first.p0Y
             if dx * dx + dy * dy < 0.01 {
-                let radius = seg.startWidth / 2.0
-                context.fillEllipse(in: CGRect(
-                    x: seg.p0X - radius, y: seg.p0Y - radius,
+                let radius = first.startWidth / 2.0
+                let rect = CGRect(
+                    x: first.p0X - radius, y: first.p0Y - radius,
                     width: radius * 2, height: radius * 2
-                ))
+                )
+                if cap == .round {
+                    context.fillEllipse(in: rect)
+                } else {
+                    context.fill(rect)
+                }
                 return
             }
         }
@@ -145,6 +233,125 @@ final class RenderCommandProcessor {
         for segment in segments {
             tessellateAndFill(context: context, segment: segment)
         }
+
+        // Joins: stroke a tiny corner through each joint so CoreGraphics draws the join.
+        context.setLineCap(.butt)
+        context.setLineJoin(join)
+        for (prev, next) in zip(segments, segments.dropFirst()) {
+            guard let incoming = endTangent(prev), let outgoing = startTangent(next) else {
+                continue
+            }
+            let joint = CGPoint(x: next.p0X, y: next.p0Y)
+            strokeTiny(
+                context: context,
+                points: [offset(joint, incoming, by: -1), joint, offset(joint, outgoing, by: 1)],
+                width: next.startWidth
+            )
+        }
+
+        // Caps: a tiny stroke ending at each end of the path gets the cap there.
+        context.setLineCap(cap)
+        if let dir = startTangent(first) {
+            let start = CGPoint(x: first.p0X, y: first.p0Y)
+            strokeTiny(
+                context: context,
+                points: [offset(start, dir, by: 1), start],
+                width: first.startWidth
+            )
+        }
+        if let dir = endTangent(last) {
+            let end = CGPoint(x: last.p3X, y: last.p3Y)
+            strokeTiny(
+                context: context,
+                points: [offset(end, dir, by: -1), end],
+                width: last.endWidth
+            )
+        }
+    }
+
+    /// Length of the strokes used to draw caps and joins, in canvas units.
+    private static let tinyLength = 0.01
+
+    private static func strokeTiny(context: CGContext, points: [CGPoint], width: Double) {
+        guard width > 0 else { return }
+        context.setLineWidth(CGFloat(width))
+        context.beginPath()
+        context.addLines(between: points)
+        context.strokePath()
+    }
+
+    private static func offset(_ point: CGPoint, _ dir: CGVector, by sign: Double) -> CGPoint {
+        CGPoint(x: point.x + dir.dx * sign * tinyLength, y: point.y + dir.dy * sign * tinyLength)
+    }
+
+    /// Unit direction the segment leaves its start point in, skipping coincident
+    /// control points.
+    private static func startTangent(_ seg: FfiPathSegment) -> CGVector? {
+        unit([
+            (seg.cp1X - seg.p0X, seg.cp1Y - seg.p0Y),
+            (seg.cp2X - seg.p0X, seg.cp2Y - seg.p0Y),
+            (seg.p3X - seg.p0X, seg.p3Y - seg.p0Y),
+        ])
+    }
+
+    /// Unit direction the segment arrives at its end point in.
+    private static func endTangent(_ seg: FfiPathSegment) -> CGVector? {
+        unit([
+            (seg.p3X - seg.cp2X, seg.p3Y - seg.cp2Y),
+            (seg.p3X - seg.cp1X, seg.p3Y - seg.cp1Y),
+            (seg.p3X - seg.p0X, seg.p3Y - seg.p0Y),
+        ])
+    }
+
+    private static func unit(_ candidates: [(Double, Double)]) -> CGVector? {
+        for (dx, dy) in candidates {
+            let len = sqrt(dx * dx + dy * dy)
+            if len > 1e-10 {
+                return CGVector(dx: dx / len, dy: dy / len)
+            }
+        }
+        return nil
+    }
+
+    /// Fills and strokes a uniform-width shape outline with round ends.
+    private static func drawShape(
+        context: CGContext,
+        segments: [FfiPathSegment],
+        closed: Bool,
+        fill: FfiColor?,
+        stroke: FfiColor?,
+        strokeWidth: Double,
+        join: CGLineJoin
+    ) {
+        guard let first = segments.first else { return }
+
+        let path = CGMutablePath()
+        path.move(to: CGPoint(x: first.p0X, y: first.p0Y))
+        for seg in segments {
+            path.addCurve(
+                to: CGPoint(x: seg.p3X, y: seg.p3Y),
+                control1: CGPoint(x: seg.cp1X, y: seg.cp1Y),
+                control2: CGPoint(x: seg.cp2X, y: seg.cp2Y)
+            )
+        }
+        if closed {
+            path.closeSubpath()
+        }
+
+        context.setBlendMode(.normal)
+        if closed, let fill {
+            context.setFillColor(cgColor(r: fill.r, g: fill.g, b: fill.b, a: fill.a))
+            context.addPath(path)
+            context.fillPath()
+        }
+        if let stroke {
+            context.setStrokeColor(cgColor(r: stroke.r, g: stroke.g, b: stroke.b, a: stroke.a))
+            context.setLineWidth(CGFloat(strokeWidth))
+            context.setLineCap(.round)
+            context.setLineJoin(join)
+            context.addPath(path)
+            context.strokePath()
+        }
     }
 
     // MARK: - Tessellation
@@ -211,16 +418,26 @@ final class RenderCommandProcessor {
         }
         context.closePath()
         context.fillPath()
-
-        // Round caps at endpoints
-        let sr = segment.startWidth / 2.0
-        let er = segment.endWidth / 2.0
-        context.fillEllipse(in: CGRect(x: p0x - sr, y: p0y - sr, width: sr * 2, height: sr * 2))
-        context.fillEllipse(in: CGRect(x: p3x - er, y: p3y - er, width: er * 2, height: er * 2))
     }
 
     // MARK: - Helpers
 
+    private static func lineCap(_ cap: FfiLineCap) -> CGLineCap {
+        switch cap {
+        case .butt: return .butt
+        case .round: return .round
+        case .square: return .square
+        }
+    }
+
+    private static func lineJoin(_ join: FfiLineJoin) -> CGLineJoin {
+        switch join {
+        case .miter: return .miter
+        case .round: return .round
+        case .bevel: return .bevel
+        }
+    }
+
     private static func cgColor(r: Float, g: Float, b: Float, a: Float) -> CGColor {
         CGColor(red: CGFloat(r), green: CGFloat(g), blue: CGFloat(b), alpha: CGFloat(a))
     }